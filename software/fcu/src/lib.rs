@@ -3,3 +3,6 @@ pub mod peripherals;
 
 #[path = "./wrapper.rs"]
 pub mod wrapper;
+
+#[path = "./storage.rs"]
+pub mod storage;
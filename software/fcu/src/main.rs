@@ -43,7 +43,7 @@ fn main() {
         let elapsed_time = cur_time - now;
         cur_time = now;
 
-        let (read_msgs, broad_ctl, broad_upd, refresh_disp) = {
+        let (read_msgs, broad_ctl, broad_upd, refresh_disp, save_cfg) = {
             let mils = elapsed_time.as_millis();
 
             (
@@ -51,6 +51,7 @@ fn main() {
                 mils > (fcu_config.ctl_poll.as_millis() as u128),
                 mils > (fcu_config.update_poll.as_millis() as u128),
                 mils > (fcu_config.display_poll.as_millis() as u128),
+                mils > (fcu_config.config_save_poll.as_millis() as u128),
             )
         };
 
@@ -61,6 +62,8 @@ fn main() {
         }
         if broad_ctl {
             controller.broadcast_ctl();
+            controller.broadcast_cruise();
+            controller.broadcast_profile();
             println!("Broadcasted Ctl")
         }
         if broad_upd {
@@ -71,5 +74,9 @@ fn main() {
             controller.update_user_display();
             println!("Update Display")
         }
+        if save_cfg {
+            controller.save_config();
+            println!("Saved Config")
+        }
     }
 }
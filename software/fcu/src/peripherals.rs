@@ -12,7 +12,10 @@ use esp_idf_hal::adc::oneshot::{AdcChannelDriver, AdcDriver};
 use esp_idf_hal::adc::ADC1;
 use esp_idf_hal::can;
 use esp_idf_hal::can::CanDriver;
-use esp_idf_hal::gpio::{Gpio12, Gpio32, Gpio33, Gpio34, Gpio36, Gpio39, Input, Output, PinDriver};
+use esp_idf_hal::gpio::{
+    Gpio12, Gpio13, Gpio14, Gpio32, Gpio33, Gpio34, Gpio36, Gpio39, Input, Output, PinDriver,
+    Pull,
+};
 use esp_idf_hal::i2c;
 use esp_idf_hal::i2c::I2cDriver;
 use esp_idf_hal::peripheral::Peripheral;
@@ -25,7 +28,6 @@ use esp_idf_sys as _;
 use lcd_i2c_rs::Lcd;
 use log::info;
 use shared::controllers::fcu::FcuState;
-use shared::messages::ids::CFG_MESG_ID;
 use shared::messages::messages::Message;
 use shared::operations::config_updater::ConfigUpdateOptions;
 use shared::utils::time::Duration;
@@ -36,6 +38,9 @@ type UPDATER_FIELD_INPUT_TYPE =
     AdcChannelDriver<'static, Gpio39, &'static AdcDriver<'static, ADC1>>;
 type UPDATER_VALUE_INPUT_TYPE =
     AdcChannelDriver<'static, Gpio34, &'static AdcDriver<'static, ADC1>>;
+type CRUISE_BUTTON_TYPE = PinDriver<'static, Gpio12, Input>;
+type WALK_BUTTON_TYPE = PinDriver<'static, Gpio13, Input>;
+type PROFILE_BUTTON_TYPE = PinDriver<'static, Gpio14, Input>;
 type CAN_TYPE = CanDriver<'static>;
 type LCD_TYPE = Lcd<'static>;
 
@@ -49,16 +54,19 @@ pub static UPDATER_FIELD_INPUT: critical_section::Mutex<RefCell<Option<UPDATER_F
     critical_section::Mutex::new(RefCell::new(None));
 pub static UPDATER_VALUE_INPUT: critical_section::Mutex<RefCell<Option<UPDATER_VALUE_INPUT_TYPE>>> =
     critical_section::Mutex::new(RefCell::new(None));
+pub static CRUISE_BUTTON: critical_section::Mutex<RefCell<Option<CRUISE_BUTTON_TYPE>>> =
+    critical_section::Mutex::new(RefCell::new(None));
+pub static WALK_BUTTON: critical_section::Mutex<RefCell<Option<WALK_BUTTON_TYPE>>> =
+    critical_section::Mutex::new(RefCell::new(None));
+pub static PROFILE_BUTTON: critical_section::Mutex<RefCell<Option<PROFILE_BUTTON_TYPE>>> =
+    critical_section::Mutex::new(RefCell::new(None));
 
 pub fn setup() {
     // Take peripherals
     let peripherals = Peripherals::take().expect("Failed to take peripherals");
 
     // Configure CAN bus
-    let filter = can::config::Filter::Standard {
-        filter: CFG_MESG_ID.as_raw(),
-        mask: 0x7FF,
-    };
+    let filter = can::config::Filter::standard_allow_all();
     let timing = can::config::Timing::B500K;
     let config = can::config::Config::new().filter(filter).timing(timing);
 
@@ -100,6 +108,18 @@ pub fn setup() {
     )
     .unwrap();
 
+    // Cruise button pulls the pin low when pressed
+    let mut cruise_button = PinDriver::input(peripherals.pins.gpio12).unwrap();
+    cruise_button.set_pull(Pull::Up).unwrap();
+
+    // Walk assist button is held for as long as the rider wants to push
+    let mut walk_button = PinDriver::input(peripherals.pins.gpio13).unwrap();
+    walk_button.set_pull(Pull::Up).unwrap();
+
+    // Profile button steps to the next ride profile on each press
+    let mut profile_button = PinDriver::input(peripherals.pins.gpio14).unwrap();
+    profile_button.set_pull(Pull::Up).unwrap();
+
     // Configure L2C Driver
     let i2c = I2cDriver::new(
         peripherals.i2c0,
@@ -126,6 +146,12 @@ pub fn setup() {
         let local_updater_value_driver: &mut Option<_> =
             &mut *UPDATER_VALUE_INPUT.borrow_ref_mut(cs);
         local_updater_value_driver.replace(updater_value_adc);
+        let local_cruise_button: &mut Option<_> = &mut *CRUISE_BUTTON.borrow_ref_mut(cs);
+        local_cruise_button.replace(cruise_button);
+        let local_walk_button: &mut Option<_> = &mut *WALK_BUTTON.borrow_ref_mut(cs);
+        local_walk_button.replace(walk_button);
+        let local_profile_button: &mut Option<_> = &mut *PROFILE_BUTTON.borrow_ref_mut(cs);
+        local_profile_button.replace(profile_button);
 
         let local_lcd: &mut Option<_> = &mut *LCD.borrow_ref_mut(cs);
         local_lcd.replace(lcd);
@@ -162,7 +188,13 @@ pub fn get_message(timeout: Duration) -> Option<Message> {
 
     if let Some(frame) = frame_res {
         if let embedded_can::Id::Standard(id) = frame.id() {
-            Message::from_bytes(id.as_raw(), frame.data())
+            match Message::from_bytes(id.as_raw(), frame.data()) {
+                Ok(msg) => Some(msg),
+                Err(err) => {
+                    println!("Dropping frame {} due to {}", id.as_raw(), err);
+                    None
+                }
+            }
         } else {
             None
         }
@@ -212,6 +244,41 @@ pub fn get_updater_val_value() -> Percentage {
     })
 }
 
+pub fn get_cruise_button() -> bool {
+    critical_section::with(|cs| {
+        let local_cruise_button: &mut Option<CRUISE_BUTTON_TYPE> =
+            &mut *CRUISE_BUTTON.borrow_ref_mut(cs);
+        if let Some(cruise_button) = local_cruise_button {
+            cruise_button.is_low()
+        } else {
+            panic!("get_cruise_button before setup")
+        }
+    })
+}
+
+pub fn get_walk_button() -> bool {
+    critical_section::with(|cs| {
+        let local_walk_button: &mut Option<WALK_BUTTON_TYPE> = &mut *WALK_BUTTON.borrow_ref_mut(cs);
+        if let Some(walk_button) = local_walk_button {
+            walk_button.is_low()
+        } else {
+            panic!("get_walk_button before setup")
+        }
+    })
+}
+
+pub fn get_profile_button() -> bool {
+    critical_section::with(|cs| {
+        let local_profile_button: &mut Option<PROFILE_BUTTON_TYPE> =
+            &mut *PROFILE_BUTTON.borrow_ref_mut(cs);
+        if let Some(profile_button) = local_profile_button {
+            profile_button.is_low()
+        } else {
+            panic!("get_profile_button before setup")
+        }
+    })
+}
+
 pub fn update_display(state: FcuState) {
     critical_section::with(|cs| {
         // `RefCell::borrow` and `RefCell::borrow_mut` are renamed to
@@ -222,13 +289,20 @@ pub fn update_display(state: FcuState) {
             lcd.set_cursor(0, 0).unwrap();
             lcd.print_str(
                 format!(
-                    "THRTL: {:03} BRAKE: {:03}",
+                    "T:{:03} B:{:03}",
                     state.throttle_req.to_int(),
                     state.brake_req.to_int()
                 )
                 .as_str(),
             )
             .unwrap();
+            lcd.set_cursor(11, 0).unwrap();
+            match state.trip.range_km {
+                Some(range_km) => lcd
+                    .print_str(format!("RNG: {:03}", range_km.clamp(0.0, 999.0) as u32).as_str())
+                    .unwrap(),
+                None => lcd.print_str("RNG: ---").unwrap(),
+            };
             lcd.set_cursor(0, 1).unwrap(); // Move to first column of the second row
             lcd.print_str(state.update.field.to_small_str()).unwrap();
 
@@ -244,7 +318,45 @@ pub fn update_display(state: FcuState) {
                 ConfigUpdateOptions::TMM(tmm) => {
                     lcd.print_str(tmm.to_small_str()).unwrap();
                 }
+                ConfigUpdateOptions::LCM(lcm) => {
+                    lcd.print_str(lcm.to_small_str()).unwrap();
+                }
+                ConfigUpdateOptions::TKP(gain)
+                | ConfigUpdateOptions::TKI(gain)
+                | ConfigUpdateOptions::TKD(gain) => {
+                    lcd.print_str(format!("{:.2}", gain).as_str()).unwrap();
+                }
+            };
+
+            lcd.set_cursor(11, 1).unwrap();
+            match state.battery.soc {
+                Some(soc) => lcd
+                    .print_str(format!("SOC: {:03}", soc.to_int()).as_str())
+                    .unwrap(),
+                None => lcd.print_str("SOC: ---").unwrap(),
             };
+
+            lcd.set_cursor(0, 2).unwrap();
+            lcd.print_str(
+                format!(
+                    "MCU: {} DRT: {:03}",
+                    state.mcu_state.map_or("----", |mcu_state| mcu_state.to_small_str()),
+                    state.derate.to_int()
+                )
+                .as_str(),
+            )
+            .unwrap();
+
+            lcd.set_cursor(0, 3).unwrap();
+            match state.cruise_target {
+                Some(target) => lcd
+                    .print_str(format!("CRZ: {:03}", target.kph() as u32).as_str())
+                    .unwrap(),
+                None => lcd.print_str("CRZ: OFF").unwrap(),
+            };
+            lcd.set_cursor(11, 3).unwrap();
+            lcd.print_str(format!("PRF: {}", state.profile.to_small_str()).as_str())
+                .unwrap();
         } else {
             panic!("update_display before setup")
         }
@@ -0,0 +1,31 @@
+use esp_idf_svc::nvs::{EspDefaultNvsPartition, EspNvs, NvsDefault};
+use esp_idf_svc::sys::EspError;
+use shared::storage::storage::{Storage, StorageKey};
+
+const NAMESPACE: &str = "ebike";
+
+// Records kept as raw blobs in the default NVS partition, NVS handles wear levelling itself
+pub struct NvsStorage {
+    nvs: EspNvs<NvsDefault>,
+}
+
+impl NvsStorage {
+    pub fn new() -> Result<Self, EspError> {
+        let partition = EspDefaultNvsPartition::take()?;
+        let nvs = EspNvs::new(partition, NAMESPACE, true)?;
+        Ok(Self { nvs })
+    }
+}
+
+impl Storage for NvsStorage {
+    type Error = EspError;
+
+    fn read(&mut self, key: StorageKey, buf: &mut [u8]) -> Result<Option<usize>, Self::Error> {
+        Ok(self.nvs.get_raw(key.name(), buf)?.map(|data| data.len()))
+    }
+
+    fn write(&mut self, key: StorageKey, data: &[u8]) -> Result<(), Self::Error> {
+        self.nvs.set_raw(key.name(), data)?;
+        Ok(())
+    }
+}
@@ -1,10 +1,14 @@
 // filepath: /esp32-led-control/esp32-led-control/src/main.rs
 use crate::peripherals::broadcast_message;
+use crate::peripherals::get_cruise_button;
 use crate::peripherals::get_message;
+use crate::peripherals::get_profile_button;
 use crate::peripherals::get_ti_value;
 use crate::peripherals::get_updater_field_value;
 use crate::peripherals::get_updater_val_value;
+use crate::peripherals::get_walk_button;
 use crate::peripherals::update_display;
+use crate::storage::NvsStorage;
 use embedded_can::nb::Can;
 use embedded_can::Frame;
 use embedded_can::StandardId;
@@ -18,6 +22,7 @@ use esp_idf_svc::sys::link_patches;
 use esp_idf_sys as _;
 use log::info;
 use shared::config::config::Config;
+use shared::config::store::ConfigStore;
 use shared::controllers::fcu::FcuConfig;
 use shared::controllers::fcu::FcuController;
 use shared::messages::messages::Message;
@@ -29,14 +34,45 @@ use std::time::Instant;
 
 pub struct FcuWrapperController {
     pub controller: FcuController,
+    cruise_pressed: bool,
+    profile_pressed: bool,
+    start: Instant,
+    storage: Option<NvsStorage>,
 }
 
 impl FcuWrapperController {
     pub fn new() -> Self {
-        let config = Config::default();
-        let controller = FcuController::new(config);
+        // keep riding without persistence if NVS can't be opened
+        let mut storage = match NvsStorage::new() {
+            Ok(storage) => Some(storage),
+            Err(err) => {
+                println!("Running without storage due to {:?}", err);
+                None
+            }
+        };
+
+        let config = match storage.as_mut().map(|storage| storage.load()) {
+            Some(Ok(Some(config))) => config,
+            Some(Err(err)) => {
+                println!("Using default config due to {:?}", err);
+                Config::default()
+            }
+            _ => Config::default(),
+        };
+        let mut controller = FcuController::new(config);
+        if let Some(storage) = &mut storage {
+            if let Err(err) = controller.load_odometer(storage) {
+                println!("Starting odometer from zero due to {:?}", err);
+            }
+        }
 
-        Self { controller }
+        Self {
+            controller,
+            cruise_pressed: false,
+            profile_pressed: false,
+            start: Instant::now(),
+            storage,
+        }
     }
 
     pub fn get_config(&self) -> FcuConfig {
@@ -48,7 +84,9 @@ impl FcuWrapperController {
         loop {
             if let Some(msg) = get_message(Duration::from_millis(1)) {
                 println!("Procesing Messages {:?}", msg);
-                self.controller.process_message(msg);
+                if let Err(err) = self.controller.process_message(msg) {
+                    println!("Dropping message {:?} due to {}", msg, err);
+                }
                 count += 1;
                 if count > 10 {
                     break;
@@ -64,8 +102,27 @@ impl FcuWrapperController {
         let tc_val = get_ti_value();
         // let break_val = get_ti_value();
         let break_val = Percentage::zero();
+        let walk_val = get_walk_button();
 
-        broadcast_message(self.controller.broadcast_ctl(tc_val, break_val));
+        broadcast_message(self.controller.broadcast_ctl(tc_val, break_val, walk_val));
+    }
+
+    pub fn broadcast_cruise(&mut self) {
+        // only send on the press, not while the button is held
+        let pressed = get_cruise_button();
+        if pressed && !self.cruise_pressed {
+            broadcast_message(self.controller.cruise_button());
+        }
+        self.cruise_pressed = pressed;
+    }
+
+    pub fn broadcast_profile(&mut self) {
+        // only send on the press, not while the button is held
+        let pressed = get_profile_button();
+        if pressed && !self.profile_pressed {
+            broadcast_message(self.controller.profile_button());
+        }
+        self.profile_pressed = pressed;
     }
 
     pub fn broadcast_upload(&mut self) {
@@ -80,7 +137,27 @@ impl FcuWrapperController {
         }
     }
 
+    pub fn save_config(&mut self) {
+        if let (Some(storage), Some(config)) =
+            (&mut self.storage, self.controller.take_changed_config())
+        {
+            if let Err(err) = storage.save(&config) {
+                println!("Failed to save config due to {:?}", err);
+            }
+        }
+    }
+
+    fn timestamp(&self) -> Timestamp {
+        Timestamp::from_micros(self.start.elapsed().as_micros() as u64)
+    }
+
     pub fn update_user_display(&mut self) {
+        self.controller.run_telemetry(self.timestamp());
+        if let Some(storage) = &mut self.storage {
+            if let Err(err) = self.controller.save_odometer(storage) {
+                println!("Failed to save odometer due to {:?}", err);
+            }
+        }
         let state = self.controller.update_user_display();
         update_display(state);
     }
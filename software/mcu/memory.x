@@ -1,5 +1,5 @@
 MEMORY
 {
-  FLASH : ORIGIN = 0x08000000, LENGTH = 2048K
+  FLASH : ORIGIN = 0x08000000, LENGTH = 1792K /* top 256K holds the saved config */
   RAM : ORIGIN = 0x10000000, LENGTH = 64K
 }
@@ -0,0 +1,51 @@
+use embedded_storage::nor_flash::{ErrorType, NorFlash, ReadNorFlash};
+use stm32f4xx_hal::flash::{Error, FlashExt, LockedFlash};
+use stm32f4xx_hal::pac::FLASH;
+
+// last two 128K sectors of bank 2, kept out of the image by memory.x. The 2M parts always
+// run dual bank so erasing here doesn't stall code running from bank 1
+pub const CONFIG_FLASH_START: u32 = 0x1C_0000;
+pub const CONFIG_FLASH_PAGES: u32 = 2;
+
+// Owns the flash and only unlocks it for the length of each erase or write
+pub struct McuFlash {
+    flash: LockedFlash,
+}
+
+impl McuFlash {
+    pub fn new(flash: FLASH) -> Self {
+        McuFlash {
+            flash: LockedFlash::new(flash),
+        }
+    }
+}
+
+impl ErrorType for McuFlash {
+    type Error = Error;
+}
+
+impl ReadNorFlash for McuFlash {
+    const READ_SIZE: usize = <LockedFlash as ReadNorFlash>::READ_SIZE;
+
+    fn read(&mut self, offset: u32, bytes: &mut [u8]) -> Result<(), Self::Error> {
+        ReadNorFlash::read(&mut self.flash, offset, bytes)
+    }
+
+    fn capacity(&self) -> usize {
+        ReadNorFlash::capacity(&self.flash)
+    }
+}
+
+impl NorFlash for McuFlash {
+    const WRITE_SIZE: usize = 1;
+    const ERASE_SIZE: usize = 128 * 1024;
+
+    fn erase(&mut self, from: u32, to: u32) -> Result<(), Self::Error> {
+        // the inherent erase on UnlockedFlash takes a sector number, not a range
+        NorFlash::erase(&mut self.flash.unlocked(), from, to)
+    }
+
+    fn write(&mut self, offset: u32, bytes: &[u8]) -> Result<(), Self::Error> {
+        NorFlash::write(&mut self.flash.unlocked(), offset, bytes)
+    }
+}
@@ -30,3 +30,6 @@ pub fn exit() -> ! {
         cortex_m::asm::bkpt();
     }
 }
+
+#[path = "./flash.rs"]
+pub mod flash;
@@ -13,8 +13,11 @@ use stm32f4xx_hal::{
 #[app(device = stm32f4xx_hal::pac, peripherals = true)]
 mod app {
     use bxcan::{Can as HalCan, Frame, Id, Mailbox, Rx0, Rx1, StandardId, Tx};
-    use defmt::{debug, println};
+    use defmt::{Debug2Format, debug, println};
+    use mcu::flash::{CONFIG_FLASH_PAGES, CONFIG_FLASH_START, McuFlash};
     use shared::config::config::Config;
+    use shared::config::flash_store::FlashConfigStore;
+    use shared::config::store::ConfigStore;
     use shared::messages::messages::Message;
     use shared::utils::time::Timestamp;
     use stm32f4xx_hal::can::Can;
@@ -40,6 +43,7 @@ mod app {
     struct Local {
         can_rx_0: Rx0<Can<CAN1>>,
         can_rx_1: Rx1<Can<CAN1>>,
+        config_store: Option<FlashConfigStore<McuFlash>>,
     }
 
     #[init]
@@ -60,13 +64,38 @@ mod app {
         let hal_can: HalCan<Can<CAN1>> = HalCan::builder(can).set_bit_timing(0x001c_0000).enable();
         let (can_tx, can_rx_0, can_rx_1) = hal_can.split();
 
+        println!("init config");
+        // a bad layout just runs on the defaults without persistence rather than not booting
+        let mut config_store = FlashConfigStore::new(
+            McuFlash::new(dp.FLASH),
+            CONFIG_FLASH_START,
+            CONFIG_FLASH_PAGES,
+        );
+        let config = match config_store.as_mut().map(|store| store.load()) {
+            Some(Ok(Some(config))) => config,
+            Some(Ok(None)) => Config::default(),
+            Some(Err(err)) => {
+                println!("Using default config due to {}", Debug2Format(&err));
+                Config::default()
+            }
+            None => {
+                println!("Using default config due to bad flash layout, not saving");
+                Config::default()
+            }
+        };
+
         println!("init controller");
-        let mut controller = McuController::new(Config::default());
+        let mut controller = McuController::new(config);
 
         println!("init tasks");
         broadcast_ecu::spawn().unwrap();
+        broadcast_regen::spawn().unwrap();
+        broadcast_abs::spawn().unwrap();
         run_engine_subsystem::spawn().unwrap();
         process_messages::spawn().unwrap();
+        if config_store.is_some() {
+            save_config::spawn().unwrap();
+        }
 
         (
             Shared {
@@ -74,7 +103,11 @@ mod app {
                 can_tx,
                 prev_ecu_mailbox: None,
             },
-            Local { can_rx_0, can_rx_1 },
+            Local {
+                can_rx_0,
+                can_rx_1,
+                config_store,
+            },
         )
     }
 
@@ -120,6 +153,44 @@ mod app {
         }
     }
 
+    #[task(shared = [controller, can_tx])]
+    async fn broadcast_abs(mut cx: broadcast_abs::Context) {
+        loop {
+            let (sleep_time, msg) = cx.shared.controller.lock(|ctl| {
+                let msg = ctl.broadcast_abs();
+                (ctl.config.mcu.abs_poll, msg)
+            });
+
+            cx.shared.can_tx.lock(|cn| {
+                let frame = Frame::new_data(StandardId::new(msg.to_id()).unwrap(), msg.to_bytes());
+
+                println!("Sending msg: {}", msg);
+                let _ = cn.transmit(&frame);
+            });
+
+            Mono::delay((sleep_time.as_millis() as u32).millis()).await;
+        }
+    }
+
+    #[task(shared = [controller, can_tx])]
+    async fn broadcast_regen(mut cx: broadcast_regen::Context) {
+        loop {
+            let (sleep_time, msg) = cx.shared.controller.lock(|ctl| {
+                let msg = ctl.broadcast_regen();
+                (ctl.config.mcu.regen_poll, msg)
+            });
+
+            cx.shared.can_tx.lock(|cn| {
+                let frame = Frame::new_data(StandardId::new(msg.to_id()).unwrap(), msg.to_bytes());
+
+                println!("Sending msg: {}", msg);
+                let _ = cn.transmit(&frame);
+            });
+
+            Mono::delay((sleep_time.as_millis() as u32).millis()).await;
+        }
+    }
+
     #[task(shared = [controller, can_tx])]
     async fn broadcast_config(mut cx: broadcast_config::Context) {
         loop {
@@ -153,6 +224,30 @@ mod app {
         }
     }
 
+    // a sector erase takes a second or two, so this runs below every other task and they
+    // preempt it for the length of the erase. The controller is only locked to copy the
+    // config out, not for the flash write
+    #[task(priority = 0, shared = [controller], local = [config_store])]
+    async fn save_config(mut cx: save_config::Context) {
+        let Some(config_store) = cx.local.config_store else {
+            return;
+        };
+        loop {
+            let (sleep_time, config) = cx
+                .shared
+                .controller
+                .lock(|ctl| (ctl.config.mcu.config_save_poll, ctl.take_changed_config()));
+
+            if let Some(config) = config {
+                if let Err(err) = config_store.save(&config) {
+                    println!("Failed to save config due to {}", Debug2Format(&err));
+                }
+            }
+
+            Mono::delay((sleep_time.as_millis() as u32).millis()).await;
+        }
+    }
+
     #[task(shared = [controller], local=[can_rx_0, can_rx_1])]
     async fn process_messages(mut cx: process_messages::Context) {
         loop {
@@ -186,12 +281,29 @@ mod app {
                     continue;
                 };
 
-                let parsed_msg = Message::from_bytes(raw_id, msg.data().unwrap());
-                if let Some(parsed_msg) = parsed_msg {
-                    println!("Processing Message: ");
-                    cx.shared.controller.lock(|ctl| {
-                        ctl.process_message(parsed_msg);
-                    });
+                let data = if let Some(data) = msg.data() {
+                    data
+                } else {
+                    continue;
+                };
+
+                match Message::from_bytes(raw_id, data) {
+                    Ok(parsed_msg) => {
+                        println!("Processing Message: ");
+                        let res = cx.shared.controller.lock(|ctl| {
+                            ctl.process_message(parsed_msg)
+                                .map_err(|err| (err, ctl.ctl_e2e_stats()))
+                        });
+                        if let Err((err, stats)) = res {
+                            println!(
+                                "Dropping message {} due to {}, control e2e {}",
+                                raw_id, err, stats
+                            );
+                        }
+                    }
+                    Err(err) => {
+                        println!("Dropping frame {} due to {}", raw_id, err);
+                    }
                 }
             } else {
                 Mono::delay((10).millis()).await;
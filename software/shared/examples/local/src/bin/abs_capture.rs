@@ -0,0 +1,207 @@
+// Headless run of the 2D vehicle sim braking the rear wheel with the ABS in the loop. Prints
+// the trace as csv, wheel speeds as the sensors report them with noise and logged to 3
+// decimals, the slips are the sim's own noise free values.
+//
+//     cargo run --release --bin abs_capture -- <friction> <brake> <max torque Nm> <seconds>
+
+use rapier2d::prelude::*;
+use shared::subsystems::mcu::abs::{AbsConfig, AbsRequest, AbsSubsystem};
+use shared::subsystems::shared::Subsystem;
+use shared::utils::{percentage::Percentage, speed::WheelSpeed, time::Timestamp};
+
+const WHEEL_RADIUS: f32 = 0.35;
+const START_SPEED: f32 = 8.0;
+const SAMPLE_MS: u64 = 20;
+// samples rolling freely before the brake goes on
+const SETTLE_SAMPLES: u64 = 10;
+// lag between the brake command and torque at the wheel
+const BRAKE_LAG_S: f32 = 0.06;
+const SENSOR_NOISE: f32 = 0.12;
+
+// seeded so the capture can be reproduced, Box-Muller over an LCG
+struct Noise(u64);
+
+impl Noise {
+    fn uniform(&mut self) -> f32 {
+        self.0 = self
+            .0
+            .wrapping_mul(6364136223846793005)
+            .wrapping_add(1442695040888963407);
+        ((self.0 >> 40) as f32 + 0.5) / (1u64 << 24) as f32
+    }
+
+    fn gauss(&mut self, sigma: f32) -> f32 {
+        let (u1, u2) = (self.uniform(), self.uniform());
+        (-2.0 * u1.ln()).sqrt() * (2.0 * std::f32::consts::PI * u2).cos() * sigma
+    }
+}
+
+fn arg(args: &[String], idx: usize, default: f32) -> f32 {
+    args.get(idx).map_or(default, |arg| arg.parse().unwrap())
+}
+
+fn main() {
+    let args: Vec<String> = std::env::args().collect();
+    let friction = arg(&args, 1, 0.2);
+    let brake = arg(&args, 2, 0.8);
+    let max_torque = arg(&args, 3, 110.0);
+    let duration = arg(&args, 4, 1.4);
+
+    let mut bodies = RigidBodySet::new();
+    let mut colliders = ColliderSet::new();
+    let mut impulse_joints = ImpulseJointSet::new();
+    let mut multibody_joints = MultibodyJointSet::new();
+
+    // flat road
+    colliders.insert(
+        ColliderBuilder::cuboid(500.0, 0.5)
+            .translation(vector![450.0, -0.5])
+            .friction(friction),
+    );
+
+    // same layout as the veh_2d sim, sized for a bike and rider
+    const CAR_GROUP: Group = Group::GROUP_1;
+    let suspension_height = 0.12;
+    let wheel_params = [vector![-0.55, 0.2783], vector![0.55, 0.2783]];
+    let car_position = point![0.0, WHEEL_RADIUS + suspension_height];
+    let body_position_in_car_space = vector![0.0, 0.4739];
+    let body_position = car_position + body_position_in_car_space;
+
+    let body_co = ColliderBuilder::cuboid(0.6, 0.2)
+        .mass(95.0)
+        .collision_groups(InteractionGroups::new(
+            CAR_GROUP,
+            !CAR_GROUP,
+            InteractionTestMode::And,
+        ));
+    let body_rb = RigidBodyBuilder::dynamic()
+        .pose(Isometry::new(body_position.coords, 0.0))
+        .linvel(vector![START_SPEED, 0.0])
+        .build();
+    let body_handle = bodies.insert(body_rb);
+    colliders.insert_with_parent(body_co, body_handle, &mut bodies);
+
+    // rear first
+    let mut wheels = vec![];
+    let mut wheel_joints = vec![];
+    for wheel_pos_in_car_space in wheel_params {
+        let wheel_center = car_position + wheel_pos_in_car_space;
+
+        let axle_rb = RigidBodyBuilder::dynamic()
+            .pose(Isometry::new(wheel_center.coords, 0.0))
+            .linvel(vector![START_SPEED, 0.0])
+            .additional_mass_properties(MassProperties::from_ball(1.0, WHEEL_RADIUS));
+        let axle_handle = bodies.insert(axle_rb);
+
+        let wheel_co = ColliderBuilder::ball(WHEEL_RADIUS)
+            .mass(2.5)
+            .collision_groups(InteractionGroups::new(
+                CAR_GROUP,
+                !CAR_GROUP,
+                InteractionTestMode::And,
+            ))
+            .friction(1.0);
+        let wheel_rb = RigidBodyBuilder::dynamic()
+            .pose(Isometry::new(wheel_center.coords, 0.0))
+            .linvel(vector![START_SPEED, 0.0])
+            .angvel(-START_SPEED / WHEEL_RADIUS);
+        let wheel_handle = bodies.insert(wheel_rb);
+        colliders.insert_with_parent(wheel_co, wheel_handle, &mut bodies);
+
+        let suspension_attachment_in_body_space =
+            wheel_pos_in_car_space - body_position_in_car_space;
+        let suspension_joint =
+            GenericJointBuilder::new(JointAxesMask::LIN_X | JointAxesMask::ANG_X)
+                .limits(JointAxis::LinY, [0.0, suspension_height])
+                .motor_position(JointAxis::LinY, 0.0, 1.0e4, 1.0e3)
+                .local_anchor1(suspension_attachment_in_body_space.into());
+        impulse_joints.insert(body_handle, axle_handle, suspension_joint, true);
+
+        let wheel_joint = RevoluteJointBuilder::new();
+        wheel_joints.push(impulse_joints.insert(axle_handle, wheel_handle, wheel_joint, true));
+        wheels.push(wheel_handle);
+    }
+
+    let gravity = vector![0.0, -9.81];
+    let params = IntegrationParameters {
+        dt: 1.0 / 1000.0,
+        ..IntegrationParameters::default()
+    };
+    let mut pipeline = PhysicsPipeline::new();
+    let mut islands = IslandManager::new();
+    let mut broad_phase = DefaultBroadPhase::new();
+    let mut narrow_phase = NarrowPhase::new();
+    let mut ccd = CCDSolver::new();
+
+    let mut abs = AbsSubsystem::new(AbsConfig::default());
+    let mut noise = Noise(0x5eed_abcd);
+    let mut brake_cmd = 0.0f32;
+    let mut torque = 0.0f32;
+    let steps_per_sample = (SAMPLE_MS as f32 / 1000.0 / params.dt).round() as u64;
+    let samples = (duration * 1000.0) as u64 / SAMPLE_MS;
+
+    println!("time,v_chassis,omega_rear,omega_front,slip_rear,slip_front");
+    for sample in 0..(SETTLE_SAMPLES + samples) {
+        for _ in 0..steps_per_sample {
+            // the rear brake is a torque limited hold on the wheel joint
+            let target = if sample >= SETTLE_SAMPLES {
+                brake_cmd * max_torque
+            } else {
+                0.0
+            };
+            torque += (target - torque) * params.dt / BRAKE_LAG_S;
+            let rear = impulse_joints.get_mut(wheel_joints[0], true).unwrap();
+            rear.data.set_motor_velocity(JointAxis::AngX, 0.0, 1.0e3);
+            rear.data.set_motor_max_force(JointAxis::AngX, torque);
+            pipeline.step(
+                &gravity,
+                &params,
+                &mut islands,
+                &mut broad_phase,
+                &mut narrow_phase,
+                &mut bodies,
+                &mut colliders,
+                &mut impulse_joints,
+                &mut multibody_joints,
+                &mut ccd,
+                &(),
+                &(),
+            );
+        }
+        if sample < SETTLE_SAMPLES {
+            continue;
+        }
+
+        let time = (sample - SETTLE_SAMPLES) as f32 * SAMPLE_MS as f32 / 1000.0;
+        let v_chassis = bodies[body_handle].linvel().x;
+        // wheels roll clockwise going forward
+        let omega_rear = -bodies[wheels[0]].angvel();
+        let omega_front = -bodies[wheels[1]].angvel();
+        let slip = |omega: f32| (1.0 - omega * WHEEL_RADIUS / v_chassis).clamp(-1.0, 1.0);
+
+        // a pulse counter never reads backwards
+        let logged = |value: f32| (value * 1000.0).round() / 1000.0;
+        let rear_sensed = logged((omega_rear + noise.gauss(SENSOR_NOISE)).max(0.0));
+        let front_sensed = logged((omega_front + noise.gauss(SENSOR_NOISE)).max(0.0));
+        let v_sensed = logged(v_chassis + noise.gauss(0.03));
+        println!(
+            "{:.3},{:.3},{:.3},{:.3},{:.3},{:.3}",
+            time,
+            v_sensed,
+            rear_sensed,
+            front_sensed,
+            slip(omega_rear),
+            slip(omega_front)
+        );
+
+        let to_rpm = |omega: f32| WheelSpeed::from(omega * 60.0 / std::f32::consts::TAU);
+        let resp = abs.run(AbsRequest {
+            rear_ws: Some(to_rpm(rear_sensed)),
+            front_ws: Some(to_rpm(front_sensed)),
+            brake_req: Percentage::from_fractional(brake),
+            longitudinal_accel: None,
+            timestamp: Timestamp::from_micros((time * 1_000_000.0).round() as u64),
+        });
+        brake_cmd = resp.brake_cmd.to_fractional();
+    }
+}
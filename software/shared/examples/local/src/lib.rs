@@ -6,3 +6,6 @@ pub mod simulation;
 
 #[path = "./ui.rs"]
 pub mod ui;
+
+#[path = "./storage.rs"]
+pub mod storage;
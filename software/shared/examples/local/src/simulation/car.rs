@@ -1,4 +1,5 @@
 use shared::{
+    controllers::fcu::FcuState,
     messages::messages::{Message, tire_status::TireStatus},
     utils::speed::WheelSpeed,
 };
@@ -10,6 +11,8 @@ pub struct CarState {
     pub ecu: EcuState,
     pub front_wheel: TireStatus,
     pub rear_wheel: TireStatus,
+    // what the FCU would be showing on its display
+    pub fcu: FcuState,
 }
 
 impl Default for CarState {
@@ -18,6 +21,7 @@ impl Default for CarState {
             ecu: EcuState::default(),
             front_wheel: TireStatus::new(shared::utils::parts::Wheel::Front, WheelSpeed::zero()),
             rear_wheel: TireStatus::new(shared::utils::parts::Wheel::Rear, WheelSpeed::zero()),
+            fcu: FcuState::default(),
         }
     }
 }
@@ -27,6 +31,10 @@ impl CarState {
         match msg {
             Message::EcuMessage(msg) => {
                 self.ecu.throttle = msg.throttle;
+                self.ecu.state = msg.state;
+                self.ecu.cruise_target = msg.cruise_target;
+                self.ecu.derate = msg.derate;
+                self.ecu.limit_reason = msg.limit_reason;
             }
             _ => {}
         }
@@ -1,14 +1,26 @@
-use shared::utils::percentage::Percentage;
+use shared::{
+    controllers::mcu::McuOperatingState,
+    operations::power_limiter::PowerLimitReason,
+    utils::{percentage::Percentage, speed::GroundSpeed},
+};
 
 #[derive(Debug, Clone, Copy)]
 pub struct EcuState {
     pub throttle: Percentage,
+    pub state: McuOperatingState,
+    pub cruise_target: Option<GroundSpeed>,
+    pub derate: Percentage,
+    pub limit_reason: PowerLimitReason,
 }
 
 impl Default for EcuState {
     fn default() -> Self {
         Self {
             throttle: Percentage::zero(),
+            state: McuOperatingState::Init,
+            cruise_target: None,
+            derate: Percentage::zero(),
+            limit_reason: PowerLimitReason::None(),
         }
     }
 }
@@ -0,0 +1,90 @@
+use std::{collections::HashMap, fs, io, path::PathBuf};
+
+use shared::{
+    config::{config::Config, store::ConfigStore},
+    storage::storage::{Storage, StorageKey},
+};
+
+// Keeps records in memory and, when given a directory, mirrors each key to its own
+// file so the simulator keeps its state between runs
+pub struct LocalStorage {
+    records: HashMap<StorageKey, Vec<u8>>,
+    dir: Option<PathBuf>,
+}
+
+impl LocalStorage {
+    pub fn in_memory() -> Self {
+        LocalStorage {
+            records: HashMap::new(),
+            dir: None,
+        }
+    }
+
+    pub fn in_dir(dir: impl Into<PathBuf>) -> io::Result<Self> {
+        let dir = dir.into();
+        fs::create_dir_all(&dir)?;
+        Ok(LocalStorage {
+            records: HashMap::new(),
+            dir: Some(dir),
+        })
+    }
+
+    // fall back to memory so the simulator still runs from a read-only directory
+    pub fn open(dir: impl Into<PathBuf>) -> Self {
+        LocalStorage::in_dir(dir).unwrap_or_else(|err| {
+            eprintln!("Keeping storage in memory due to {}", err);
+            LocalStorage::in_memory()
+        })
+    }
+
+    // the saved config if there is one, otherwise the defaults passed in
+    pub fn load_config(&mut self, default: Config) -> Config {
+        match self.load() {
+            Ok(Some(config)) => config,
+            Ok(None) => default,
+            Err(err) => {
+                eprintln!("Using default config due to {:?}", err);
+                default
+            }
+        }
+    }
+
+    fn path(&self, key: StorageKey) -> Option<PathBuf> {
+        self.dir
+            .as_ref()
+            .map(|dir| dir.join(format!("{}.bin", key.name())))
+    }
+}
+
+impl Storage for LocalStorage {
+    type Error = io::Error;
+
+    fn read(&mut self, key: StorageKey, buf: &mut [u8]) -> Result<Option<usize>, Self::Error> {
+        if !self.records.contains_key(&key) {
+            match self.path(key).map(fs::read) {
+                Some(Ok(data)) => {
+                    self.records.insert(key, data);
+                }
+                Some(Err(err)) if err.kind() != io::ErrorKind::NotFound => return Err(err),
+                _ => return Ok(None),
+            }
+        }
+        let data = &self.records[&key];
+        if data.len() > buf.len() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("{} record larger than the read buffer", key.name()),
+            ));
+        }
+        buf[..data.len()].copy_from_slice(data);
+        Ok(Some(data.len()))
+    }
+
+    fn write(&mut self, key: StorageKey, data: &[u8]) -> Result<(), Self::Error> {
+        if let Some(path) = self.path(key) {
+            fs::write(path, data)?;
+        }
+        self.records.insert(key, data.to_vec());
+        Ok(())
+    }
+}
@@ -13,7 +13,7 @@ use tokio::{
 use futures::{FutureExt, StreamExt};
 use shared::{
     config::config::Config,
-    controllers::mcu::McuController,
+    controllers::{fcu::FcuState, mcu::McuController},
     messages::messages::{Message, ecu::EcuMessage},
     utils::{percentage::Percentage, time::Timestamp},
 };
@@ -102,6 +102,11 @@ pub async fn get_ecu_throttle() -> Percentage {
     local_mut.ecu.throttle
 }
 
+pub async fn update_fcu_state(state: FcuState) {
+    let mut local_mut = CURRENT_CAR_STATE.get().unwrap().lock().await;
+    local_mut.fcu = state;
+}
+
 pub async fn get_car_state() -> CarState {
     let mut local_mut = CURRENT_CAR_STATE.get().unwrap().lock().await;
     local_mut.clone()
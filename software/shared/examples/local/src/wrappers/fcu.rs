@@ -10,12 +10,12 @@ use tokio::{io, sync::MutexGuard, task};
 
 use futures::{FutureExt, StreamExt};
 use shared::{
-    config::config::Config,
+    config::{config::Config, store::ConfigStore},
     controllers::{
-        fcu::{FcuController, FcuState},
+        fcu::{FcuController, FcuState, TripMeter},
         mcu::McuController,
     },
-    messages::messages::Message,
+    messages::messages::{Message, trip::TripResetMessage},
     operations::config_updater::ConfigUpdateState,
     utils::{percentage::Percentage, time::Timestamp},
 };
@@ -25,21 +25,30 @@ use tokio::sync::Mutex;
 
 use crate::{
     simulation::car::CarState,
+    storage::LocalStorage,
     wrappers::core::{
         broadcast_message, get_car_state, get_next_message, get_req_throttle, get_timestamp,
-        local_sleep, update_req_throttle,
+        local_sleep, update_fcu_state, update_req_throttle,
     },
 };
 
 pub struct LocalFcuRunner {
     controller: Mutex<FcuController>,
+    storage: Mutex<LocalStorage>,
 }
 
 impl LocalFcuRunner {
     fn new(config: Config) -> Self {
-        let controler = FcuController::new(config);
+        let mut storage = LocalStorage::open("./storage/fcu");
+        let mut controler = FcuController::new(storage.load_config(config));
+        if let Err(err) = controler.load_odometer(&mut storage) {
+            eprintln!("Starting odometer from zero due to {:?}", err);
+        }
         let controller = Mutex::from(controler);
-        LocalFcuRunner { controller }
+        LocalFcuRunner {
+            controller,
+            storage: Mutex::from(storage),
+        }
     }
 
     pub async fn broadcast_ctl(&self) {
@@ -51,7 +60,7 @@ impl LocalFcuRunner {
                 let tc_val = get_req_throttle().await;
                 let break_val = Percentage::zero();
 
-                let msg = controller.broadcast_ctl(tc_val, break_val);
+                let msg = controller.broadcast_ctl(tc_val, break_val, false);
                 (controller.config.fcu.ctl_poll, msg)
             };
             //eprintln!("{}", Into::<String>::into(msg));
@@ -78,11 +87,41 @@ impl LocalFcuRunner {
     }
 
     pub async fn update_user_display(&self) {
-        {
-            let mut controller = self.controller.lock().await;
+        loop {
+            let (sleep_time, state) = {
+                let mut controller = self.controller.lock().await;
+                controller.run_telemetry(get_timestamp());
+                let mut storage = self.storage.lock().await;
+                if let Err(err) = controller.save_odometer(&mut *storage) {
+                    eprintln!("Failed to save odometer due to {}", err);
+                }
+                (
+                    controller.config.fcu.display_poll,
+                    controller.update_user_display(),
+                )
+            };
+            update_fcu_state(state).await;
+            local_sleep(sleep_time).await
+        }
+    }
 
-            let state = controller.update_user_display();
-        };
+    pub async fn save_config(&self) {
+        loop {
+            let (sleep_time, config) = {
+                let mut controller = self.controller.lock().await;
+                (
+                    controller.config.fcu.config_save_poll,
+                    controller.take_changed_config(),
+                )
+            };
+            if let Some(config) = config {
+                let mut storage = self.storage.lock().await;
+                if let Err(err) = storage.save(&config) {
+                    eprintln!("Failed to save config due to {}", err);
+                }
+            }
+            local_sleep(sleep_time).await
+        }
     }
 
     pub async fn process_messages(&self) {
@@ -90,7 +129,9 @@ impl LocalFcuRunner {
             let msg = get_next_message().await;
             {
                 let mut controller = self.controller.lock().await;
-                controller.process_message(msg);
+                if let Err(err) = controller.process_message(msg) {
+                    eprintln!("Dropping message {:?} due to {}", msg, err);
+                }
             }
         }
     }
@@ -99,10 +140,11 @@ impl LocalFcuRunner {
         let runner = LocalFcuRunner::new(config);
 
         // Spawn a new task
-        let (_, _, _, _) = tokio::join!(
+        let (_, _, _, _, _) = tokio::join!(
             runner.broadcast_ctl(),
             runner.broadcast_upload(),
             runner.update_user_display(),
+            runner.save_config(),
             runner.process_messages(),
         );
     }
@@ -129,6 +171,61 @@ impl FcuUiComponent {
                 .show_percentage()
                 .fill(Color32::from_rgb(0, 255, 0)),
         );
+        ui.label(format!("MCU State: {}", car_state.ecu.state.to_small_str()));
+        ui.label(match car_state.ecu.cruise_target {
+            Some(target) => format!("Cruise: {:.0} kph", target.kph()),
+            None => "Cruise: OFF".to_string(),
+        });
+        ui.label(format!("Thermal Derate: {}%", car_state.ecu.derate.to_int()));
+        ui.label(format!(
+            "Power Limit: {}",
+            car_state.ecu.limit_reason.to_small_str()
+        ));
+
+        let trip = car_state.fcu.trip;
+        ui.label(match car_state.fcu.battery.soc {
+            Some(soc) => format!("SoC: {}%", soc.to_int()),
+            None => "SoC: --".to_string(),
+        });
+        ui.label(format!(
+            "Trip: {:.2} km, {:.0} Wh",
+            trip.distance_km, trip.energy_wh
+        ));
+        ui.label(match trip.wh_per_km {
+            Some(wh_per_km) => format!("Consumption: {:.1} Wh/km", wh_per_km),
+            None => "Consumption: --".to_string(),
+        });
+        ui.label(match trip.range_km {
+            Some(range_km) => format!("Range: {:.0} km", range_km),
+            None => "Range: --".to_string(),
+        });
+
+        let odometer = car_state.fcu.odometer;
+        ui.label(format!(
+            "Odometer: {:.1} km",
+            odometer.odometer_m as f32 / 1000.0
+        ));
+        ui.label(format!(
+            "Ride Time: {}:{:02}:{:02}",
+            odometer.ride_time_s / 3600,
+            odometer.ride_time_s / 60 % 60,
+            odometer.ride_time_s % 60
+        ));
+        for trip in [TripMeter::A(), TripMeter::B()] {
+            let name = if trip == TripMeter::A() { "A" } else { "B" };
+            ui.horizontal(|ui| {
+                ui.label(format!(
+                    "Trip {}: {:.1} km",
+                    name,
+                    odometer.trip_m(trip) as f32 / 1000.0
+                ));
+                if ui.button("Reset").clicked() {
+                    futures::executor::block_on(broadcast_message(Message::TripResetMessage(
+                        TripResetMessage { trip },
+                    )));
+                }
+            });
+        }
 
         let local_perct = Percentage::from_ui(self.throttle_req);
         self.update_state.request_every(
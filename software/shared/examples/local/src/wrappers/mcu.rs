@@ -8,30 +8,40 @@ use tokio::{io, sync::MutexGuard, task};
 
 use futures::{FutureExt, StreamExt};
 use shared::{
-    config::config::Config, controllers::mcu::McuController, messages::messages::Message,
+    config::{config::Config, store::ConfigStore},
+    controllers::mcu::McuController,
+    messages::messages::Message,
     utils::time::Timestamp,
 };
 use tokio_util::codec::{FramedRead, LinesCodec}; // For the .next() method on FramedRead
 
 use tokio::sync::Mutex;
 
-use crate::wrappers::core::{broadcast_message, get_next_message, get_timestamp, local_sleep};
+use crate::{
+    storage::LocalStorage,
+    wrappers::core::{broadcast_message, get_next_message, get_timestamp, local_sleep},
+};
 
 pub struct LocalMcuRunner {
     controller: Mutex<McuController>,
+    storage: Mutex<LocalStorage>,
 }
 
 impl LocalMcuRunner {
     fn new(config: Config) -> Self {
-        let controler = McuController::new(config);
+        let mut storage = LocalStorage::open("./storage/mcu");
+        let controler = McuController::new(storage.load_config(config));
         let controller = Mutex::from(controler);
-        LocalMcuRunner { controller }
+        LocalMcuRunner {
+            controller,
+            storage: Mutex::from(storage),
+        }
     }
 
     pub async fn broadcast_ecu(&self) {
         loop {
             let (sleep_time, msg) = {
-                let controller = self.controller.lock().await;
+                let mut controller = self.controller.lock().await;
                 let msg = controller.broadcast_ecu();
                 (controller.config.mcu.ecu_poll, msg)
             };
@@ -50,6 +60,28 @@ impl LocalMcuRunner {
             local_sleep(sleep_time).await
         }
     }
+    pub async fn broadcast_regen(&self) {
+        loop {
+            let (sleep_time, msg) = {
+                let controller = self.controller.lock().await;
+                let msg = controller.broadcast_regen();
+                (controller.config.mcu.regen_poll, msg)
+            };
+            (broadcast_message(msg)).await;
+            local_sleep(sleep_time).await
+        }
+    }
+    pub async fn broadcast_abs(&self) {
+        loop {
+            let (sleep_time, msg) = {
+                let controller = self.controller.lock().await;
+                let msg = controller.broadcast_abs();
+                (controller.config.mcu.abs_poll, msg)
+            };
+            (broadcast_message(msg)).await;
+            local_sleep(sleep_time).await
+        }
+    }
     pub async fn run_engine_subsystem(&self) {
         loop {
             let sleep_time = {
@@ -66,8 +98,29 @@ impl LocalMcuRunner {
             let msg = get_next_message().await;
             {
                 let mut controller = self.controller.lock().await;
-                controller.process_message(msg);
+                if let Err(err) = controller.process_message(msg) {
+                    eprintln!("Dropping message {:?} due to {}", msg, err);
+                }
+            }
+        }
+    }
+
+    pub async fn save_config(&self) {
+        loop {
+            let (sleep_time, config) = {
+                let mut controller = self.controller.lock().await;
+                (
+                    controller.config.mcu.config_save_poll,
+                    controller.take_changed_config(),
+                )
+            };
+            if let Some(config) = config {
+                let mut storage = self.storage.lock().await;
+                if let Err(err) = storage.save(&config) {
+                    eprintln!("Failed to save config due to {}", err);
+                }
             }
+            local_sleep(sleep_time).await
         }
     }
 
@@ -75,11 +128,14 @@ impl LocalMcuRunner {
         let runner = LocalMcuRunner::new(config);
 
         // Spawn a new task
-        let (_, _, _, _) = tokio::join!(
+        let (_, _, _, _, _, _, _) = tokio::join!(
             runner.run_engine_subsystem(),
             runner.broadcast_ecu(),
             runner.process_messages(),
             runner.broadcast_config(),
+            runner.broadcast_regen(),
+            runner.broadcast_abs(),
+            runner.save_config(),
         );
     }
 }
@@ -1,5 +1,18 @@
 use crate::{
-    controllers::fcu::FcuConfig, controllers::mcu::McuConfig, subsystems::mcu::engine::EngineConfig,
+    config::profile::{RideProfile, RideProfileId, RideProfiles},
+    controllers::{battery::BatteryConfig, fcu::FcuConfig, mcu::McuConfig},
+    messages::error::DecodeError,
+    operations::{
+        launch_control::LaunchControlMode, speed_limiter::SpeedLimitClass,
+        throttle_map::ThottleMapMode, traction_control::TractionControlMode,
+    },
+    subsystems::mcu::{
+        abs::AbsConfig,
+        engine::{AssistMode, EngineConfig},
+        pas::PasConfig,
+        regen::RegenConfig,
+    },
+    utils::percentage::Percentage,
 };
 
 #[derive(Debug, Clone, Copy, Default)]
@@ -7,39 +20,193 @@ pub struct Config {
     pub fcu: FcuConfig,
     pub mcu: McuConfig,
     pub engine: EngineConfig,
+    pub regen: RegenConfig,
+    pub pas: PasConfig,
+    pub abs: AbsConfig,
+    pub profiles: RideProfiles,
+    pub battery: BatteryConfig,
 }
 
 impl Config {
     pub fn apply_delta(&mut self, delta: ConfigDelta) {
-        self.engine = delta.engine;
+        // only take the fields carried by the config message, the rest stays local
+        self.engine.throttle_map_mode = delta.throttle_map_mode;
+        self.engine.traction_control_mode = delta.traction_control_mode;
+        self.engine.desired_slip = delta.desired_slip;
+        self.engine.brake_cutoff_threshold = delta.brake_cutoff_threshold;
+        self.engine.brake_cutoff_hysteresis = delta.brake_cutoff_hysteresis;
+        self.engine.assist_mode = delta.assist_mode;
+        self.engine.speed_limit_class = delta.speed_limit_class;
+        self.engine.launch_control_mode = delta.launch_control_mode;
+    }
+
+    pub fn select_profile(&mut self, id: RideProfileId) {
+        self.profiles.active = id;
+        self.profiles.get(id).apply(&mut self.engine);
+    }
+
+    // a knob change moves the rider onto the custom profile so the named ones stay as set
+    pub fn capture_custom_profile(&mut self) {
+        self.profiles.custom = RideProfile::from_engine(&self.engine);
+        self.profiles.active = RideProfileId::Custom();
     }
 }
 
+// the engine settings that fit in the config message
 #[derive(Debug, Clone, Copy)]
 pub struct ConfigDelta {
-    pub engine: EngineConfig,
+    pub throttle_map_mode: ThottleMapMode,
+    pub traction_control_mode: TractionControlMode,
+    pub desired_slip: Percentage,
+    pub brake_cutoff_threshold: Percentage,
+    pub brake_cutoff_hysteresis: Percentage,
+    pub assist_mode: AssistMode,
+    pub speed_limit_class: SpeedLimitClass,
+    pub launch_control_mode: LaunchControlMode,
 }
+
 impl ConfigDelta {
+    pub fn from_engine(engine: &EngineConfig) -> Self {
+        ConfigDelta {
+            throttle_map_mode: engine.throttle_map_mode,
+            traction_control_mode: engine.traction_control_mode,
+            desired_slip: engine.desired_slip,
+            brake_cutoff_threshold: engine.brake_cutoff_threshold,
+            brake_cutoff_hysteresis: engine.brake_cutoff_hysteresis,
+            assist_mode: engine.assist_mode,
+            speed_limit_class: engine.speed_limit_class,
+            launch_control_mode: engine.launch_control_mode,
+        }
+    }
+
     pub fn to_bytes(&self) -> [u8; 8] {
         [
-            self.engine.throttle_map_mode.into(),
-            self.engine.traction_control_mode.into(),
-            self.engine.desired_slip.into(),
-            0,
-            0,
-            0,
-            0,
-            0,
+            self.throttle_map_mode.into(),
+            self.traction_control_mode.into(),
+            self.desired_slip.into(),
+            self.brake_cutoff_threshold.into(),
+            self.brake_cutoff_hysteresis.into(),
+            self.assist_mode.into(),
+            self.speed_limit_class.into(),
+            self.launch_control_mode.into(),
         ]
     }
+}
 
-    pub fn from_bytes(data: &[u8]) -> Self {
-        Self {
-            engine: EngineConfig {
-                throttle_map_mode: data[0].into(),
-                traction_control_mode: data[1].into(),
-                desired_slip: data[2].into(),
-            },
-        }
+impl TryFrom<&[u8]> for ConfigDelta {
+    type Error = DecodeError;
+
+    fn try_from(data: &[u8]) -> Result<Self, Self::Error> {
+        DecodeError::check_len(data, 8)?;
+        Ok(Self {
+            throttle_map_mode: data[0].try_into()?,
+            traction_control_mode: data[1].try_into()?,
+            desired_slip: data[2].into(),
+            brake_cutoff_threshold: data[3].into(),
+            brake_cutoff_hysteresis: data[4].into(),
+            assist_mode: data[5].try_into()?,
+            speed_limit_class: data[6].try_into()?,
+            launch_control_mode: data[7].try_into()?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn active(config: &Config) -> RideProfile {
+        RideProfile::from_engine(&config.engine)
+    }
+
+    #[test]
+    fn eco_applies_its_settings() {
+        let mut config = Config::default();
+        config.select_profile(RideProfileId::Eco());
+        assert_eq!(config.profiles.active, RideProfileId::Eco());
+        assert_eq!(config.engine.throttle_map_mode, ThottleMapMode::Level0());
+        assert_eq!(
+            config.engine.traction_control_mode,
+            TractionControlMode::Level1()
+        );
+        assert_eq!(
+            config.engine.desired_slip,
+            Percentage::from_fractional(0.08)
+        );
+        assert_eq!(config.engine.speed_limit_class, SpeedLimitClass::Eu());
+        assert_eq!(config.engine.power_limit, Percentage::from_fractional(0.5));
+    }
+
+    #[test]
+    fn tour_applies_its_settings() {
+        let mut config = Config::default();
+        config.select_profile(RideProfileId::Tour());
+        assert_eq!(config.engine.throttle_map_mode, ThottleMapMode::Level1());
+        assert_eq!(
+            config.engine.traction_control_mode,
+            TractionControlMode::Level1()
+        );
+        assert_eq!(config.engine.desired_slip, Percentage::from_fractional(0.1));
+        assert_eq!(config.engine.speed_limit_class, SpeedLimitClass::Eu());
+        assert_eq!(config.engine.power_limit, Percentage::from_fractional(0.8));
+    }
+
+    #[test]
+    fn sport_applies_its_settings() {
+        let mut config = Config::default();
+        config.select_profile(RideProfileId::Sport());
+        assert_eq!(config.engine.throttle_map_mode, ThottleMapMode::Level2());
+        assert_eq!(
+            config.engine.traction_control_mode,
+            TractionControlMode::Level0()
+        );
+        assert_eq!(
+            config.engine.desired_slip,
+            Percentage::from_fractional(0.15)
+        );
+        assert_eq!(config.engine.speed_limit_class, SpeedLimitClass::UsClass3());
+        assert_eq!(config.engine.power_limit, Percentage::full());
+    }
+
+    #[test]
+    fn profile_leaves_other_settings_alone() {
+        let mut config = Config::default();
+        config.engine.assist_mode = AssistMode::Pas();
+        config.engine.launch_control_mode = LaunchControlMode::Level1();
+        config.select_profile(RideProfileId::Eco());
+        assert_eq!(config.engine.assist_mode, AssistMode::Pas());
+        assert_eq!(
+            config.engine.launch_control_mode,
+            LaunchControlMode::Level1()
+        );
+    }
+
+    #[test]
+    fn custom_round_trips() {
+        let mut config = Config::default();
+        config.select_profile(RideProfileId::Eco());
+        config.engine.throttle_map_mode = ThottleMapMode::Level2();
+        config.engine.desired_slip = Percentage::from_fractional(0.12);
+        config.engine.power_limit = Percentage::from_fractional(0.7);
+        config.capture_custom_profile();
+        let custom = active(&config);
+        assert_eq!(config.profiles.active, RideProfileId::Custom());
+
+        config.select_profile(RideProfileId::Sport());
+        assert_eq!(active(&config), RideProfile::sport());
+        config.select_profile(RideProfileId::Custom());
+        assert_eq!(active(&config), custom);
+    }
+
+    #[test]
+    fn custom_capture_keeps_named_profiles() {
+        let mut config = Config::default();
+        config.select_profile(RideProfileId::Tour());
+        config.engine.power_limit = Percentage::from_fractional(0.3);
+        config.capture_custom_profile();
+        assert_eq!(config.profiles.tour, RideProfile::tour());
+
+        config.select_profile(RideProfileId::Tour());
+        assert_eq!(config.engine.power_limit, Percentage::from_fractional(0.8));
     }
 }
@@ -0,0 +1,295 @@
+use embedded_storage::nor_flash::NorFlash;
+
+use crate::{
+    config::{
+        config::Config,
+        store::{CONFIG_RECORD_LEN, ConfigStore},
+    },
+    messages::error::DecodeError,
+    storage::storage::StorageError,
+};
+
+// every copy starts with a sequence number, an erased slot reads back as all ones
+const SEQUENCE_LEN: usize = 4;
+const ERASED_SEQUENCE: u32 = u32::MAX;
+// room for a slot padded out to flash that writes up to 32 bytes at a time
+const SLOT_BUF_LEN: usize = (SEQUENCE_LEN + CONFIG_RECORD_LEN).next_multiple_of(32);
+
+// where the next copy goes
+#[derive(Debug, Clone, Copy)]
+struct Cursor {
+    slot: u32,
+    sequence: u32,
+}
+
+// Wear levelled config store over raw NOR flash. Each save appends a new copy to the next
+// slot of a ring spanning several erase pages, so a page is only erased once per lap of the
+// ring. The newest copy that checks out wins on load, a save torn by a power cut just falls
+// back to the copy before it.
+pub struct FlashConfigStore<F: NorFlash> {
+    flash: F,
+    start: u32,
+    pages: u32,
+    // found by scanning the ring on first use
+    cursor: Option<Cursor>,
+}
+
+impl<F: NorFlash> FlashConfigStore<F> {
+    const SLOT_LEN: usize = (SEQUENCE_LEN + CONFIG_RECORD_LEN).next_multiple_of(F::WRITE_SIZE);
+
+    // the region has to be whole erase pages, and at least two so erasing the page ahead
+    // never takes out the only good copy
+    pub fn new(flash: F, start: u32, pages: u32) -> Option<Self> {
+        let end = start as usize + pages as usize * F::ERASE_SIZE;
+        if pages < 2
+            || !(start as usize).is_multiple_of(F::ERASE_SIZE)
+            || end > flash.capacity()
+            || Self::SLOT_LEN > SLOT_BUF_LEN
+            || Self::SLOT_LEN > F::ERASE_SIZE
+            || Self::SLOT_LEN % F::READ_SIZE != 0
+        {
+            return None;
+        }
+        Some(FlashConfigStore {
+            flash,
+            start,
+            pages,
+            cursor: None,
+        })
+    }
+
+    pub fn release(self) -> F {
+        self.flash
+    }
+
+    fn slots_per_page() -> u32 {
+        (F::ERASE_SIZE / Self::SLOT_LEN) as u32
+    }
+
+    fn slot_count(&self) -> u32 {
+        self.pages * Self::slots_per_page()
+    }
+
+    fn page_offset(&self, slot: u32) -> u32 {
+        self.start + (slot / Self::slots_per_page()) * F::ERASE_SIZE as u32
+    }
+
+    fn slot_offset(&self, slot: u32) -> u32 {
+        self.page_offset(slot) + (slot % Self::slots_per_page()) * Self::SLOT_LEN as u32
+    }
+
+    fn read_slot(&mut self, slot: u32, buf: &mut [u8; SLOT_BUF_LEN]) -> Result<u32, F::Error> {
+        let offset = self.slot_offset(slot);
+        self.flash.read(offset, &mut buf[..Self::SLOT_LEN])?;
+        Ok(u32::from_le_bytes([buf[0], buf[1], buf[2], buf[3]]))
+    }
+
+    // walk the whole ring for the newest good copy and the last slot written to
+    fn scan(&mut self) -> Result<Result<Option<Config>, DecodeError>, F::Error> {
+        let mut buf = [0u8; SLOT_BUF_LEN];
+        let mut newest: Option<(u32, Config)> = None;
+        let mut last_written: Option<(u32, u32)> = None;
+        let mut corrupt = None;
+        for slot in 0..self.slot_count() {
+            let sequence = self.read_slot(slot, &mut buf)?;
+            if sequence == ERASED_SEQUENCE {
+                continue;
+            }
+            if last_written.is_none_or(|(_, last)| sequence > last) {
+                last_written = Some((slot, sequence));
+            }
+            let record = &buf[SEQUENCE_LEN..SEQUENCE_LEN + CONFIG_RECORD_LEN];
+            match Config::from_record(record) {
+                Ok(config) if newest.is_none_or(|(newest, _)| sequence > newest) => {
+                    newest = Some((sequence, config));
+                }
+                Ok(_) => {}
+                Err(err) => corrupt = Some(err),
+            }
+        }
+
+        self.cursor = Some(match last_written {
+            Some((slot, sequence)) => Cursor {
+                slot: (slot + 1) % self.slot_count(),
+                sequence: sequence + 1,
+            },
+            None => Cursor {
+                slot: 0,
+                sequence: 0,
+            },
+        });
+        Ok(match (newest, corrupt) {
+            (Some((_, config)), _) => Ok(Some(config)),
+            (None, Some(err)) => Err(err),
+            (None, None) => Ok(None),
+        })
+    }
+}
+
+impl<F: NorFlash> ConfigStore for FlashConfigStore<F> {
+    type Error = F::Error;
+
+    fn load(&mut self) -> Result<Option<Config>, StorageError<Self::Error>> {
+        self.scan()
+            .map_err(StorageError::Backend)?
+            .map_err(StorageError::Corrupt)
+    }
+
+    fn save(&mut self, config: &Config) -> Result<(), Self::Error> {
+        let mut cursor = match self.cursor {
+            Some(cursor) => cursor,
+            None => {
+                // only the cursor is wanted, whatever the ring held gets written past
+                let _ = self.scan()?;
+                self.cursor.unwrap_or(Cursor {
+                    slot: 0,
+                    sequence: 0,
+                })
+            }
+        };
+
+        // anything left in the slot means the page wasn't erased by us, start the next page
+        let mut buf = [0u8; SLOT_BUF_LEN];
+        let per_page = Self::slots_per_page();
+        if cursor.slot % per_page != 0 {
+            self.read_slot(cursor.slot, &mut buf)?;
+            if buf[..Self::SLOT_LEN].iter().any(|byte| *byte != 0xFF) {
+                cursor.slot = (cursor.slot / per_page + 1) * per_page % self.slot_count();
+            }
+        }
+        if cursor.slot % per_page == 0 {
+            let page = self.page_offset(cursor.slot);
+            self.flash.erase(page, page + F::ERASE_SIZE as u32)?;
+        }
+
+        buf.fill(0xFF);
+        buf[..SEQUENCE_LEN].copy_from_slice(&cursor.sequence.to_le_bytes());
+        buf[SEQUENCE_LEN..SEQUENCE_LEN + CONFIG_RECORD_LEN].copy_from_slice(&config.to_record());
+        let offset = self.slot_offset(cursor.slot);
+        self.flash.write(offset, &buf[..Self::SLOT_LEN])?;
+
+        self.cursor = Some(Cursor {
+            slot: (cursor.slot + 1) % self.slot_count(),
+            sequence: cursor.sequence + 1,
+        });
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use embedded_storage::nor_flash::{ErrorType, NorFlashErrorKind, ReadNorFlash};
+
+    const PAGE: usize = 2048;
+    const PAGES: usize = 2;
+
+    // flash that only clears bits on write, like the real thing
+    struct RamFlash {
+        data: [u8; PAGE * PAGES],
+        erases: usize,
+    }
+
+    impl RamFlash {
+        fn new() -> Self {
+            RamFlash {
+                data: [0xFF; PAGE * PAGES],
+                erases: 0,
+            }
+        }
+    }
+
+    impl ErrorType for RamFlash {
+        type Error = NorFlashErrorKind;
+    }
+
+    impl ReadNorFlash for RamFlash {
+        const READ_SIZE: usize = 1;
+
+        fn read(&mut self, offset: u32, bytes: &mut [u8]) -> Result<(), Self::Error> {
+            let offset = offset as usize;
+            bytes.copy_from_slice(&self.data[offset..offset + bytes.len()]);
+            Ok(())
+        }
+
+        fn capacity(&self) -> usize {
+            self.data.len()
+        }
+    }
+
+    impl NorFlash for RamFlash {
+        const WRITE_SIZE: usize = 4;
+        const ERASE_SIZE: usize = PAGE;
+
+        fn erase(&mut self, from: u32, to: u32) -> Result<(), Self::Error> {
+            self.data[from as usize..to as usize].fill(0xFF);
+            self.erases += 1;
+            Ok(())
+        }
+
+        fn write(&mut self, offset: u32, bytes: &[u8]) -> Result<(), Self::Error> {
+            let offset = offset as usize;
+            for (cell, byte) in self.data[offset..offset + bytes.len()]
+                .iter_mut()
+                .zip(bytes)
+            {
+                *cell &= *byte;
+            }
+            Ok(())
+        }
+    }
+
+    fn config_with_slip(slip: f32) -> Config {
+        let mut config = Config::default();
+        config.engine.desired_slip = slip.into();
+        config
+    }
+
+    fn desired_slip(config: Config) -> f32 {
+        config.engine.desired_slip.into()
+    }
+
+    #[test]
+    fn blank_flash_loads_nothing() {
+        let mut store = FlashConfigStore::new(RamFlash::new(), 0, PAGES as u32).unwrap();
+        assert!(matches!(store.load(), Ok(None)));
+    }
+
+    #[test]
+    fn ring_wraps_and_keeps_newest() {
+        let mut store = FlashConfigStore::new(RamFlash::new(), 0, PAGES as u32).unwrap();
+        let slots = store.slot_count();
+        for idx in 0..(slots * 3 + 1) {
+            store.save(&config_with_slip(idx as f32)).unwrap();
+        }
+        let flash = store.release();
+        // one erase per page per lap, not per save
+        assert_eq!(flash.erases, 7);
+
+        let mut store = FlashConfigStore::new(flash, 0, PAGES as u32).unwrap();
+        assert_eq!(
+            desired_slip(store.load().unwrap().unwrap()),
+            (slots * 3) as f32
+        );
+    }
+
+    #[test]
+    fn torn_save_falls_back() {
+        let mut store = FlashConfigStore::new(RamFlash::new(), 0, PAGES as u32).unwrap();
+        store.save(&config_with_slip(0.1)).unwrap();
+        store.save(&config_with_slip(0.2)).unwrap();
+        let mut flash = store.release();
+
+        // power lost half way through the second copy
+        let slot_len = FlashConfigStore::<RamFlash>::SLOT_LEN;
+        flash.data[slot_len + slot_len / 2..2 * slot_len].fill(0xFF);
+
+        let mut store = FlashConfigStore::new(flash, 0, PAGES as u32).unwrap();
+        assert_eq!(desired_slip(store.load().unwrap().unwrap()), 0.1);
+        // the torn slot is skipped, not written over
+        store.save(&config_with_slip(0.3)).unwrap();
+        let mut store = FlashConfigStore::new(store.release(), 0, PAGES as u32).unwrap();
+        assert_eq!(desired_slip(store.load().unwrap().unwrap()), 0.3);
+    }
+}
@@ -1,2 +1,11 @@
 #[path = "./config.rs"]
 pub mod config;
+
+#[path = "./profile.rs"]
+pub mod profile;
+
+#[path = "./store.rs"]
+pub mod store;
+
+#[path = "./flash_store.rs"]
+pub mod flash_store;
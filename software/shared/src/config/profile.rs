@@ -0,0 +1,207 @@
+use crate::{
+    messages::error::DecodeError,
+    operations::{
+        speed_limiter::SpeedLimitClass, throttle_map::ThottleMapMode,
+        traction_control::TractionControlMode,
+    },
+    subsystems::mcu::engine::EngineConfig,
+    utils::percentage::Percentage,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum RideProfileId {
+    Eco(),
+    Tour(),
+    Sport(),
+    Custom(), // whatever the knobs were last set to
+}
+
+impl RideProfileId {
+    // order the FCU button steps through
+    pub fn next(&self) -> Self {
+        match self {
+            RideProfileId::Eco() => RideProfileId::Tour(),
+            RideProfileId::Tour() => RideProfileId::Sport(),
+            RideProfileId::Sport() => RideProfileId::Custom(),
+            RideProfileId::Custom() => RideProfileId::Eco(),
+        }
+    }
+
+    pub fn to_small_str(&self) -> &str {
+        match self {
+            RideProfileId::Eco() => "ECO",
+            RideProfileId::Tour() => "TOUR",
+            RideProfileId::Sport() => "SPRT",
+            RideProfileId::Custom() => "CUST",
+        }
+    }
+}
+
+impl From<RideProfileId> for u8 {
+    fn from(id: RideProfileId) -> u8 {
+        match id {
+            RideProfileId::Eco() => 0,
+            RideProfileId::Tour() => 1,
+            RideProfileId::Sport() => 2,
+            RideProfileId::Custom() => 3,
+        }
+    }
+}
+
+impl TryFrom<u8> for RideProfileId {
+    type Error = DecodeError;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(RideProfileId::Eco()),
+            1 => Ok(RideProfileId::Tour()),
+            2 => Ok(RideProfileId::Sport()),
+            3 => Ok(RideProfileId::Custom()),
+            _ => Err(DecodeError::InvalidValue {
+                field: "RideProfileId",
+                value,
+            }),
+        }
+    }
+}
+
+// the engine settings a profile switch replaces, everything else is left alone
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RideProfile {
+    pub throttle_map_mode: ThottleMapMode,
+    pub traction_control_mode: TractionControlMode,
+    pub desired_slip: Percentage,
+    pub speed_limit_class: SpeedLimitClass,
+    pub power_limit: Percentage,
+}
+
+impl RideProfile {
+    pub fn eco() -> Self {
+        RideProfile {
+            throttle_map_mode: ThottleMapMode::Level0(),
+            traction_control_mode: TractionControlMode::Level1(),
+            desired_slip: Percentage::from_fractional(0.08),
+            speed_limit_class: SpeedLimitClass::Eu(),
+            power_limit: Percentage::from_fractional(0.5),
+        }
+    }
+
+    pub fn tour() -> Self {
+        RideProfile {
+            throttle_map_mode: ThottleMapMode::Level1(),
+            traction_control_mode: TractionControlMode::Level1(),
+            desired_slip: Percentage::from_fractional(0.1),
+            speed_limit_class: SpeedLimitClass::Eu(),
+            power_limit: Percentage::from_fractional(0.8),
+        }
+    }
+
+    pub fn sport() -> Self {
+        RideProfile {
+            throttle_map_mode: ThottleMapMode::Level2(),
+            traction_control_mode: TractionControlMode::Level0(),
+            desired_slip: Percentage::from_fractional(0.15),
+            speed_limit_class: SpeedLimitClass::UsClass3(),
+            power_limit: Percentage::full(),
+        }
+    }
+
+    pub fn from_engine(engine: &EngineConfig) -> Self {
+        RideProfile {
+            throttle_map_mode: engine.throttle_map_mode,
+            traction_control_mode: engine.traction_control_mode,
+            desired_slip: engine.desired_slip,
+            speed_limit_class: engine.speed_limit_class,
+            power_limit: engine.power_limit,
+        }
+    }
+
+    pub fn apply(&self, engine: &mut EngineConfig) {
+        engine.throttle_map_mode = self.throttle_map_mode;
+        engine.traction_control_mode = self.traction_control_mode;
+        engine.desired_slip = self.desired_slip;
+        engine.speed_limit_class = self.speed_limit_class;
+        engine.power_limit = self.power_limit;
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct RideProfiles {
+    pub active: RideProfileId,
+    pub eco: RideProfile,
+    pub tour: RideProfile,
+    pub sport: RideProfile,
+    pub custom: RideProfile,
+}
+
+impl Default for RideProfiles {
+    fn default() -> Self {
+        RideProfiles {
+            active: RideProfileId::Custom(),
+            eco: RideProfile::eco(),
+            tour: RideProfile::tour(),
+            sport: RideProfile::sport(),
+            custom: RideProfile::from_engine(&EngineConfig::default()),
+        }
+    }
+}
+
+impl RideProfiles {
+    pub fn get(&self, id: RideProfileId) -> RideProfile {
+        match id {
+            RideProfileId::Eco() => self.eco,
+            RideProfileId::Tour() => self.tour,
+            RideProfileId::Sport() => self.sport,
+            RideProfileId::Custom() => self.custom,
+        }
+    }
+
+    pub fn get_mut(&mut self, id: RideProfileId) -> &mut RideProfile {
+        match id {
+            RideProfileId::Eco() => &mut self.eco,
+            RideProfileId::Tour() => &mut self.tour,
+            RideProfileId::Sport() => &mut self.sport,
+            RideProfileId::Custom() => &mut self.custom,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn button_cycles_every_profile() {
+        let mut id = RideProfileId::Eco();
+        for expected in [
+            RideProfileId::Tour(),
+            RideProfileId::Sport(),
+            RideProfileId::Custom(),
+            RideProfileId::Eco(),
+        ] {
+            id = id.next();
+            assert_eq!(id, expected);
+        }
+    }
+
+    #[test]
+    fn id_round_trips_through_u8() {
+        for id in [
+            RideProfileId::Eco(),
+            RideProfileId::Tour(),
+            RideProfileId::Sport(),
+            RideProfileId::Custom(),
+        ] {
+            assert_eq!(RideProfileId::try_from(u8::from(id)), Ok(id));
+        }
+        assert!(RideProfileId::try_from(4).is_err());
+    }
+
+    #[test]
+    fn apply_then_read_back() {
+        let mut engine = EngineConfig::default();
+        RideProfile::sport().apply(&mut engine);
+        assert_eq!(RideProfile::from_engine(&engine), RideProfile::sport());
+    }
+}
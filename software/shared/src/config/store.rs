@@ -0,0 +1,770 @@
+use crate::{
+    config::{
+        config::Config,
+        profile::{RideProfile, RideProfiles},
+    },
+    controllers::{
+        battery::BatteryConfig,
+        fcu::{FcuConfig, OdometerConfig, TripConfig},
+        mcu::McuConfig,
+    },
+    messages::error::DecodeError,
+    operations::{
+        cruise_control::CruiseControlConfig,
+        power_limiter::PowerLimiterConfig,
+        slew_limiter::SlewLimiterConfig,
+        slip_estimator::SlipEstimatorConfig,
+        soc_estimator::SocEstimatorConfig,
+        thermal_derate::{ThermalDerateConfig, ThermalLimit},
+        throttle_map::{THROTTLE_TABLE_MAX_POINTS, ThrottleBreakpoint, ThrottleTable},
+        torque_assist::TorqueAssistConfig,
+        traction_control::TractionControlGains,
+        walk_assist::WalkAssistConfig,
+    },
+    storage::storage::{Storage, StorageError, StorageKey, crc16},
+    subsystems::mcu::{
+        abs::AbsConfig,
+        engine::EngineConfig,
+        pas::{PAS_TIER_COUNT, PasConfig, PasTier},
+        regen::RegenConfig,
+    },
+    utils::{percentage::Percentage, speed::Cadence, time::Duration},
+};
+
+// bump whenever a field is added, removed or reordered, older records are then ignored
+pub const CONFIG_VERSION: u8 = 2;
+// checked against the serializer on every write and by `payload_len_matches_serializer`
+const CONFIG_PAYLOAD_LEN: usize = 565;
+// version byte, payload, crc16
+pub const CONFIG_RECORD_LEN: usize = 1 + CONFIG_PAYLOAD_LEN + 2;
+
+// Somewhere the full config survives a power cycle
+pub trait ConfigStore {
+    type Error: core::fmt::Debug;
+
+    // None if nothing has been saved yet, the caller starts from the defaults
+    fn load(&mut self) -> Result<Option<Config>, StorageError<Self::Error>>;
+
+    fn save(&mut self, config: &Config) -> Result<(), Self::Error>;
+}
+
+// record storage keeps the config as one more record next to the others
+impl<S: Storage> ConfigStore for S {
+    type Error = S::Error;
+
+    fn load(&mut self) -> Result<Option<Config>, StorageError<Self::Error>> {
+        let mut buf = [0u8; CONFIG_RECORD_LEN];
+        let len = self
+            .read(StorageKey::Config(), &mut buf)
+            .map_err(StorageError::Backend)?;
+        match len {
+            Some(len) => Config::from_record(&buf[..len])
+                .map(Some)
+                .map_err(StorageError::Corrupt),
+            None => Ok(None),
+        }
+    }
+
+    fn save(&mut self, config: &Config) -> Result<(), Self::Error> {
+        self.write(StorageKey::Config(), &config.to_record())
+    }
+}
+
+impl Config {
+    pub fn to_record(&self) -> [u8; CONFIG_RECORD_LEN] {
+        let mut data = [0u8; CONFIG_RECORD_LEN];
+        data[0] = CONFIG_VERSION;
+        let mut writer = ConfigWriter::new(&mut data[1..1 + CONFIG_PAYLOAD_LEN]);
+        self.persist(&mut writer);
+        // a short payload would still carry a valid crc, so this can't be left to debug builds
+        assert_eq!(
+            writer.pos, CONFIG_PAYLOAD_LEN,
+            "CONFIG_PAYLOAD_LEN is out of step with Config::persist"
+        );
+        let crc = crc16(&data[..1 + CONFIG_PAYLOAD_LEN]);
+        data[1 + CONFIG_PAYLOAD_LEN..].copy_from_slice(&crc.to_le_bytes());
+        data
+    }
+
+    pub fn from_record(data: &[u8]) -> Result<Self, DecodeError> {
+        DecodeError::check_len(data, CONFIG_RECORD_LEN)?;
+        if data[0] != CONFIG_VERSION {
+            return Err(DecodeError::InvalidValue {
+                field: "Config::version",
+                value: data[0],
+            });
+        }
+        let crc = u16::from_le_bytes([data[1 + CONFIG_PAYLOAD_LEN], data[2 + CONFIG_PAYLOAD_LEN]]);
+        if crc16(&data[..1 + CONFIG_PAYLOAD_LEN]) != crc {
+            return Err(DecodeError::CrcMismatch);
+        }
+        Config::restore(&mut ConfigReader::new(&data[1..1 + CONFIG_PAYLOAD_LEN]))
+    }
+}
+
+pub struct ConfigWriter<'a> {
+    data: &'a mut [u8],
+    pos: usize,
+}
+
+impl<'a> ConfigWriter<'a> {
+    pub fn new(data: &'a mut [u8]) -> Self {
+        ConfigWriter { data, pos: 0 }
+    }
+
+    fn put(&mut self, bytes: &[u8]) {
+        self.data[self.pos..self.pos + bytes.len()].copy_from_slice(bytes);
+        self.pos += bytes.len();
+    }
+
+    pub fn put_u8(&mut self, value: u8) {
+        self.put(&[value]);
+    }
+
+    pub fn put_u16(&mut self, value: u16) {
+        self.put(&value.to_le_bytes());
+    }
+
+    pub fn put_u32(&mut self, value: u32) {
+        self.put(&value.to_le_bytes());
+    }
+
+    pub fn put_u64(&mut self, value: u64) {
+        self.put(&value.to_le_bytes());
+    }
+
+    pub fn put_f32(&mut self, value: f32) {
+        self.put(&value.to_le_bytes());
+    }
+
+    // full precision, the single byte form used on CAN loses the low bits
+    pub fn put_percentage(&mut self, value: Percentage) {
+        self.put_f32(value.into());
+    }
+
+    pub fn put_duration(&mut self, value: Duration) {
+        self.put_u64(value.as_millis());
+    }
+}
+
+pub struct ConfigReader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> ConfigReader<'a> {
+    pub fn new(data: &'a [u8]) -> Self {
+        ConfigReader { data, pos: 0 }
+    }
+
+    fn take<const N: usize>(&mut self) -> Result<[u8; N], DecodeError> {
+        DecodeError::check_len(self.data, self.pos + N)?;
+        let mut bytes = [0u8; N];
+        bytes.copy_from_slice(&self.data[self.pos..self.pos + N]);
+        self.pos += N;
+        Ok(bytes)
+    }
+
+    pub fn get_u8(&mut self) -> Result<u8, DecodeError> {
+        Ok(self.take::<1>()?[0])
+    }
+
+    pub fn get_u16(&mut self) -> Result<u16, DecodeError> {
+        Ok(u16::from_le_bytes(self.take()?))
+    }
+
+    pub fn get_u32(&mut self) -> Result<u32, DecodeError> {
+        Ok(u32::from_le_bytes(self.take()?))
+    }
+
+    pub fn get_u64(&mut self) -> Result<u64, DecodeError> {
+        Ok(u64::from_le_bytes(self.take()?))
+    }
+
+    pub fn get_f32(&mut self) -> Result<f32, DecodeError> {
+        Ok(f32::from_le_bytes(self.take()?))
+    }
+
+    pub fn get_percentage(&mut self) -> Result<Percentage, DecodeError> {
+        Ok(Percentage::from(self.get_f32()?))
+    }
+
+    pub fn get_duration(&mut self) -> Result<Duration, DecodeError> {
+        Ok(Duration::from_millis(self.get_u64()?))
+    }
+}
+
+// Fixed layout serialization of a config section, fields in declaration order
+pub trait Persist: Sized {
+    fn persist(&self, w: &mut ConfigWriter);
+
+    fn restore(r: &mut ConfigReader) -> Result<Self, DecodeError>;
+}
+
+impl Persist for Config {
+    fn persist(&self, w: &mut ConfigWriter) {
+        self.fcu.persist(w);
+        self.mcu.persist(w);
+        self.engine.persist(w);
+        self.regen.persist(w);
+        self.pas.persist(w);
+        self.abs.persist(w);
+        self.profiles.persist(w);
+        self.battery.persist(w);
+    }
+
+    fn restore(r: &mut ConfigReader) -> Result<Self, DecodeError> {
+        Ok(Config {
+            fcu: FcuConfig::restore(r)?,
+            mcu: McuConfig::restore(r)?,
+            engine: EngineConfig::restore(r)?,
+            regen: RegenConfig::restore(r)?,
+            pas: PasConfig::restore(r)?,
+            abs: AbsConfig::restore(r)?,
+            profiles: RideProfiles::restore(r)?,
+            battery: BatteryConfig::restore(r)?,
+        })
+    }
+}
+
+impl Persist for FcuConfig {
+    fn persist(&self, w: &mut ConfigWriter) {
+        w.put_duration(self.message_poll);
+        w.put_duration(self.ctl_poll);
+        w.put_duration(self.update_poll);
+        w.put_duration(self.display_poll);
+        w.put_duration(self.ws_timeout);
+        w.put_duration(self.config_save_poll);
+        self.trip.persist(w);
+        self.odometer.persist(w);
+    }
+
+    fn restore(r: &mut ConfigReader) -> Result<Self, DecodeError> {
+        Ok(FcuConfig {
+            message_poll: r.get_duration()?,
+            ctl_poll: r.get_duration()?,
+            update_poll: r.get_duration()?,
+            display_poll: r.get_duration()?,
+            ws_timeout: r.get_duration()?,
+            config_save_poll: r.get_duration()?,
+            trip: TripConfig::restore(r)?,
+            odometer: OdometerConfig::restore(r)?,
+        })
+    }
+}
+
+impl Persist for TripConfig {
+    fn persist(&self, w: &mut ConfigWriter) {
+        w.put_f32(self.bucket_km);
+        w.put_f32(self.min_window_km);
+        w.put_f32(self.pack_capacity_wh);
+        w.put_duration(self.max_step);
+    }
+
+    fn restore(r: &mut ConfigReader) -> Result<Self, DecodeError> {
+        Ok(TripConfig {
+            bucket_km: r.get_f32()?,
+            min_window_km: r.get_f32()?,
+            pack_capacity_wh: r.get_f32()?,
+            max_step: r.get_duration()?,
+        })
+    }
+}
+
+impl Persist for OdometerConfig {
+    fn persist(&self, w: &mut ConfigWriter) {
+        w.put_u32(self.save_every_m);
+        w.put_u32(self.save_every_s);
+        w.put_f32(self.moving_kph);
+        w.put_duration(self.max_step);
+    }
+
+    fn restore(r: &mut ConfigReader) -> Result<Self, DecodeError> {
+        Ok(OdometerConfig {
+            save_every_m: r.get_u32()?,
+            save_every_s: r.get_u32()?,
+            moving_kph: r.get_f32()?,
+            max_step: r.get_duration()?,
+        })
+    }
+}
+
+impl Persist for McuConfig {
+    fn persist(&self, w: &mut ConfigWriter) {
+        w.put_duration(self.engine_poll);
+        w.put_duration(self.ecu_poll);
+        w.put_duration(self.config_poll);
+        w.put_duration(self.regen_poll);
+        w.put_duration(self.abs_poll);
+        w.put_duration(self.ctl_timeout);
+        w.put_duration(self.ws_timeout);
+        w.put_duration(self.cadence_timeout);
+        w.put_duration(self.torque_timeout);
+        w.put_duration(self.imu_timeout);
+        w.put_duration(self.temperature_timeout);
+        w.put_percentage(self.limp_throttle_limit);
+        w.put_duration(self.config_save_poll);
+    }
+
+    fn restore(r: &mut ConfigReader) -> Result<Self, DecodeError> {
+        Ok(McuConfig {
+            engine_poll: r.get_duration()?,
+            ecu_poll: r.get_duration()?,
+            config_poll: r.get_duration()?,
+            regen_poll: r.get_duration()?,
+            abs_poll: r.get_duration()?,
+            ctl_timeout: r.get_duration()?,
+            ws_timeout: r.get_duration()?,
+            cadence_timeout: r.get_duration()?,
+            torque_timeout: r.get_duration()?,
+            imu_timeout: r.get_duration()?,
+            temperature_timeout: r.get_duration()?,
+            limp_throttle_limit: r.get_percentage()?,
+            config_save_poll: r.get_duration()?,
+        })
+    }
+}
+
+impl Persist for EngineConfig {
+    fn persist(&self, w: &mut ConfigWriter) {
+        w.put_u8(self.assist_mode.into());
+        w.put_u8(self.throttle_map_mode.into());
+        self.throttle_table.persist(w);
+        w.put_u8(self.traction_control_mode.into());
+        self.traction_control_gains.persist(w);
+        w.put_percentage(self.desired_slip);
+        w.put_percentage(self.brake_cutoff_threshold);
+        w.put_percentage(self.brake_cutoff_hysteresis);
+        self.torque_assist.persist(w);
+        w.put_u8(self.speed_limit_class.into());
+        w.put_f32(self.wheel_diameter_inch);
+        w.put_u8(self.launch_control_mode.into());
+        self.slew_limiter.persist(w);
+        self.slip_estimator.persist(w);
+        w.put_u8(self.anti_wheelie_mode.into());
+        self.cruise_control.persist(w);
+        self.walk_assist.persist(w);
+        w.put_percentage(self.power_limit);
+        self.thermal_derate.persist(w);
+        self.power_limiter.persist(w);
+    }
+
+    fn restore(r: &mut ConfigReader) -> Result<Self, DecodeError> {
+        Ok(EngineConfig {
+            assist_mode: r.get_u8()?.try_into()?,
+            throttle_map_mode: r.get_u8()?.try_into()?,
+            throttle_table: ThrottleTable::restore(r)?,
+            traction_control_mode: r.get_u8()?.try_into()?,
+            traction_control_gains: TractionControlGains::restore(r)?,
+            desired_slip: r.get_percentage()?,
+            brake_cutoff_threshold: r.get_percentage()?,
+            brake_cutoff_hysteresis: r.get_percentage()?,
+            torque_assist: TorqueAssistConfig::restore(r)?,
+            speed_limit_class: r.get_u8()?.try_into()?,
+            wheel_diameter_inch: r.get_f32()?,
+            launch_control_mode: r.get_u8()?.try_into()?,
+            slew_limiter: SlewLimiterConfig::restore(r)?,
+            slip_estimator: SlipEstimatorConfig::restore(r)?,
+            anti_wheelie_mode: r.get_u8()?.try_into()?,
+            cruise_control: CruiseControlConfig::restore(r)?,
+            walk_assist: WalkAssistConfig::restore(r)?,
+            power_limit: r.get_percentage()?,
+            thermal_derate: ThermalDerateConfig::restore(r)?,
+            power_limiter: PowerLimiterConfig::restore(r)?,
+        })
+    }
+}
+
+// always the full set of slots so the layout doesn't depend on the table length
+impl Persist for ThrottleTable {
+    fn persist(&self, w: &mut ConfigWriter) {
+        let breakpoints = self.breakpoints();
+        w.put_u8(self.interpolation.into());
+        w.put_u8(breakpoints.len() as u8);
+        for idx in 0..THROTTLE_TABLE_MAX_POINTS {
+            let point = breakpoints
+                .get(idx)
+                .copied()
+                .unwrap_or(ThrottleBreakpoint::new(
+                    Percentage::zero(),
+                    Percentage::zero(),
+                ));
+            w.put_percentage(point.input);
+            w.put_percentage(point.output);
+        }
+    }
+
+    fn restore(r: &mut ConfigReader) -> Result<Self, DecodeError> {
+        let interpolation = r.get_u8()?.try_into()?;
+        let len = r.get_u8()?;
+        let mut points = [ThrottleBreakpoint::new(Percentage::zero(), Percentage::zero());
+            THROTTLE_TABLE_MAX_POINTS];
+        for point in points.iter_mut() {
+            *point = ThrottleBreakpoint::new(r.get_percentage()?, r.get_percentage()?);
+        }
+        if len as usize > THROTTLE_TABLE_MAX_POINTS {
+            return Err(DecodeError::InvalidValue {
+                field: "ThrottleTable::len",
+                value: len,
+            });
+        }
+        ThrottleTable::new(&points[..len as usize], interpolation)
+    }
+}
+
+impl Persist for TractionControlGains {
+    fn persist(&self, w: &mut ConfigWriter) {
+        w.put_f32(self.kp);
+        w.put_f32(self.ki);
+        w.put_f32(self.kd);
+        w.put_u16(self.derivative_tau_ms);
+        w.put_f32(self.integral_limit);
+        w.put_f32(self.output_min);
+        w.put_f32(self.output_max);
+    }
+
+    fn restore(r: &mut ConfigReader) -> Result<Self, DecodeError> {
+        Ok(TractionControlGains {
+            kp: r.get_f32()?,
+            ki: r.get_f32()?,
+            kd: r.get_f32()?,
+            derivative_tau_ms: r.get_u16()?,
+            integral_limit: r.get_f32()?,
+            output_min: r.get_f32()?,
+            output_max: r.get_f32()?,
+        })
+    }
+}
+
+impl Persist for TorqueAssistConfig {
+    fn persist(&self, w: &mut ConfigWriter) {
+        w.put_f32(self.assist_ratio);
+        w.put_f32(self.max_motor_torque_nm);
+        w.put_f32(self.start_torque_nm);
+        w.put_u16(self.filter_tau_ms);
+    }
+
+    fn restore(r: &mut ConfigReader) -> Result<Self, DecodeError> {
+        Ok(TorqueAssistConfig {
+            assist_ratio: r.get_f32()?,
+            max_motor_torque_nm: r.get_f32()?,
+            start_torque_nm: r.get_f32()?,
+            filter_tau_ms: r.get_u16()?,
+        })
+    }
+}
+
+impl Persist for SlewLimiterConfig {
+    fn persist(&self, w: &mut ConfigWriter) {
+        w.put_f32(self.rise_per_s);
+        w.put_f32(self.fall_per_s);
+        w.put_u16(self.smoothing_tau_ms);
+    }
+
+    fn restore(r: &mut ConfigReader) -> Result<Self, DecodeError> {
+        Ok(SlewLimiterConfig {
+            rise_per_s: r.get_f32()?,
+            fall_per_s: r.get_f32()?,
+            smoothing_tau_ms: r.get_u16()?,
+        })
+    }
+}
+
+impl Persist for SlipEstimatorConfig {
+    fn persist(&self, w: &mut ConfigWriter) {
+        w.put_u16(self.min_speed_rpm);
+        w.put_f32(self.max_accel_mps2);
+        w.put_f32(self.max_decel_mps2);
+        w.put_f32(self.imu_weight);
+    }
+
+    fn restore(r: &mut ConfigReader) -> Result<Self, DecodeError> {
+        Ok(SlipEstimatorConfig {
+            min_speed_rpm: r.get_u16()?,
+            max_accel_mps2: r.get_f32()?,
+            max_decel_mps2: r.get_f32()?,
+            imu_weight: r.get_f32()?,
+        })
+    }
+}
+
+impl Persist for CruiseControlConfig {
+    fn persist(&self, w: &mut ConfigWriter) {
+        w.put_f32(self.kp);
+        w.put_f32(self.ki);
+        w.put_f32(self.integral_limit);
+        w.put_f32(self.min_speed_kph);
+    }
+
+    fn restore(r: &mut ConfigReader) -> Result<Self, DecodeError> {
+        Ok(CruiseControlConfig {
+            kp: r.get_f32()?,
+            ki: r.get_f32()?,
+            integral_limit: r.get_f32()?,
+            min_speed_kph: r.get_f32()?,
+        })
+    }
+}
+
+impl Persist for WalkAssistConfig {
+    fn persist(&self, w: &mut ConfigWriter) {
+        w.put_percentage(self.command);
+        w.put_f32(self.max_speed_kph);
+        w.put_f32(self.taper_kph);
+    }
+
+    fn restore(r: &mut ConfigReader) -> Result<Self, DecodeError> {
+        Ok(WalkAssistConfig {
+            command: r.get_percentage()?,
+            max_speed_kph: r.get_f32()?,
+            taper_kph: r.get_f32()?,
+        })
+    }
+}
+
+impl Persist for ThermalDerateConfig {
+    fn persist(&self, w: &mut ConfigWriter) {
+        self.motor.persist(w);
+        self.controller.persist(w);
+    }
+
+    fn restore(r: &mut ConfigReader) -> Result<Self, DecodeError> {
+        Ok(ThermalDerateConfig {
+            motor: ThermalLimit::restore(r)?,
+            controller: ThermalLimit::restore(r)?,
+        })
+    }
+}
+
+impl Persist for ThermalLimit {
+    fn persist(&self, w: &mut ConfigWriter) {
+        w.put_f32(self.derate_start_c);
+        w.put_f32(self.cutoff_c);
+    }
+
+    fn restore(r: &mut ConfigReader) -> Result<Self, DecodeError> {
+        Ok(ThermalLimit {
+            derate_start_c: r.get_f32()?,
+            cutoff_c: r.get_f32()?,
+        })
+    }
+}
+
+impl Persist for PowerLimiterConfig {
+    fn persist(&self, w: &mut ConfigWriter) {
+        w.put_f32(self.max_current_a);
+        w.put_f32(self.min_voltage_v);
+        w.put_f32(self.current_gain);
+        w.put_f32(self.voltage_gain);
+        w.put_f32(self.release_rate);
+    }
+
+    fn restore(r: &mut ConfigReader) -> Result<Self, DecodeError> {
+        Ok(PowerLimiterConfig {
+            max_current_a: r.get_f32()?,
+            min_voltage_v: r.get_f32()?,
+            current_gain: r.get_f32()?,
+            voltage_gain: r.get_f32()?,
+            release_rate: r.get_f32()?,
+        })
+    }
+}
+
+impl Persist for RegenConfig {
+    fn persist(&self, w: &mut ConfigWriter) {
+        w.put_percentage(self.brake_start);
+        w.put_percentage(self.max_regen);
+        w.put_percentage(self.max_brake_slip);
+    }
+
+    fn restore(r: &mut ConfigReader) -> Result<Self, DecodeError> {
+        Ok(RegenConfig {
+            brake_start: r.get_percentage()?,
+            max_regen: r.get_percentage()?,
+            max_brake_slip: r.get_percentage()?,
+        })
+    }
+}
+
+impl Persist for PasConfig {
+    fn persist(&self, w: &mut ConfigWriter) {
+        for tier in self.tiers.iter() {
+            w.put_u16(tier.cadence.as_rpm());
+            w.put_percentage(tier.assist);
+        }
+    }
+
+    fn restore(r: &mut ConfigReader) -> Result<Self, DecodeError> {
+        let mut tiers = [PasTier {
+            cadence: Cadence::zero(),
+            assist: Percentage::zero(),
+        }; PAS_TIER_COUNT];
+        for tier in tiers.iter_mut() {
+            tier.cadence = Cadence::from_rpm(r.get_u16()?);
+            tier.assist = r.get_percentage()?;
+        }
+        Ok(PasConfig { tiers })
+    }
+}
+
+impl Persist for AbsConfig {
+    fn persist(&self, w: &mut ConfigWriter) {
+        w.put_percentage(self.brake_start);
+        w.put_percentage(self.release_slip);
+        w.put_percentage(self.reapply_slip);
+        w.put_f32(self.release_decel_rpm_s);
+        w.put_percentage(self.release_level);
+        w.put_u16(self.hold_time_ms);
+        w.put_f32(self.reapply_rate);
+        self.slip_estimator.persist(w);
+        w.put_f32(self.wheel_diameter_inch);
+    }
+
+    fn restore(r: &mut ConfigReader) -> Result<Self, DecodeError> {
+        Ok(AbsConfig {
+            brake_start: r.get_percentage()?,
+            release_slip: r.get_percentage()?,
+            reapply_slip: r.get_percentage()?,
+            release_decel_rpm_s: r.get_f32()?,
+            release_level: r.get_percentage()?,
+            hold_time_ms: r.get_u16()?,
+            reapply_rate: r.get_f32()?,
+            slip_estimator: SlipEstimatorConfig::restore(r)?,
+            wheel_diameter_inch: r.get_f32()?,
+        })
+    }
+}
+
+impl Persist for RideProfiles {
+    fn persist(&self, w: &mut ConfigWriter) {
+        w.put_u8(self.active.into());
+        self.eco.persist(w);
+        self.tour.persist(w);
+        self.sport.persist(w);
+        self.custom.persist(w);
+    }
+
+    fn restore(r: &mut ConfigReader) -> Result<Self, DecodeError> {
+        Ok(RideProfiles {
+            active: r.get_u8()?.try_into()?,
+            eco: RideProfile::restore(r)?,
+            tour: RideProfile::restore(r)?,
+            sport: RideProfile::restore(r)?,
+            custom: RideProfile::restore(r)?,
+        })
+    }
+}
+
+impl Persist for RideProfile {
+    fn persist(&self, w: &mut ConfigWriter) {
+        w.put_u8(self.throttle_map_mode.into());
+        w.put_u8(self.traction_control_mode.into());
+        w.put_percentage(self.desired_slip);
+        w.put_u8(self.speed_limit_class.into());
+        w.put_percentage(self.power_limit);
+    }
+
+    fn restore(r: &mut ConfigReader) -> Result<Self, DecodeError> {
+        Ok(RideProfile {
+            throttle_map_mode: r.get_u8()?.try_into()?,
+            traction_control_mode: r.get_u8()?.try_into()?,
+            desired_slip: r.get_percentage()?,
+            speed_limit_class: r.get_u8()?.try_into()?,
+            power_limit: r.get_percentage()?,
+        })
+    }
+}
+
+impl Persist for BatteryConfig {
+    fn persist(&self, w: &mut ConfigWriter) {
+        self.soc_estimator.persist(w);
+        w.put_duration(self.bms_timeout);
+    }
+
+    fn restore(r: &mut ConfigReader) -> Result<Self, DecodeError> {
+        Ok(BatteryConfig {
+            soc_estimator: SocEstimatorConfig::restore(r)?,
+            bms_timeout: r.get_duration()?,
+        })
+    }
+}
+
+impl Persist for SocEstimatorConfig {
+    fn persist(&self, w: &mut ConfigWriter) {
+        w.put_f32(self.capacity_ah);
+        w.put_f32(self.rest_current_a);
+        w.put_u64(self.rest_time_ms);
+        w.put_u64(self.correction_tau_ms);
+    }
+
+    fn restore(r: &mut ConfigReader) -> Result<Self, DecodeError> {
+        Ok(SocEstimatorConfig {
+            capacity_ah: r.get_f32()?,
+            rest_current_a: r.get_f32()?,
+            rest_time_ms: r.get_u64()?,
+            correction_tau_ms: r.get_u64()?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn payload_len_matches_serializer() {
+        // room to spare so a payload that has grown is measured rather than panicking
+        let mut data = [0u8; CONFIG_PAYLOAD_LEN + 64];
+        let mut writer = ConfigWriter::new(&mut data);
+        Config::default().persist(&mut writer);
+        assert_eq!(writer.pos, CONFIG_PAYLOAD_LEN);
+
+        let mut reader = ConfigReader::new(&data);
+        Config::restore(&mut reader).unwrap();
+        assert_eq!(reader.pos, CONFIG_PAYLOAD_LEN);
+    }
+
+    #[test]
+    fn record_round_trip() {
+        let mut config = Config::default();
+        config.engine.desired_slip = 0.123.into();
+        config.profiles.tour.power_limit = 0.42.into();
+        let record = config.to_record();
+
+        let restored = Config::from_record(&record).unwrap();
+        assert_eq!(restored.to_record(), record);
+        assert_eq!(restored.engine.desired_slip.to_fractional(), 0.123);
+        assert_eq!(restored.profiles.tour, config.profiles.tour);
+        assert_eq!(restored.engine.throttle_table, config.engine.throttle_table);
+    }
+
+    #[test]
+    fn corrupt_record_is_rejected() {
+        let record = Config::default().to_record();
+
+        let mut flipped = record;
+        flipped[100] ^= 0x01;
+        assert_eq!(
+            Config::from_record(&flipped).err(),
+            Some(DecodeError::CrcMismatch)
+        );
+
+        let mut old = record;
+        old[0] = CONFIG_VERSION - 1;
+        assert_eq!(
+            Config::from_record(&old).err(),
+            Some(DecodeError::InvalidValue {
+                field: "Config::version",
+                value: CONFIG_VERSION - 1
+            })
+        );
+
+        assert_eq!(
+            Config::from_record(&record[..CONFIG_RECORD_LEN - 1]).err(),
+            Some(DecodeError::Truncated {
+                expected: CONFIG_RECORD_LEN,
+                actual: CONFIG_RECORD_LEN - 1
+            })
+        );
+    }
+}
@@ -0,0 +1,231 @@
+use crate::{
+    messages::messages::Message,
+    operations::{
+        message_timeout::MessageTimeout,
+        soc_estimator::{SocEstimator, SocEstimatorConfig},
+    },
+    utils::{
+        percentage::Percentage,
+        time::{Duration, Timestamp},
+    },
+};
+
+#[derive(Debug, Clone, Copy)]
+pub struct BatteryConfig {
+    pub soc_estimator: SocEstimatorConfig,
+    pub bms_timeout: Duration,
+}
+
+impl Default for BatteryConfig {
+    fn default() -> Self {
+        BatteryConfig {
+            soc_estimator: SocEstimatorConfig::default(),
+            bms_timeout: Duration::from_millis(1000),
+        }
+    }
+}
+
+// latest BMS readings, None once a frame goes stale
+#[derive(Debug, Clone, Copy, Default)]
+pub struct BatteryState {
+    pub pack_voltage_v: Option<f32>,
+    pub pack_current_a: Option<f32>,
+    pub cell_min_v: Option<f32>,
+    pub cell_max_v: Option<f32>,
+    pub temperature_c: Option<f32>,
+    // last estimate is kept through a BMS dropout
+    pub soc: Option<Percentage>,
+}
+
+// Follows the BMS frames on the bus and keeps the state of charge estimate, shared by
+// both controllers so they agree on what the pack is doing
+pub struct BatteryMonitor {
+    config: BatteryConfig,
+    state: BatteryState,
+    soc_estimator: SocEstimator,
+    pack_timeout: MessageTimeout,
+    cell_timeout: MessageTimeout,
+}
+
+impl BatteryMonitor {
+    pub fn new(config: BatteryConfig) -> Self {
+        BatteryMonitor {
+            config,
+            state: BatteryState::default(),
+            soc_estimator: SocEstimator::new(config.soc_estimator),
+            pack_timeout: MessageTimeout::new(),
+            cell_timeout: MessageTimeout::new(),
+        }
+    }
+
+    pub fn update_config(&mut self, config: BatteryConfig) {
+        self.config = config;
+        self.soc_estimator.update_config(config.soc_estimator);
+    }
+
+    // returns true when the message was a BMS frame
+    pub fn process_message(&mut self, msg: &Message) -> bool {
+        match msg {
+            Message::BmsPackMessage(msg) => {
+                self.state.pack_voltage_v = Some(msg.pack_voltage_v);
+                self.state.pack_current_a = Some(msg.pack_current_a);
+                self.pack_timeout.received();
+                true
+            }
+            Message::BmsCellMessage(msg) => {
+                self.state.cell_min_v = Some(msg.cell_min_v);
+                self.state.cell_max_v = Some(msg.cell_max_v);
+                self.state.temperature_c = Some(msg.temperature_c);
+                self.cell_timeout.received();
+                true
+            }
+            _ => false,
+        }
+    }
+
+    pub fn run(&mut self, timestamp: Timestamp) -> BatteryState {
+        if self.pack_timeout.update(timestamp, self.config.bms_timeout) {
+            self.state.pack_voltage_v = None;
+            self.state.pack_current_a = None;
+        }
+        if self.cell_timeout.update(timestamp, self.config.bms_timeout) {
+            self.state.cell_min_v = None;
+            self.state.cell_max_v = None;
+            self.state.temperature_c = None;
+        }
+
+        match (self.state.pack_current_a, self.state.cell_min_v) {
+            (Some(current_a), Some(cell_min_v)) => {
+                self.state.soc = Some(
+                    self.soc_estimator
+                        .run_algo(timestamp, current_a, cell_min_v),
+                );
+            }
+            _ => self.soc_estimator.hold(),
+        }
+        self.state
+    }
+
+    pub fn state(&self) -> BatteryState {
+        self.state
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::messages::messages::bms::{BmsCellMessage, BmsPackMessage};
+
+    const STEP_MS: u64 = 100;
+
+    struct Bench {
+        battery: BatteryMonitor,
+        now_ms: u64,
+    }
+
+    impl Bench {
+        fn new() -> Self {
+            Bench {
+                battery: BatteryMonitor::new(BatteryConfig::default()),
+                now_ms: 0,
+            }
+        }
+
+        fn send(&mut self, pack_current_a: f32, cell_min_v: f32) {
+            assert!(
+                self.battery
+                    .process_message(&Message::BmsPackMessage(BmsPackMessage {
+                        pack_voltage_v: cell_min_v * 13.0,
+                        pack_current_a,
+                    }))
+            );
+            assert!(
+                self.battery
+                    .process_message(&Message::BmsCellMessage(BmsCellMessage {
+                        cell_min_v,
+                        cell_max_v: cell_min_v + 0.01,
+                        temperature_c: 25.0,
+                    }))
+            );
+        }
+
+        fn run(&mut self) -> BatteryState {
+            let state = self.battery.run(Timestamp::from_micros(self.now_ms * 1000));
+            self.now_ms += STEP_MS;
+            state
+        }
+    }
+
+    fn soc(state: &BatteryState) -> f32 {
+        state.soc.unwrap().to_fractional()
+    }
+
+    #[test]
+    fn no_soc_before_the_bms_reports() {
+        let mut bench = Bench::new();
+        assert!(bench.run().soc.is_none());
+    }
+
+    #[test]
+    fn follows_the_bms_frames() {
+        let mut bench = Bench::new();
+        bench.send(5.0, 3.80);
+        let state = bench.run();
+        assert_eq!(state.pack_current_a, Some(5.0));
+        assert_eq!(state.cell_min_v, Some(3.80));
+        assert!((soc(&state) - 0.5).abs() < 1e-4);
+
+        for _ in 0..10 {
+            bench.send(14.0, 3.80);
+            bench.run();
+        }
+        // 14 A for a second out of a 14 Ah pack
+        assert!((soc(&bench.battery.state()) - (0.5 - 1.0 / 3600.0)).abs() < 1e-5);
+    }
+
+    #[test]
+    fn dropout_keeps_soc_and_skips_the_gap() {
+        let mut bench = Bench::new();
+        bench.send(14.0, 3.80);
+        bench.run();
+
+        // silent past the timeout, the readings go stale but the estimate stays
+        for _ in 0..600 {
+            bench.run();
+        }
+        let state = bench.battery.state();
+        assert!(state.pack_current_a.is_none());
+        assert!(state.cell_min_v.is_none());
+        // only the second before the timeout was counted, not the whole minute
+        let held = soc(&state);
+        assert!(held < 0.5 && held > 0.5 - 2.0 / 3600.0);
+
+        // and the gap is not counted when the BMS comes back
+        bench.send(14.0, 3.80);
+        let state = bench.run();
+        assert!((soc(&state) - held).abs() < 1e-6);
+    }
+
+    #[test]
+    fn one_stale_frame_holds_the_estimate() {
+        let mut bench = Bench::new();
+        bench.send(14.0, 3.80);
+        bench.run();
+        let mut held = None;
+        for _ in 0..30 {
+            bench
+                .battery
+                .process_message(&Message::BmsPackMessage(BmsPackMessage {
+                    pack_voltage_v: 49.4,
+                    pack_current_a: 14.0,
+                }));
+            let state = bench.run();
+            if state.cell_min_v.is_none() {
+                // fresh current alone does not move the estimate
+                let soc = soc(&state);
+                assert!((soc - *held.get_or_insert(soc)).abs() < 1e-6);
+            }
+        }
+        assert!(held.is_some());
+    }
+}
@@ -2,9 +2,21 @@ use core::ops::{Deref, DerefMut};
 use core::time;
 
 use crate::config::config::ConfigDelta;
+use crate::config::profile::RideProfileId;
+use crate::config::store::CONFIG_RECORD_LEN;
+use crate::controllers::battery::{BatteryMonitor, BatteryState};
+use crate::controllers::mcu::McuOperatingState;
+use crate::messages::e2e::E2eHeader;
+use crate::messages::error::DecodeError;
 use crate::messages::messages::control_req::ControlReqMessage;
+use crate::messages::messages::cruise::CruiseMessage;
+use crate::messages::messages::profile::ProfileMessage;
 use crate::messages::messages::tire_status::TireStatus;
 use crate::operations::config_updater::{ConfigUpdateState, ConfigUpdater};
+use crate::operations::cruise_control::CruiseCommand;
+use crate::operations::message_timeout::MessageTimeout;
+use crate::operations::power_limiter::PowerLimitReason;
+use crate::storage::storage::{Storage, StorageError, StorageKey, crc16};
 use crate::{
     config::config::Config,
     controllers::shared::Lockable,
@@ -16,7 +28,7 @@ use crate::{
     utils::{
         parts::Wheel,
         percentage::Percentage,
-        speed::WheelSpeed,
+        speed::{GroundSpeed, WheelSpeed},
         time::{Duration, Timestamp},
     },
 };
@@ -27,6 +39,11 @@ pub struct FcuConfig {
     pub ctl_poll: Duration,
     pub update_poll: Duration,
     pub display_poll: Duration,
+    pub ws_timeout: Duration,
+    // config changes are batched up and written back this often
+    pub config_save_poll: Duration,
+    pub trip: TripConfig,
+    pub odometer: OdometerConfig,
 }
 
 impl Default for FcuConfig {
@@ -36,16 +53,373 @@ impl Default for FcuConfig {
             ctl_poll: Duration::from_millis(15),
             update_poll: Duration::from_millis(500),
             display_poll: Duration::from_millis(100),
+            ws_timeout: Duration::from_millis(500),
+            config_save_poll: Duration::from_millis(5000),
+            trip: TripConfig::default(),
+            odometer: OdometerConfig::default(),
         }
     }
 }
 
+// distance the consumption average is taken over, in buckets so old riding drops out
+const TRIP_WINDOW_BUCKETS: usize = 10;
+
+#[derive(Debug, Clone, Copy)]
+pub struct TripConfig {
+    pub bucket_km: f32,
+    // consumption isn't shown until this much of the window is filled
+    pub min_window_km: f32,
+    // usable pack energy, 14 Ah at 48 V nominal
+    pub pack_capacity_wh: f32,
+    // samples further apart than this are a gap, not riding
+    pub max_step: Duration,
+}
+
+impl Default for TripConfig {
+    fn default() -> Self {
+        TripConfig {
+            bucket_km: 0.5,
+            min_window_km: 0.5,
+            pack_capacity_wh: 672.0,
+            max_step: Duration::from_millis(2000),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TripStats {
+    pub distance_km: f32,
+    pub energy_wh: f32,
+    pub wh_per_km: Option<f32>,
+    pub range_km: Option<f32>,
+}
+
+pub struct TripComputer {
+    config: TripConfig,
+    stats: TripStats,
+    // (km, Wh) per bucket, the current one is still filling
+    window: [(f32, f32); TRIP_WINDOW_BUCKETS],
+    current_bucket: usize,
+    prev_timestamp: Option<Timestamp>,
+}
+
+impl TripComputer {
+    pub fn new(config: TripConfig) -> Self {
+        TripComputer {
+            config,
+            stats: TripStats::default(),
+            window: [(0.0, 0.0); TRIP_WINDOW_BUCKETS],
+            current_bucket: 0,
+            prev_timestamp: None,
+        }
+    }
+
+    fn push_window(&mut self, km: f32, wh: f32) {
+        let bucket = &mut self.window[self.current_bucket];
+        bucket.0 += km;
+        bucket.1 += wh;
+        if bucket.0 >= self.config.bucket_km {
+            self.current_bucket = (self.current_bucket + 1) % TRIP_WINDOW_BUCKETS;
+            self.window[self.current_bucket] = (0.0, 0.0);
+        }
+    }
+
+    fn window_wh_per_km(&self) -> Option<f32> {
+        let (km, wh) = self.window.iter().fold((0.0, 0.0), |(km, wh), bucket| {
+            (km + bucket.0, wh + bucket.1)
+        });
+        if km >= self.config.min_window_km && km > 0.0 {
+            Some(wh / km)
+        } else {
+            None
+        }
+    }
+
+    pub fn run_algo(
+        &mut self,
+        curr_time: Timestamp,
+        speed: Option<GroundSpeed>,
+        pack_voltage_v: Option<f32>,
+        pack_current_a: Option<f32>,
+        soc: Option<Percentage>,
+    ) -> TripStats {
+        let dt_ms = self
+            .prev_timestamp
+            .map(|prev_time| curr_time.as_micros().saturating_sub(prev_time.as_micros()) / 1000);
+        self.prev_timestamp = Some(curr_time);
+
+        if let Some(dt_ms) = dt_ms
+            && dt_ms <= self.config.max_step.as_millis()
+        {
+            let dt_h = dt_ms as f32 / 3_600_000.0;
+            let km = speed.map_or(0.0, |speed| speed.kph() * dt_h);
+            // regen comes back off the energy used
+            let wh = match (pack_voltage_v, pack_current_a) {
+                (Some(voltage_v), Some(current_a)) => voltage_v * current_a * dt_h,
+                _ => 0.0,
+            };
+            self.stats.distance_km += km;
+            self.stats.energy_wh += wh;
+            self.push_window(km, wh);
+        }
+
+        self.stats.wh_per_km = self.window_wh_per_km();
+        self.stats.range_km = match (self.stats.wh_per_km, soc) {
+            (Some(wh_per_km), Some(soc)) if wh_per_km > 0.0 => {
+                Some(soc.to_fractional() * self.config.pack_capacity_wh / wh_per_km)
+            }
+            _ => None,
+        };
+        self.stats
+    }
+
+    pub fn stats(&self) -> TripStats {
+        self.stats
+    }
+
+    pub fn reset(&mut self) {
+        self.stats = TripStats::default();
+        self.window = [(0.0, 0.0); TRIP_WINDOW_BUCKETS];
+        self.current_bucket = 0;
+        self.prev_timestamp = None;
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum TripMeter {
+    A(),
+    B(),
+}
+
+impl From<TripMeter> for u8 {
+    fn from(trip: TripMeter) -> u8 {
+        match trip {
+            TripMeter::A() => 0,
+            TripMeter::B() => 1,
+        }
+    }
+}
+
+impl TryFrom<u8> for TripMeter {
+    type Error = DecodeError;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(TripMeter::A()),
+            1 => Ok(TripMeter::B()),
+            _ => Err(DecodeError::InvalidValue {
+                field: "TripMeter",
+                value,
+            }),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct OdometerConfig {
+    // write back after this much new distance or ride time, or as soon as the bike stops
+    pub save_every_m: u32,
+    pub save_every_s: u32,
+    // ride time only counts while faster than this
+    pub moving_kph: f32,
+    // samples further apart than this are a gap, not riding
+    pub max_step: Duration,
+}
+
+impl Default for OdometerConfig {
+    fn default() -> Self {
+        OdometerConfig {
+            save_every_m: 100,
+            save_every_s: 60,
+            moving_kph: 2.0,
+            max_step: Duration::from_millis(2000),
+        }
+    }
+}
+
+// what is kept across power cycles, whole metres and seconds so a long odometer
+// doesn't lose small steps to float rounding
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct OdometerRecord {
+    pub odometer_m: u32,
+    pub trip_a_m: u32,
+    pub trip_b_m: u32,
+    pub ride_time_s: u32,
+}
+
+impl OdometerRecord {
+    const VERSION: u8 = 1;
+    pub const LEN: usize = 19;
+
+    pub fn to_bytes(&self) -> [u8; Self::LEN] {
+        let mut data = [0u8; Self::LEN];
+        data[0] = Self::VERSION;
+        data[1..5].copy_from_slice(&self.odometer_m.to_le_bytes());
+        data[5..9].copy_from_slice(&self.trip_a_m.to_le_bytes());
+        data[9..13].copy_from_slice(&self.trip_b_m.to_le_bytes());
+        data[13..17].copy_from_slice(&self.ride_time_s.to_le_bytes());
+        let crc = crc16(&data[..17]);
+        data[17..19].copy_from_slice(&crc.to_le_bytes());
+        data
+    }
+
+    pub fn trip_m(&self, trip: TripMeter) -> u32 {
+        match trip {
+            TripMeter::A() => self.trip_a_m,
+            TripMeter::B() => self.trip_b_m,
+        }
+    }
+}
+
+impl TryFrom<&[u8]> for OdometerRecord {
+    type Error = DecodeError;
+
+    fn try_from(data: &[u8]) -> Result<Self, Self::Error> {
+        DecodeError::check_len(data, Self::LEN)?;
+        if data[0] != Self::VERSION {
+            return Err(DecodeError::InvalidValue {
+                field: "OdometerRecord::version",
+                value: data[0],
+            });
+        }
+        if crc16(&data[..17]) != u16::from_le_bytes([data[17], data[18]]) {
+            return Err(DecodeError::CrcMismatch);
+        }
+        let word = |idx: usize| {
+            u32::from_le_bytes([data[idx], data[idx + 1], data[idx + 2], data[idx + 3]])
+        };
+        Ok(Self {
+            odometer_m: word(1),
+            trip_a_m: word(5),
+            trip_b_m: word(9),
+            ride_time_s: word(13),
+        })
+    }
+}
+
+pub struct Odometer {
+    config: OdometerConfig,
+    record: OdometerRecord,
+    // partial metres and milliseconds carried between runs
+    distance_rem_m: f32,
+    time_rem_ms: u64,
+    unsaved_m: u32,
+    unsaved_s: u32,
+    // write at the next chance whatever the distance, for stops and trip resets
+    save_requested: bool,
+    dirty: bool,
+    moving: bool,
+    prev_timestamp: Option<Timestamp>,
+}
+
+impl Odometer {
+    pub fn new(config: OdometerConfig) -> Self {
+        Odometer {
+            config,
+            record: OdometerRecord::default(),
+            distance_rem_m: 0.0,
+            time_rem_ms: 0,
+            unsaved_m: 0,
+            unsaved_s: 0,
+            save_requested: false,
+            dirty: false,
+            moving: false,
+            prev_timestamp: None,
+        }
+    }
+
+    pub fn load(&mut self, record: OdometerRecord) {
+        self.record = record;
+        self.mark_saved();
+    }
+
+    pub fn record(&self) -> OdometerRecord {
+        self.record
+    }
+
+    pub fn reset_trip(&mut self, trip: TripMeter) {
+        match trip {
+            TripMeter::A() => self.record.trip_a_m = 0,
+            TripMeter::B() => self.record.trip_b_m = 0,
+        }
+        // a reset is written straight away so it survives a power cut
+        self.dirty = true;
+        self.save_requested = true;
+    }
+
+    pub fn run_algo(&mut self, curr_time: Timestamp, speed: Option<GroundSpeed>) -> OdometerRecord {
+        let dt_ms = self
+            .prev_timestamp
+            .map(|prev_time| curr_time.as_micros().saturating_sub(prev_time.as_micros()) / 1000);
+        self.prev_timestamp = Some(curr_time);
+        let dt_ms = match dt_ms {
+            Some(dt_ms) if dt_ms <= self.config.max_step.as_millis() => dt_ms,
+            _ => return self.record,
+        };
+
+        let kph = speed.map_or(0.0, |speed| speed.kph());
+        let was_moving = self.moving;
+        self.moving = kph > self.config.moving_kph;
+        if was_moving && !self.moving && self.dirty {
+            // stopped, good moment to write what we have
+            self.save_requested = true;
+        }
+
+        self.distance_rem_m += kph / 3.6 * dt_ms as f32 / 1000.0;
+        let whole_m = self.distance_rem_m as u32;
+        if whole_m > 0 {
+            self.distance_rem_m -= whole_m as f32;
+            self.record.odometer_m = self.record.odometer_m.saturating_add(whole_m);
+            self.record.trip_a_m = self.record.trip_a_m.saturating_add(whole_m);
+            self.record.trip_b_m = self.record.trip_b_m.saturating_add(whole_m);
+            self.unsaved_m = self.unsaved_m.saturating_add(whole_m);
+            self.dirty = true;
+        }
+
+        if self.moving {
+            self.time_rem_ms += dt_ms;
+            let whole_s = (self.time_rem_ms / 1000) as u32;
+            if whole_s > 0 {
+                self.time_rem_ms -= whole_s as u64 * 1000;
+                self.record.ride_time_s = self.record.ride_time_s.saturating_add(whole_s);
+                self.unsaved_s = self.unsaved_s.saturating_add(whole_s);
+                self.dirty = true;
+            }
+        }
+        self.record
+    }
+
+    pub fn needs_save(&self) -> bool {
+        self.dirty
+            && (self.save_requested
+                || self.unsaved_m >= self.config.save_every_m
+                || self.unsaved_s >= self.config.save_every_s)
+    }
+
+    pub fn mark_saved(&mut self) {
+        self.unsaved_m = 0;
+        self.unsaved_s = 0;
+        self.save_requested = false;
+        self.dirty = false;
+    }
+}
+
 #[derive(Debug, Clone, Copy)]
 pub struct FcuState {
     pub throttle_req: Percentage,
     pub brake_req: Percentage,
+    pub walk_assist: bool,
     pub update: ConfigUpdateState,
     pub cur_ws: Option<WheelSpeed>,
+    pub mcu_state: Option<McuOperatingState>,
+    pub cruise_target: Option<GroundSpeed>,
+    pub profile: RideProfileId,
+    pub derate: Percentage,
+    pub battery: BatteryState,
+    pub limit_reason: PowerLimitReason,
+    pub trip: TripStats,
+    pub odometer: OdometerRecord,
 }
 
 impl Default for FcuState {
@@ -53,66 +427,582 @@ impl Default for FcuState {
         FcuState {
             throttle_req: Percentage::zero(),
             brake_req: Percentage::zero(),
+            walk_assist: false,
             update: ConfigUpdateState::default(),
             cur_ws: None,
+            mcu_state: None,
+            cruise_target: None,
+            profile: RideProfileId::Custom(),
+            derate: Percentage::zero(),
+            battery: BatteryState::default(),
+            limit_reason: PowerLimitReason::None(),
+            trip: TripStats::default(),
+            odometer: OdometerRecord::default(),
         }
     }
 }
 
 pub struct FcuController {
     pub config: Config,
+    // set when the config changes, cleared once it has been handed out to save
+    config_changed: bool,
     state: FcuState,
     config_updater: ConfigUpdater,
+    battery: BatteryMonitor,
+    trip_computer: TripComputer,
+    odometer: Odometer,
+    front_ws_timeout: MessageTimeout,
+    ctl_counter: u8,
 }
 
 impl FcuController {
     pub fn new(config: Config) -> Self {
         FcuController {
             config,
-            state: FcuState::default(),
+            config_changed: false,
+            state: FcuState {
+                profile: config.profiles.active,
+                ..FcuState::default()
+            },
             config_updater: ConfigUpdater::new(),
+            battery: BatteryMonitor::new(config.battery),
+            trip_computer: TripComputer::new(config.fcu.trip),
+            odometer: Odometer::new(config.fcu.odometer),
+            front_ws_timeout: MessageTimeout::new(),
+            ctl_counter: 0,
         }
     }
 
-    pub fn process_message(&mut self, msg: Message) {
+    pub fn process_message(&mut self, msg: Message) -> Result<(), DecodeError> {
         match msg {
             Message::ConfigMessage(req) => {
+                let before = self.config.to_record();
                 self.config.apply_delta(req);
+                self.note_config_change(before);
             }
             Message::UpdateMessage(req) => {
-                req.update(&mut self.config);
+                let before = self.config.to_record();
+                req.update(&mut self.config)?;
+                self.state.profile = self.config.profiles.active;
+                self.note_config_change(before);
+            }
+            Message::ProfileMessage(msg) => {
+                let before = self.config.to_record();
+                self.config.select_profile(msg.profile);
+                self.state.profile = msg.profile;
+                self.note_config_change(before);
+            }
+            Message::TireStatusMessage(TireStatus {
+                wheel: Wheel::Front,
+                ws,
+            }) => {
+                self.state.cur_ws = Some(ws);
+                self.front_ws_timeout.received();
+            }
+            Message::TripResetMessage(msg) => {
+                self.odometer.reset_trip(msg.trip);
+                self.state.odometer = self.odometer.record();
+                // the trip computer follows trip A, the one a rider clears per ride
+                if msg.trip == TripMeter::A() {
+                    self.trip_computer.reset();
+                    self.state.trip = self.trip_computer.stats();
+                }
+            }
+            Message::BmsPackMessage(_) | Message::BmsCellMessage(_) => {
+                self.battery.process_message(&msg);
+            }
+            Message::EcuMessage(msg) => {
+                self.state.mcu_state = Some(msg.state);
+                self.state.cruise_target = msg.cruise_target;
+                self.state.derate = msg.derate;
+                self.state.limit_reason = msg.limit_reason;
             }
             _ => {}
         }
+        Ok(())
     }
 
     pub fn run_config_update(&mut self, state: ConfigUpdateState) -> Option<Message> {
         if state != self.state.update {
+            let prev = self.state.update;
             self.state.update = state;
-            Some(self.config_updater.run(state))
+            let msg = self.config_updater.run(prev, state)?;
+            // keep our copy in step so the display shows the custom profile
+            let before = self.config.to_record();
+            if let Message::UpdateMessage(req) = msg
+                && req.update(&mut self.config).is_ok()
+            {
+                self.state.profile = self.config.profiles.active;
+                self.note_config_change(before);
+            }
+            Some(msg)
         } else {
             None
         }
     }
 
-    pub fn broadcast_ctl(&mut self, throttle: Percentage, brake: Percentage) -> Message {
+    pub fn broadcast_ctl(
+        &mut self,
+        throttle: Percentage,
+        brake: Percentage,
+        walk_assist: bool,
+    ) -> Message {
         self.state.brake_req = brake;
         self.state.throttle_req = throttle;
+        self.state.walk_assist = walk_assist;
+        self.ctl_counter = self.ctl_counter.wrapping_add(1);
         Message::ControlReqMessage(ControlReqMessage {
             throttle_req: throttle,
             brake_req: brake,
+            walk_assist,
+            e2e: E2eHeader::new(self.ctl_counter),
         })
     }
 
+    // the cruise button toggles, the MCU reports back whether it latched
+    pub fn cruise_button(&mut self) -> Message {
+        let command = if self.state.cruise_target.is_some() {
+            CruiseCommand::Cancel()
+        } else {
+            CruiseCommand::Set()
+        };
+        Message::CruiseMessage(CruiseMessage { command })
+    }
+
+    // the profile button steps through the profiles in order
+    pub fn profile_button(&mut self) -> Message {
+        let profile = self.state.profile.next();
+        let before = self.config.to_record();
+        self.config.select_profile(profile);
+        self.state.profile = profile;
+        self.note_config_change(before);
+        Message::ProfileMessage(ProfileMessage { profile })
+    }
+
     pub fn broadcast_wheel(&mut self, ws: WheelSpeed) -> Message {
         self.state.cur_ws = Some(ws);
+        self.front_ws_timeout.received();
         Message::TireStatusMessage(TireStatus {
             wheel: Wheel::Front,
             ws: ws,
         })
     }
 
+    // battery and trip telemetry, run off the display loop
+    pub fn run_telemetry(&mut self, timestamp: Timestamp) {
+        self.state.battery = self.battery.run(timestamp);
+        if self
+            .front_ws_timeout
+            .update(timestamp, self.config.fcu.ws_timeout)
+        {
+            self.state.cur_ws = None;
+        }
+        let speed = self
+            .state
+            .cur_ws
+            .map(|ws| GroundSpeed::from_wheel_speed(ws, self.config.engine.wheel_diameter_inch));
+        self.state.trip = self.trip_computer.run_algo(
+            timestamp,
+            speed,
+            self.state.battery.pack_voltage_v,
+            self.state.battery.pack_current_a,
+            self.state.battery.soc,
+        );
+        self.state.odometer = self.odometer.run_algo(timestamp, speed);
+    }
+
+    // a missing record is a fresh unit, a corrupt one starts from zero and is reported
+    pub fn load_odometer<S: Storage>(
+        &mut self,
+        storage: &mut S,
+    ) -> Result<(), StorageError<S::Error>> {
+        let mut buf = [0u8; OdometerRecord::LEN];
+        let len = storage
+            .read(StorageKey::Odometer(), &mut buf)
+            .map_err(StorageError::Backend)?;
+        if let Some(len) = len {
+            let record = OdometerRecord::try_from(&buf[..len]).map_err(StorageError::Corrupt)?;
+            self.odometer.load(record);
+            self.state.odometer = record;
+        }
+        Ok(())
+    }
+
+    // only touches storage when enough has changed, returns whether it wrote
+    pub fn save_odometer<S: Storage>(&mut self, storage: &mut S) -> Result<bool, S::Error> {
+        if !self.odometer.needs_save() {
+            return Ok(false);
+        }
+        storage.write(StorageKey::Odometer(), &self.odometer.record().to_bytes())?;
+        self.odometer.mark_saved();
+        Ok(true)
+    }
+
+    // the MCU resends its config on a timer, only a change to what's stored is worth a write
+    fn note_config_change(&mut self, before: [u8; CONFIG_RECORD_LEN]) {
+        if self.config.to_record() != before {
+            self.config_changed = true;
+        }
+    }
+
+    // the config to write back if it changed since the last call
+    pub fn take_changed_config(&mut self) -> Option<Config> {
+        if self.config_changed {
+            self.config_changed = false;
+            Some(self.config)
+        } else {
+            None
+        }
+    }
+
     pub fn update_user_display(&self) -> FcuState {
         self.state
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::{
+        messages::messages::trip::TripResetMessage, operations::throttle_map::ThottleMapMode,
+    };
+
+    fn fcu() -> FcuController {
+        FcuController::new(Config::default())
+    }
+
+    // samples every 100 ms, so 36 kph covers a metre a step
+    const TRIP_STEP_MS: u64 = 100;
+    const PACK_V: f32 = 50.0;
+
+    struct TripBench {
+        trip: TripComputer,
+        now_ms: u64,
+    }
+
+    impl TripBench {
+        fn new() -> Self {
+            TripBench {
+                trip: TripComputer::new(TripConfig::default()),
+                now_ms: 0,
+            }
+        }
+
+        fn step(&mut self, kph: f32, current_a: f32, soc: Option<f32>) -> TripStats {
+            self.now_ms += TRIP_STEP_MS;
+            self.trip.run_algo(
+                Timestamp::from_micros(self.now_ms * 1000),
+                Some(GroundSpeed::from_kph(kph)),
+                Some(PACK_V),
+                Some(current_a),
+                soc.map(Percentage::from_fractional),
+            )
+        }
+
+        // at 36 kph, so the energy per km is current * PACK_V / 36
+        fn ride_km(&mut self, km: f32, current_a: f32) -> TripStats {
+            let mut stats = self.trip.stats();
+            for _ in 0..(km * 1000.0).round() as u32 {
+                stats = self.step(36.0, current_a, None);
+            }
+            stats
+        }
+    }
+
+    fn assert_near(actual: f32, expected: f32, tolerance: f32) {
+        assert!(
+            (actual - expected).abs() < tolerance,
+            "expected {} got {}",
+            expected,
+            actual
+        );
+    }
+
+    #[test]
+    fn trip_integrates_distance_and_energy() {
+        let mut bench = TripBench::new();
+        // the first sample only sets the clock
+        let stats = bench.ride_km(0.1, 10.0);
+        assert_near(stats.distance_km, 0.099, 1e-4);
+        // 50 V at 10 A for 9.9 s
+        assert_near(stats.energy_wh, 500.0 * 9.9 / 3600.0, 1e-3);
+    }
+
+    #[test]
+    fn consumption_waits_for_min_window() {
+        let mut bench = TripBench::new();
+        let min_window_km = TripConfig::default().min_window_km;
+        let stats = bench.ride_km(min_window_km - 0.01, 18.0);
+        assert!(stats.wh_per_km.is_none());
+
+        // 50 V at 18 A over 36 km per hour
+        let stats = bench.ride_km(0.02, 18.0);
+        assert_near(stats.wh_per_km.unwrap(), 25.0, 0.1);
+    }
+
+    #[test]
+    fn gap_is_not_counted_as_riding() {
+        let mut bench = TripBench::new();
+        let before = bench.ride_km(0.1, 10.0);
+        bench.now_ms += TripConfig::default().max_step.as_millis() + TRIP_STEP_MS;
+        let after = bench.step(36.0, 10.0, None);
+        assert_eq!(after.distance_km, before.distance_km);
+        assert_eq!(after.energy_wh, before.energy_wh);
+
+        // and it picks up again from the new sample
+        assert!(bench.step(36.0, 10.0, None).distance_km > before.distance_km);
+    }
+
+    #[test]
+    fn old_riding_rolls_out_of_the_window() {
+        let mut bench = TripBench::new();
+        let config = TripConfig::default();
+        let window_km = config.bucket_km * TRIP_WINDOW_BUCKETS as f32;
+        assert_near(bench.ride_km(window_km, 36.0).wh_per_km.unwrap(), 50.0, 0.5);
+
+        // a window and a bucket of gentle riding pushes the hard pull out entirely
+        let stats = bench.ride_km(window_km + config.bucket_km, 7.2);
+        assert_near(stats.wh_per_km.unwrap(), 10.0, 0.05);
+        // the trip totals keep everything
+        assert_near(stats.distance_km, 2.0 * window_km + config.bucket_km, 0.01);
+    }
+
+    #[test]
+    fn range_from_soc_and_consumption() {
+        let mut bench = TripBench::new();
+        bench.ride_km(1.0, 14.4);
+        // 20 Wh/km with half of 672 Wh left
+        let stats = bench.step(36.0, 14.4, Some(0.5));
+        assert_near(stats.range_km.unwrap(), 16.8, 0.1);
+
+        // no SoC, no range
+        assert!(bench.step(36.0, 14.4, None).range_km.is_none());
+    }
+
+    #[test]
+    fn no_range_while_regen_outweighs_use() {
+        let mut bench = TripBench::new();
+        let stats = bench.ride_km(1.0, -5.0);
+        assert!(stats.wh_per_km.unwrap() < 0.0);
+        assert!(bench.step(36.0, -5.0, Some(0.5)).range_km.is_none());
+    }
+
+    struct OdometerBench {
+        odometer: Odometer,
+        now_ms: u64,
+    }
+
+    impl OdometerBench {
+        fn new() -> Self {
+            let mut odometer = Odometer::new(OdometerConfig::default());
+            // the first sample only sets the clock
+            odometer.run_algo(Timestamp::from_micros(0), None);
+            OdometerBench {
+                odometer,
+                now_ms: 0,
+            }
+        }
+
+        fn ride(&mut self, kph: f32, step_ms: u64, steps: u32) -> OdometerRecord {
+            for _ in 0..steps {
+                self.now_ms += step_ms;
+                self.odometer.run_algo(
+                    Timestamp::from_micros(self.now_ms * 1000),
+                    Some(GroundSpeed::from_kph(kph)),
+                );
+            }
+            self.odometer.record()
+        }
+    }
+
+    fn odometer_record() -> OdometerRecord {
+        OdometerRecord {
+            odometer_m: 123_456,
+            trip_a_m: 7_890,
+            trip_b_m: 42,
+            ride_time_s: 86_400,
+        }
+    }
+
+    #[test]
+    fn odometer_record_round_trips() {
+        let record = odometer_record();
+        let data = record.to_bytes();
+        assert_eq!(OdometerRecord::try_from(&data[..]), Ok(record));
+    }
+
+    #[test]
+    fn odometer_record_rejects_corruption() {
+        let mut data = odometer_record().to_bytes();
+        data[3] ^= 0x01;
+        assert_eq!(
+            OdometerRecord::try_from(&data[..]),
+            Err(DecodeError::CrcMismatch)
+        );
+
+        let mut data = odometer_record().to_bytes();
+        data[0] = 0;
+        assert_eq!(
+            OdometerRecord::try_from(&data[..]),
+            Err(DecodeError::InvalidValue {
+                field: "OdometerRecord::version",
+                value: 0
+            })
+        );
+
+        let data = odometer_record().to_bytes();
+        assert_eq!(
+            OdometerRecord::try_from(&data[..OdometerRecord::LEN - 1]),
+            Err(DecodeError::Truncated {
+                expected: OdometerRecord::LEN,
+                actual: OdometerRecord::LEN - 1
+            })
+        );
+    }
+
+    #[test]
+    fn odometer_carries_part_metres() {
+        let mut bench = OdometerBench::new();
+        // 0.35 m a step at walking pace, well clear of whole metres
+        assert_eq!(bench.ride(3.6, 350, 2).odometer_m, 0);
+        assert_eq!(bench.ride(3.6, 350, 1).odometer_m, 1);
+        assert_eq!(bench.ride(3.6, 350, 2).odometer_m, 1);
+        let record = bench.ride(3.6, 350, 1);
+        assert_eq!(record.odometer_m, 2);
+        assert_eq!(record.trip_a_m, 2);
+        assert_eq!(record.trip_b_m, 2);
+        assert_eq!(bench.ride(3.6, 350, 3).odometer_m, 3);
+    }
+
+    #[test]
+    fn ride_time_only_counts_while_moving() {
+        let mut bench = OdometerBench::new();
+        assert_eq!(bench.ride(0.0, 500, 10).ride_time_s, 0);
+        assert_eq!(bench.ride(10.0, 400, 2).ride_time_s, 0);
+        assert_eq!(bench.ride(10.0, 400, 1).ride_time_s, 1);
+    }
+
+    #[test]
+    fn odometer_skips_gaps() {
+        let mut bench = OdometerBench::new();
+        let step_ms = OdometerConfig::default().max_step.as_millis() + 1;
+        assert_eq!(bench.ride(36.0, step_ms, 5), OdometerRecord::default());
+    }
+
+    #[test]
+    fn reset_trip_clears_one_meter_and_saves() {
+        let mut bench = OdometerBench::new();
+        bench.ride(36.0, 100, 50);
+        bench.odometer.mark_saved();
+
+        bench.odometer.reset_trip(TripMeter::B());
+        let record = bench.odometer.record();
+        assert_eq!(record.trip_b_m, 0);
+        assert!(record.trip_a_m > 0);
+        assert_eq!(record.trip_a_m, record.odometer_m);
+        assert!(bench.odometer.needs_save());
+    }
+
+    #[test]
+    fn saves_after_distance() {
+        let mut bench = OdometerBench::new();
+        // a metre a step, inside the save time
+        bench.ride(36.0, 100, 90);
+        assert!(!bench.odometer.needs_save());
+        bench.ride(36.0, 100, 15);
+        assert!(bench.odometer.needs_save());
+
+        bench.odometer.mark_saved();
+        assert!(!bench.odometer.needs_save());
+    }
+
+    #[test]
+    fn saves_after_ride_time() {
+        let mut bench = OdometerBench::new();
+        // too slow to cover the save distance in the save time
+        let save_every_s = OdometerConfig::default().save_every_s;
+        bench.ride(3.0, 1000, save_every_s - 1);
+        assert!(!bench.odometer.needs_save());
+        bench.ride(3.0, 1000, 1);
+        assert!(bench.odometer.needs_save());
+    }
+
+    #[test]
+    fn saves_on_stop() {
+        let mut bench = OdometerBench::new();
+        bench.ride(36.0, 100, 10);
+        assert!(!bench.odometer.needs_save());
+        bench.ride(0.0, 100, 1);
+        assert!(bench.odometer.needs_save());
+
+        // standing still with nothing new doesn't write again
+        bench.odometer.mark_saved();
+        bench.ride(0.0, 100, 50);
+        assert!(!bench.odometer.needs_save());
+    }
+
+    #[test]
+    fn trip_a_reset_clears_trip_computer() {
+        let mut fcu = fcu();
+        let ws = WheelSpeed::from(150u16);
+        for step in 0..50 {
+            fcu.broadcast_wheel(ws);
+            fcu.run_telemetry(Timestamp::from_micros(step * 100_000));
+        }
+        assert!(fcu.update_user_display().trip.distance_km > 0.0);
+
+        // trip B leaves the trip computer alone
+        fcu.process_message(Message::TripResetMessage(TripResetMessage {
+            trip: TripMeter::B(),
+        }))
+        .unwrap();
+        assert!(fcu.update_user_display().trip.distance_km > 0.0);
+
+        fcu.process_message(Message::TripResetMessage(TripResetMessage {
+            trip: TripMeter::A(),
+        }))
+        .unwrap();
+        assert_eq!(fcu.update_user_display().trip.distance_km, 0.0);
+        assert_eq!(fcu.trip_computer.stats().distance_km, 0.0);
+    }
+
+    #[test]
+    fn repeated_config_is_not_saved() {
+        let mut fcu = fcu();
+        let delta = ConfigDelta::from_engine(&fcu.config.engine);
+        for _ in 0..3 {
+            fcu.process_message(Message::ConfigMessage(delta)).unwrap();
+            assert!(fcu.take_changed_config().is_none());
+        }
+
+        let changed = ConfigDelta {
+            throttle_map_mode: if delta.throttle_map_mode == ThottleMapMode::Level1() {
+                ThottleMapMode::Level2()
+            } else {
+                ThottleMapMode::Level1()
+            },
+            ..delta
+        };
+        fcu.process_message(Message::ConfigMessage(changed))
+            .unwrap();
+        assert!(fcu.take_changed_config().is_some());
+        fcu.process_message(Message::ConfigMessage(changed))
+            .unwrap();
+        assert!(fcu.take_changed_config().is_none());
+    }
+
+    #[test]
+    fn reselecting_profile_is_not_saved() {
+        let mut fcu = fcu();
+        let profile = ProfileMessage {
+            profile: RideProfileId::Sport(),
+        };
+        fcu.process_message(Message::ProfileMessage(profile))
+            .unwrap();
+        assert!(fcu.take_changed_config().is_some());
+        fcu.process_message(Message::ProfileMessage(profile))
+            .unwrap();
+        assert!(fcu.take_changed_config().is_none());
+    }
+}
@@ -2,27 +2,60 @@ use core::ops::{Deref, DerefMut};
 use core::time;
 
 use crate::config::config::ConfigDelta;
+use crate::config::store::CONFIG_RECORD_LEN;
+use crate::controllers::battery::{BatteryMonitor, BatteryState};
+use crate::messages::e2e::{E2eHeader, E2eReceiver, E2eStats};
+use crate::messages::error::DecodeError;
+use crate::messages::messages::abs::AbsMessage;
+use crate::messages::messages::regen::RegenMessage;
+use crate::operations::cruise_control::CruiseCommand;
+use crate::operations::message_timeout::{MessageTimeout, TimeoutStatus};
+use crate::operations::power_limiter::PowerLimitReason;
+use crate::operations::slip_estimator::SlipEstimate;
+use crate::operations::thermal_derate::TemperatureSensor;
+use crate::operations::throttle_map::ThrottleTableUpload;
 use crate::{
     config::config::Config,
     controllers::shared::Lockable,
     messages::messages::{Message, ecu::EcuMessage},
     subsystems::{
-        mcu::engine::{EngineRequest, EngineSubsystem},
+        mcu::{
+            abs::{AbsPhase, AbsRequest, AbsResponse, AbsSubsystem},
+            engine::{EngineRequest, EngineSubsystem},
+            pas::{PasRequest, PasSubsystem},
+            regen::{RegenRequest, RegenSubsystem},
+        },
         shared::Subsystem,
     },
     utils::{
         parts::Wheel,
         percentage::Percentage,
-        speed::WheelSpeed,
+        speed::{Cadence, GroundSpeed, WheelSpeed},
         time::{Duration, Timestamp},
+        torque::Torque,
     },
 };
 
+// rejected control frames in a row before the link is treated as failed, a single
+// corrupted frame on a noisy bus shouldn't cut the motor
+const CTL_E2E_MAX_ERRORS: u8 = 3;
+
 #[derive(Debug, Clone, Copy)]
 pub struct McuConfig {
     pub engine_poll: Duration,
     pub ecu_poll: Duration,
     pub config_poll: Duration,
+    pub regen_poll: Duration,
+    pub abs_poll: Duration,
+    pub ctl_timeout: Duration,
+    pub ws_timeout: Duration,
+    pub cadence_timeout: Duration,
+    pub torque_timeout: Duration,
+    pub imu_timeout: Duration,
+    pub temperature_timeout: Duration,
+    pub limp_throttle_limit: Percentage,
+    // config changes are batched up and written back this often to spare the flash
+    pub config_save_poll: Duration,
 }
 
 impl Default for McuConfig {
@@ -31,6 +64,72 @@ impl Default for McuConfig {
             engine_poll: Duration::from_millis(20),
             ecu_poll: Duration::from_millis(50),
             config_poll: Duration::from_millis(1000),
+            regen_poll: Duration::from_millis(100),
+            abs_poll: Duration::from_millis(20),
+            ctl_timeout: Duration::from_millis(200),
+            ws_timeout: Duration::from_millis(200),
+            cadence_timeout: Duration::from_millis(500),
+            torque_timeout: Duration::from_millis(200),
+            imu_timeout: Duration::from_millis(100),
+            temperature_timeout: Duration::from_millis(1000),
+            limp_throttle_limit: Percentage::from_fractional(0.3),
+            config_save_poll: Duration::from_millis(5000),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum McuOperatingState {
+    Init,    // waiting for a zero throttle request
+    Armed,   // ready, throttle at zero
+    Running, // applying throttle
+    Fault,   // throttle cut, latched until the FCU link is healthy and the throttle is at zero
+    Limp,    // wheel speed missing or lost, throttle capped
+}
+
+impl McuOperatingState {
+    pub fn to_small_str(&self) -> &str {
+        match self {
+            McuOperatingState::Init => "INIT",
+            McuOperatingState::Armed => "ARM",
+            McuOperatingState::Running => "RUN",
+            McuOperatingState::Fault => "FLT",
+            McuOperatingState::Limp => "LIMP",
+        }
+    }
+
+    pub fn allows_throttle(&self) -> bool {
+        matches!(self, McuOperatingState::Running | McuOperatingState::Limp)
+    }
+}
+
+impl From<McuOperatingState> for u8 {
+    fn from(state: McuOperatingState) -> u8 {
+        match state {
+            McuOperatingState::Init => 0,
+            McuOperatingState::Armed => 1,
+            McuOperatingState::Running => 2,
+            McuOperatingState::Fault => 3,
+            McuOperatingState::Limp => 4,
+        }
+    }
+}
+
+impl TryFrom<u8> for McuOperatingState {
+    type Error = DecodeError;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(McuOperatingState::Init),
+            1 => Ok(McuOperatingState::Armed),
+            2 => Ok(McuOperatingState::Running),
+            3 => Ok(McuOperatingState::Fault),
+            4 => Ok(McuOperatingState::Limp),
+            _ => Err(DecodeError::InvalidValue {
+                field: "McuOperatingState",
+                value,
+            }),
         }
     }
 }
@@ -39,10 +138,39 @@ impl Default for McuConfig {
 pub struct McuState {
     throttle: Percentage,
     brake: Percentage,
+    regen: Percentage,
+    regen_slip_limited: bool,
+    abs: AbsResponse,
     throttle_req: Percentage,
+    pas_req: Percentage,
     brake_req: Percentage,
+    walk_assist: bool,
     rear_ws: Option<WheelSpeed>,
     front_ws: Option<WheelSpeed>,
+    cadence: Option<Cadence>,
+    rider_torque: Option<Torque>,
+    longitudinal_accel: Option<f32>,
+    pitch_deg: Option<f32>,
+    slip: SlipEstimate,
+    cruise_cmd: Option<CruiseCommand>,
+    cruise_target: Option<GroundSpeed>,
+    motor_temp_c: Option<f32>,
+    controller_temp_c: Option<f32>,
+    derate: Percentage,
+    limit_reason: PowerLimitReason,
+    ecu_counter: u8,
+    // rejected control frames in a row, reset by the next good one
+    ctl_e2e_errors: u8,
+    ctl_timeout: MessageTimeout,
+    rear_ws_timeout: MessageTimeout,
+    front_ws_timeout: MessageTimeout,
+    cadence_timeout: MessageTimeout,
+    torque_timeout: MessageTimeout,
+    imu_timeout: MessageTimeout,
+    motor_temp_timeout: MessageTimeout,
+    controller_temp_timeout: MessageTimeout,
+    timeouts: TimeoutStatus,
+    operating_state: McuOperatingState,
 }
 
 impl Default for McuState {
@@ -50,72 +178,699 @@ impl Default for McuState {
         McuState {
             throttle: Percentage::zero(),
             brake: Percentage::zero(),
+            regen: Percentage::zero(),
+            regen_slip_limited: false,
+            abs: AbsResponse {
+                brake_cmd: Percentage::zero(),
+                phase: AbsPhase::Inactive,
+                cycles: 0,
+            },
             throttle_req: Percentage::zero(),
+            pas_req: Percentage::zero(),
             brake_req: Percentage::zero(),
+            walk_assist: false,
             rear_ws: None,
             front_ws: None,
+            cadence: None,
+            rider_torque: None,
+            longitudinal_accel: None,
+            pitch_deg: None,
+            slip: SlipEstimate::none(),
+            cruise_cmd: None,
+            cruise_target: None,
+            motor_temp_c: None,
+            controller_temp_c: None,
+            derate: Percentage::zero(),
+            limit_reason: PowerLimitReason::None(),
+            ecu_counter: 0,
+            ctl_e2e_errors: 0,
+            ctl_timeout: MessageTimeout::new(),
+            rear_ws_timeout: MessageTimeout::new(),
+            front_ws_timeout: MessageTimeout::new(),
+            cadence_timeout: MessageTimeout::new(),
+            torque_timeout: MessageTimeout::new(),
+            imu_timeout: MessageTimeout::new(),
+            motor_temp_timeout: MessageTimeout::new(),
+            controller_temp_timeout: MessageTimeout::new(),
+            timeouts: TimeoutStatus::default(),
+            operating_state: McuOperatingState::Init,
         }
     }
 }
 
 pub struct McuController {
     pub config: Config,
+    // set when a message changes the config, cleared once it has been handed out to save
+    config_changed: bool,
     state: McuState,
+    ctl_e2e: E2eReceiver,
+    throttle_table_upload: ThrottleTableUpload,
+    battery: BatteryMonitor,
 
     engine_subsystem: EngineSubsystem,
+    regen_subsystem: RegenSubsystem,
+    pas_subsystem: PasSubsystem,
+    abs_subsystem: AbsSubsystem,
 }
 
 impl McuController {
     pub fn new(config: Config) -> Self {
         let engine_subsystem = EngineSubsystem::new(config.engine);
+        let regen_subsystem = RegenSubsystem::new(config.regen);
+        let pas_subsystem = PasSubsystem::new(config.pas);
+        let abs_subsystem = AbsSubsystem::new(config.abs);
         McuController {
             config,
+            config_changed: false,
             state: McuState::default(),
+            ctl_e2e: E2eReceiver::new(),
+            throttle_table_upload: ThrottleTableUpload::new(),
+            battery: BatteryMonitor::new(config.battery),
             engine_subsystem,
+            regen_subsystem,
+            pas_subsystem,
+            abs_subsystem,
         }
     }
 
-    pub fn process_message(&mut self, msg: Message) {
+    pub fn process_message(&mut self, msg: Message) -> Result<(), DecodeError> {
         match msg {
             Message::TireStatusMessage(status) => match status.wheel {
                 Wheel::Rear => {
                     self.state.rear_ws = Some(status.ws);
+                    self.state.rear_ws_timeout.received();
                 }
                 Wheel::Front => {
                     self.state.front_ws = Some(status.ws);
+                    self.state.front_ws_timeout.received();
                 }
             },
             Message::ControlReqMessage(req) => {
+                if let Err(err) = self.ctl_e2e.check(req.e2e) {
+                    self.state.ctl_e2e_errors = self.state.ctl_e2e_errors.saturating_add(1);
+                    return Err(err);
+                }
+                self.state.ctl_e2e_errors = 0;
                 self.state.throttle_req = req.throttle_req;
                 self.state.brake_req = req.brake_req;
+                self.state.walk_assist = req.walk_assist;
+                self.state.ctl_timeout.received();
             }
+            Message::CadenceMessage(msg) => {
+                self.state.cadence = Some(msg.cadence);
+                self.state.cadence_timeout.received();
+            }
+            Message::TorqueMessage(msg) => {
+                self.state.rider_torque = Some(msg.rider_torque);
+                self.state.torque_timeout.received();
+            }
+            Message::CruiseMessage(msg) => {
+                self.state.cruise_cmd = Some(msg.command);
+            }
+            Message::ImuMessage(msg) => {
+                self.state.longitudinal_accel = Some(msg.longitudinal_accel_mps2);
+                self.state.pitch_deg = Some(msg.pitch_deg);
+                self.state.imu_timeout.received();
+            }
+            Message::BmsPackMessage(_) | Message::BmsCellMessage(_) => {
+                self.battery.process_message(&msg);
+            }
+            Message::TemperatureMessage(msg) => match msg.sensor {
+                TemperatureSensor::MotorWinding() => {
+                    self.state.motor_temp_c = Some(msg.temperature_c);
+                    self.state.motor_temp_timeout.received();
+                }
+                TemperatureSensor::ControllerMosfet() => {
+                    self.state.controller_temp_c = Some(msg.temperature_c);
+                    self.state.controller_temp_timeout.received();
+                }
+            },
             Message::UpdateMessage(req) => {
-                req.update(&mut self.config);
+                let before = self.config.to_record();
+                req.update(&mut self.config)?;
+                self.engine_subsystem.update(self.config.engine);
+                self.note_config_change(before);
+            }
+            Message::ProfileMessage(msg) => {
+                let before = self.config.to_record();
+                self.config.select_profile(msg.profile);
+                self.engine_subsystem.update(self.config.engine);
+                self.note_config_change(before);
+            }
+            Message::ThrottleTableMessage(msg) => {
+                if let Some(table) = self.throttle_table_upload.push(msg)? {
+                    let before = self.config.to_record();
+                    self.config.engine.throttle_table = table;
+                    self.engine_subsystem.throttle_map.update_table(table);
+                    self.note_config_change(before);
+                }
             }
             _ => {}
         }
+        Ok(())
+    }
+
+    fn update_timeouts(&mut self, timestamp: Timestamp) {
+        let mcu_config = self.config.mcu;
+        self.state.timeouts = TimeoutStatus {
+            ctl: self
+                .state
+                .ctl_timeout
+                .update(timestamp, mcu_config.ctl_timeout),
+            front_ws: self
+                .state
+                .front_ws_timeout
+                .update(timestamp, mcu_config.ws_timeout),
+            rear_ws: self
+                .state
+                .rear_ws_timeout
+                .update(timestamp, mcu_config.ws_timeout),
+            cadence: self
+                .state
+                .cadence_timeout
+                .update(timestamp, mcu_config.cadence_timeout),
+            torque: self
+                .state
+                .torque_timeout
+                .update(timestamp, mcu_config.torque_timeout),
+            imu: self
+                .state
+                .imu_timeout
+                .update(timestamp, mcu_config.imu_timeout),
+            // either sensor going quiet flags the pair
+            temperature: self
+                .state
+                .motor_temp_timeout
+                .update(timestamp, mcu_config.temperature_timeout)
+                | self
+                    .state
+                    .controller_temp_timeout
+                    .update(timestamp, mcu_config.temperature_timeout),
+        };
+    }
+
+    fn fresh_rider_torque(&self) -> Option<Torque> {
+        if self.state.timeouts.torque {
+            None
+        } else {
+            self.state.rider_torque
+        }
+    }
+
+    fn update_operating_state(&mut self) {
+        let timeouts = self.state.timeouts;
+        // a wheel speed that never arrived is as unusable as one that stopped
+        let ws_lost = timeouts.front_ws || timeouts.rear_ws;
+        let link_failed = timeouts.ctl || self.state.ctl_e2e_errors >= CTL_E2E_MAX_ERRORS;
+        // a latched cruise keeps the motor running with the grip released
+        let throttle_applied = self.engine_subsystem.assist_mode.has_demand(
+            self.state.throttle_req,
+            self.state.pas_req,
+            self.fresh_rider_torque(),
+        ) || self.engine_subsystem.cruise_control.is_engaged()
+            || self.state.walk_assist;
+
+        self.state.operating_state = match self.state.operating_state {
+            // booting and clearing a fault both need a healthy link and the throttle at zero
+            state @ (McuOperatingState::Init | McuOperatingState::Fault) => {
+                if !link_failed && !throttle_applied {
+                    McuOperatingState::Armed
+                } else {
+                    state
+                }
+            }
+            _ if link_failed => McuOperatingState::Fault,
+            // only leave limp once the rider has rolled off the throttle
+            McuOperatingState::Limp if throttle_applied => McuOperatingState::Limp,
+            _ if ws_lost => McuOperatingState::Limp,
+            _ if throttle_applied => McuOperatingState::Running,
+            _ => McuOperatingState::Armed,
+        };
+
+        // while the link is down take whatever counter the FCU sends next, it may have
+        // restarted and a fresh count can land anywhere outside the receiver's window
+        if self.state.operating_state == McuOperatingState::Fault && link_failed {
+            self.ctl_e2e.reset();
+        }
     }
 
     pub fn run_engine_subsystem(&mut self, timestamp: Timestamp) {
+        self.update_timeouts(timestamp);
+        let battery = self.battery.run(timestamp);
+        let timeouts = self.state.timeouts;
+
+        // stale cadence reads as stopped pedals
+        let pas_resp = self.pas_subsystem.run(PasRequest {
+            cadence: if timeouts.cadence {
+                None
+            } else {
+                self.state.cadence
+            },
+        });
+        self.state.pas_req = pas_resp.assist_req;
+
+        self.update_operating_state();
+        let operating_state = self.state.operating_state;
+        let motor_allowed = !timeouts.ctl && operating_state.allows_throttle();
+
+        // failsafe: cut throttle on a stale request and drop stale wheel speeds so
+        // traction control is disabled instead of acting on old data
         let req = EngineRequest {
-            rear_ws: self.state.rear_ws,
-            front_ws: self.state.front_ws,
-            throttle_req: self.state.throttle_req,
-            timestamp: timestamp,
+            rear_ws: if timeouts.rear_ws {
+                None
+            } else {
+                self.state.rear_ws
+            },
+            front_ws: if timeouts.front_ws {
+                None
+            } else {
+                self.state.front_ws
+            },
+            throttle_req: if motor_allowed {
+                self.state.throttle_req
+            } else {
+                Percentage::zero()
+            },
+            pas_req: if motor_allowed {
+                self.state.pas_req
+            } else {
+                Percentage::zero()
+            },
+            rider_torque: if motor_allowed {
+                self.fresh_rider_torque()
+            } else {
+                None
+            },
+            brake_req: self.state.brake_req,
+            // a stale IMU just leaves the slip estimate on the front wheel alone
+            longitudinal_accel: if timeouts.imu {
+                None
+            } else {
+                self.state.longitudinal_accel
+            },
+            pitch_deg: if timeouts.imu {
+                None
+            } else {
+                self.state.pitch_deg
+            },
+            walk_assist: motor_allowed && self.state.walk_assist,
+            motor_allowed,
+            throttle_limit: if operating_state == McuOperatingState::Limp {
+                self.config.mcu.limp_throttle_limit
+            } else {
+                Percentage::full()
+            },
+            // a stale reading is dropped, the derate holds its last limit
+            motor_temp_c: if self.state.motor_temp_timeout.is_timed_out() {
+                None
+            } else {
+                self.state.motor_temp_c
+            },
+            controller_temp_c: if self.state.controller_temp_timeout.is_timed_out() {
+                None
+            } else {
+                self.state.controller_temp_c
+            },
+            pack_voltage_v: battery.pack_voltage_v,
+            pack_current_a: battery.pack_current_a,
+            // cruise drops out with everything else when the motor isn't allowed to run
+            cruise_cmd: if motor_allowed {
+                self.state.cruise_cmd.take()
+            } else {
+                self.state.cruise_cmd = None;
+                Some(CruiseCommand::Cancel())
+            },
+            timestamp,
         };
         let resp = self.engine_subsystem.run(req);
+        self.state.slip = resp.slip;
+        self.state.cruise_target = resp.cruise_target;
+        self.state.derate = resp.derate;
+        self.state.limit_reason = resp.limit_reason;
         self.state.throttle = resp.throttle_req;
+
+        // ABS follows the rider's last brake request in every state, dropping the
+        // brakes on a lost frame is worse than holding them
+        self.state.abs = self.abs_subsystem.run(AbsRequest {
+            rear_ws: req.rear_ws,
+            front_ws: req.front_ws,
+            brake_req: self.state.brake_req,
+            longitudinal_accel: req.longitudinal_accel,
+            timestamp,
+        });
+
+        // regen only runs off a fresh brake request in states that may drive the motor,
+        // and stays off while ABS is cycling the rear brake
+        let regen_allowed = !timeouts.ctl
+            && self.state.abs.phase == AbsPhase::Inactive
+            && !matches!(
+                operating_state,
+                McuOperatingState::Init | McuOperatingState::Fault
+            );
+        let regen_resp = self.regen_subsystem.run(RegenRequest {
+            slip: self.state.slip,
+            brake_req: if regen_allowed {
+                self.state.brake_req
+            } else {
+                Percentage::zero()
+            },
+        });
+        self.state.regen = regen_resp.regen;
+        self.state.regen_slip_limited = regen_resp.slip_limited;
+    }
+
+    pub fn broadcast_abs(&self) -> Message {
+        Message::AbsMessage(AbsMessage {
+            brake_cmd: self.state.abs.brake_cmd,
+            phase: self.state.abs.phase,
+            cycles: self.state.abs.cycles,
+        })
     }
 
-    pub fn broadcast_ecu(&self) -> Message {
+    pub fn broadcast_ecu(&mut self) -> Message {
+        self.state.ecu_counter = self.state.ecu_counter.wrapping_add(1);
         Message::EcuMessage(EcuMessage {
             throttle: self.state.throttle,
+            timeouts: self.state.timeouts,
+            state: self.state.operating_state,
+            drive_slip: self.state.slip.drive_slip,
+            brake_slip: self.state.slip.brake_slip,
+            cruise_target: self.state.cruise_target,
+            derate: self.state.derate,
+            limit_reason: self.state.limit_reason,
+            e2e: E2eHeader::new(self.state.ecu_counter),
         })
     }
 
-    pub fn broadcast_config(&self) -> Message {
-        Message::ConfigMessage(ConfigDelta {
-            engine: self.config.engine,
+    pub fn slip(&self) -> SlipEstimate {
+        self.state.slip
+    }
+
+    // a repeated message leaves the stored form alone, and so the flash
+    fn note_config_change(&mut self, before: [u8; CONFIG_RECORD_LEN]) {
+        if self.config.to_record() != before {
+            self.config_changed = true;
+        }
+    }
+
+    // the config to write back if it changed since the last call
+    pub fn take_changed_config(&mut self) -> Option<Config> {
+        if self.config_changed {
+            self.config_changed = false;
+            Some(self.config)
+        } else {
+            None
+        }
+    }
+
+    pub fn battery(&self) -> BatteryState {
+        self.battery.state()
+    }
+
+    pub fn operating_state(&self) -> McuOperatingState {
+        self.state.operating_state
+    }
+
+    pub fn timeouts(&self) -> TimeoutStatus {
+        self.state.timeouts
+    }
+
+    pub fn ctl_e2e_stats(&self) -> E2eStats {
+        self.ctl_e2e.stats()
+    }
+
+    pub fn broadcast_regen(&self) -> Message {
+        Message::RegenMessage(RegenMessage {
+            regen: self.state.regen,
+            slip_limited: self.state.regen_slip_limited,
         })
     }
+
+    pub fn broadcast_config(&self) -> Message {
+        Message::ConfigMessage(ConfigDelta::from_engine(&self.config.engine))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::{
+        messages::messages::{control_req::ControlReqMessage, tire_status::TireStatus},
+        operations::speed_limiter::SpeedLimitClass,
+    };
+
+    const STEP_MS: u64 = 20;
+
+    struct Bench {
+        mcu: McuController,
+        now_ms: u64,
+        counter: u8,
+        // whether the wheel speed sensors are reporting
+        wheel_speeds: bool,
+    }
+
+    impl Bench {
+        fn new() -> Self {
+            Self::with_config(Config::default())
+        }
+
+        fn with_config(config: Config) -> Self {
+            Bench {
+                mcu: McuController::new(config),
+                now_ms: 0,
+                counter: 0,
+                wheel_speeds: true,
+            }
+        }
+
+        // no speed limit, so throttle still passes with the wheel speeds gone
+        fn off_road() -> Self {
+            let mut config = Config::default();
+            config.engine.speed_limit_class = SpeedLimitClass::Off();
+            Self::with_config(config)
+        }
+
+        fn send(&mut self, throttle: f32, e2e: E2eHeader) -> Result<(), DecodeError> {
+            self.mcu
+                .process_message(Message::ControlReqMessage(ControlReqMessage {
+                    throttle_req: Percentage::from_fractional(throttle),
+                    brake_req: Percentage::zero(),
+                    walk_assist: false,
+                    e2e,
+                }))
+        }
+
+        // one engine cycle, with a control frame from the FCU if one arrived
+        fn step(&mut self, throttle: Option<f32>) -> Percentage {
+            if let Some(throttle) = throttle {
+                self.counter = self.counter.wrapping_add(1);
+                self.send(throttle, E2eHeader::new(self.counter)).unwrap();
+            }
+            self.run()
+        }
+
+        fn run(&mut self) -> Percentage {
+            // standing still, the speed limiter won't pass throttle without a speed
+            if self.wheel_speeds {
+                for wheel in [Wheel::Front, Wheel::Rear] {
+                    self.mcu
+                        .process_message(Message::TireStatusMessage(TireStatus::new(
+                            wheel,
+                            WheelSpeed::zero(),
+                        )))
+                        .unwrap();
+                }
+            }
+            self.now_ms += STEP_MS;
+            self.mcu
+                .run_engine_subsystem(Timestamp::from_micros(self.now_ms * 1000));
+            match self.mcu.broadcast_ecu() {
+                Message::EcuMessage(msg) => msg.throttle,
+                _ => unreachable!(),
+            }
+        }
+
+        fn settle(&mut self, throttle: f32) -> Percentage {
+            let mut out = Percentage::zero();
+            for _ in 0..50 {
+                out = self.step(Some(throttle));
+            }
+            out
+        }
+
+        fn running() -> Self {
+            let mut bench = Bench::new();
+            bench.settle(0.0);
+            bench.settle(1.0);
+            assert_eq!(bench.mcu.operating_state(), McuOperatingState::Running);
+            bench
+        }
+    }
+
+    #[test]
+    fn arms_only_once_throttle_is_at_zero() {
+        let mut bench = Bench::new();
+        assert_eq!(bench.mcu.operating_state(), McuOperatingState::Init);
+
+        // powered up with the throttle held open, nothing happens until it is released
+        assert_eq!(bench.settle(0.5), Percentage::zero());
+        assert_eq!(bench.mcu.operating_state(), McuOperatingState::Init);
+
+        bench.step(Some(0.0));
+        assert_eq!(bench.mcu.operating_state(), McuOperatingState::Armed);
+        assert!(bench.settle(0.5) > Percentage::zero());
+        assert_eq!(bench.mcu.operating_state(), McuOperatingState::Running);
+    }
+
+    #[test]
+    fn does_not_arm_without_control_frames() {
+        let mut bench = Bench::new();
+        for _ in 0..20 {
+            bench.step(None);
+        }
+        assert_eq!(bench.mcu.operating_state(), McuOperatingState::Init);
+    }
+
+    #[test]
+    fn ctl_timeout_at_full_throttle_cuts_on_same_cycle() {
+        let mut bench = Bench::running();
+        assert!(bench.step(Some(1.0)) > Percentage::from_fractional(0.5));
+
+        // the FCU goes quiet, the motor has to be off the cycle the timeout fires
+        loop {
+            let throttle = bench.step(None);
+            if bench.mcu.timeouts().ctl {
+                assert_eq!(throttle, Percentage::zero());
+                break;
+            }
+        }
+        assert_eq!(bench.mcu.operating_state(), McuOperatingState::Fault);
+    }
+
+    #[test]
+    fn fault_stays_latched_until_throttle_is_released() {
+        let mut bench = Bench::running();
+        for _ in 0..20 {
+            bench.step(None);
+        }
+        assert_eq!(bench.mcu.operating_state(), McuOperatingState::Fault);
+
+        // the link comes back with the throttle still open, the fault holds
+        assert_eq!(bench.settle(1.0), Percentage::zero());
+        assert_eq!(bench.mcu.operating_state(), McuOperatingState::Fault);
+
+        bench.step(Some(0.0));
+        assert_eq!(bench.mcu.operating_state(), McuOperatingState::Armed);
+        assert!(bench.settle(1.0) > Percentage::zero());
+        assert_eq!(bench.mcu.operating_state(), McuOperatingState::Running);
+    }
+
+    #[test]
+    fn single_bad_crc_is_tolerated() {
+        let mut bench = Bench::running();
+        let e2e = E2eHeader {
+            counter: bench.counter.wrapping_add(1),
+            crc_valid: false,
+        };
+        assert_eq!(bench.send(1.0, e2e), Err(DecodeError::CrcMismatch));
+        bench.run();
+        assert_eq!(bench.mcu.operating_state(), McuOperatingState::Running);
+    }
+
+    #[test]
+    fn repeated_bad_crc_faults() {
+        let mut bench = Bench::running();
+        for _ in 0..CTL_E2E_MAX_ERRORS {
+            let e2e = E2eHeader {
+                counter: bench.counter.wrapping_add(1),
+                crc_valid: false,
+            };
+            assert!(bench.send(1.0, e2e).is_err());
+        }
+        // well inside the timeout, it is the bad frames that fault
+        assert_eq!(bench.run(), Percentage::zero());
+        assert!(!bench.mcu.timeouts().ctl);
+        assert_eq!(bench.mcu.operating_state(), McuOperatingState::Fault);
+    }
+
+    #[test]
+    fn stuck_counter_faults() {
+        let mut bench = Bench::running();
+        for _ in 0..CTL_E2E_MAX_ERRORS {
+            assert!(bench.send(1.0, E2eHeader::new(bench.counter)).is_err());
+        }
+        assert_eq!(bench.run(), Percentage::zero());
+        assert_eq!(bench.mcu.operating_state(), McuOperatingState::Fault);
+    }
+
+    #[test]
+    fn fcu_restart_faults_then_resyncs() {
+        let mut bench = Bench::running();
+        // the FCU comes back with its counter somewhere else entirely
+        bench.counter = bench.counter.wrapping_add(100);
+        for _ in 0..CTL_E2E_MAX_ERRORS {
+            bench.counter = bench.counter.wrapping_add(1);
+            assert!(bench.send(0.0, E2eHeader::new(bench.counter)).is_err());
+        }
+        bench.run();
+        assert_eq!(bench.mcu.operating_state(), McuOperatingState::Fault);
+
+        bench.step(Some(0.0));
+        bench.step(Some(0.0));
+        assert_eq!(bench.mcu.operating_state(), McuOperatingState::Armed);
+        let stats = bench.mcu.ctl_e2e_stats();
+        assert_eq!(stats.sequence_errors, CTL_E2E_MAX_ERRORS as u32);
+    }
+
+    #[test]
+    fn boots_to_limp_without_wheel_speed() {
+        let mut bench = Bench::new();
+        bench.wheel_speeds = false;
+        bench.settle(0.0);
+        assert_eq!(bench.mcu.operating_state(), McuOperatingState::Limp);
+    }
+
+    #[test]
+    fn limp_caps_throttle_before_the_slew_limiter() {
+        let mut bench = Bench::off_road();
+        bench.wheel_speeds = false;
+        bench.settle(0.0);
+        let limit = bench.mcu.config.mcu.limp_throttle_limit;
+        assert!((bench.settle(1.0).to_fractional() - limit.to_fractional()).abs() < 1e-3);
+        assert_eq!(bench.mcu.operating_state(), McuOperatingState::Limp);
+
+        // the slew limiter followed the capped command, so a roll off starts winding down
+        // straight away rather than sitting at the cap
+        assert!(bench.step(Some(0.0)) < limit);
+    }
+
+    #[test]
+    fn enters_limp_when_wheel_speed_is_lost() {
+        let mut bench = Bench::running();
+        bench.wheel_speeds = false;
+        for _ in 0..20 {
+            bench.step(Some(1.0));
+        }
+        assert_eq!(bench.mcu.operating_state(), McuOperatingState::Limp);
+    }
+
+    #[test]
+    fn leaves_limp_after_roll_off_once_wheel_speed_returns() {
+        let mut bench = Bench::new();
+        bench.wheel_speeds = false;
+        bench.settle(0.0);
+        bench.settle(1.0);
+        assert_eq!(bench.mcu.operating_state(), McuOperatingState::Limp);
+
+        // speeds are back but the rider is still on the throttle
+        bench.wheel_speeds = true;
+        bench.settle(1.0);
+        assert_eq!(bench.mcu.operating_state(), McuOperatingState::Limp);
+
+        bench.step(Some(0.0));
+        assert_eq!(bench.mcu.operating_state(), McuOperatingState::Armed);
+        bench.settle(1.0);
+        assert_eq!(bench.mcu.operating_state(), McuOperatingState::Running);
+    }
 }
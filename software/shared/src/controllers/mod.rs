@@ -6,3 +6,6 @@ pub mod mcu;
 
 #[path = "./fcu.rs"]
 pub mod fcu;
+
+#[path = "./battery.rs"]
+pub mod battery;
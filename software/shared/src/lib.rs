@@ -17,3 +17,6 @@ pub mod controllers;
 
 #[path = "./config/mod.rs"]
 pub mod config;
+
+#[path = "./storage/mod.rs"]
+pub mod storage;
@@ -1,14 +1,25 @@
 use shared::{
+    controllers::mcu::McuOperatingState,
     messages::{
+        e2e::E2eHeader,
         ids::ECU_MESG_ID,
         messages::{Message, ecu::EcuMessage},
     },
+    operations::{message_timeout::TimeoutStatus, power_limiter::PowerLimitReason},
     utils::percentage::Percentage,
 };
 
 fn main() {
     let msg = Message::EcuMessage(EcuMessage {
         throttle: Percentage::full(),
+        timeouts: TimeoutStatus::default(),
+        state: McuOperatingState::Running,
+        drive_slip: Percentage::zero(),
+        brake_slip: Percentage::zero(),
+        derate: Percentage::zero(),
+        limit_reason: PowerLimitReason::None(),
+        cruise_target: None,
+        e2e: E2eHeader::default(),
     });
     let bytes = msg.to_bytes();
     let byte_str = bytes
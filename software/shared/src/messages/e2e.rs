@@ -0,0 +1,276 @@
+use embedded_can::StandardId;
+
+use crate::messages::error::DecodeError;
+
+// Protected payloads keep the alive counter and CRC in the last two bytes of the frame
+pub const E2E_COUNTER_IDX: usize = 6;
+pub const E2E_CRC_IDX: usize = 7;
+
+// CRC-8 SAE J1850 as used by AUTOSAR E2E profile 1
+const CRC8_POLY: u8 = 0x1D;
+const CRC8_INIT: u8 = 0xFF;
+const CRC8_XOR_OUT: u8 = 0xFF;
+
+// how far the counter may advance between accepted frames, covering a couple of frames
+// lost on the bus. Anything further, or backwards, is a replay or a stuck sender
+const MAX_COUNTER_STEP: u8 = 3;
+
+fn crc8_update(mut crc: u8, byte: u8) -> u8 {
+    crc ^= byte;
+    for _ in 0..8 {
+        crc = if crc & 0x80 != 0 {
+            (crc << 1) ^ CRC8_POLY
+        } else {
+            crc << 1
+        };
+    }
+    crc
+}
+
+// CRC over the message id (data id) followed by the payload, so a frame can't be
+// replayed under a different id
+pub fn crc8(id: StandardId, payload: &[u8]) -> u8 {
+    let raw_id = id.as_raw();
+    let mut crc = CRC8_INIT;
+    crc = crc8_update(crc, (raw_id & 0xFF) as u8);
+    crc = crc8_update(crc, (raw_id >> 8) as u8);
+    for byte in payload {
+        crc = crc8_update(crc, *byte);
+    }
+    crc ^ CRC8_XOR_OUT
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct E2eHeader {
+    pub counter: u8,
+    // set by the decoder, always true for locally built messages
+    pub crc_valid: bool,
+}
+
+impl E2eHeader {
+    pub fn new(counter: u8) -> Self {
+        Self {
+            counter,
+            crc_valid: true,
+        }
+    }
+
+    // Write the counter and CRC into an encoded payload
+    pub fn protect(&self, id: StandardId, mut data: [u8; 8]) -> [u8; 8] {
+        data[E2E_COUNTER_IDX] = self.counter;
+        data[E2E_CRC_IDX] = crc8(id, &data[..E2E_CRC_IDX]);
+        data
+    }
+
+    // Read the counter and verify the CRC of a received payload
+    pub fn from_bytes(id: StandardId, data: &[u8]) -> Result<Self, DecodeError> {
+        DecodeError::check_len(data, E2E_CRC_IDX + 1)?;
+        Ok(Self {
+            counter: data[E2E_COUNTER_IDX],
+            crc_valid: crc8(id, &data[..E2E_CRC_IDX]) == data[E2E_CRC_IDX],
+        })
+    }
+}
+
+impl Default for E2eHeader {
+    fn default() -> Self {
+        Self::new(0)
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct E2eStats {
+    pub accepted: u32,
+    pub crc_errors: u32,
+    pub repeated_errors: u32,
+    pub sequence_errors: u32,
+}
+
+// Receiver side state for one protected message stream
+#[derive(Debug, Clone, Copy, Default)]
+pub struct E2eReceiver {
+    last_counter: Option<u8>,
+    stats: E2eStats,
+}
+
+impl E2eReceiver {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn check(&mut self, header: E2eHeader) -> Result<(), DecodeError> {
+        if !header.crc_valid {
+            self.stats.crc_errors = self.stats.crc_errors.saturating_add(1);
+            return Err(DecodeError::CrcMismatch);
+        }
+        if let Some(last_counter) = self.last_counter {
+            let step = header.counter.wrapping_sub(last_counter);
+            if step == 0 {
+                self.stats.repeated_errors = self.stats.repeated_errors.saturating_add(1);
+                return Err(DecodeError::RepeatedCounter(header.counter));
+            }
+            if step > MAX_COUNTER_STEP {
+                self.stats.sequence_errors = self.stats.sequence_errors.saturating_add(1);
+                return Err(DecodeError::CounterJump {
+                    expected: last_counter.wrapping_add(1),
+                    actual: header.counter,
+                });
+            }
+        }
+        self.last_counter = Some(header.counter);
+        self.stats.accepted = self.stats.accepted.saturating_add(1);
+        Ok(())
+    }
+
+    pub fn stats(&self) -> E2eStats {
+        self.stats
+    }
+
+    // take whatever counter arrives next, for when the sender may have restarted
+    pub fn reset(&mut self) {
+        self.last_counter = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn id() -> StandardId {
+        StandardId::new(0x123).unwrap()
+    }
+
+    fn frame(counter: u8) -> E2eHeader {
+        E2eHeader::new(counter)
+    }
+
+    #[test]
+    fn crc8_matches_sae_j1850_check_value() {
+        let crc = b"123456789"
+            .iter()
+            .fold(CRC8_INIT, |crc, byte| crc8_update(crc, *byte));
+        assert_eq!(crc ^ CRC8_XOR_OUT, 0x4B);
+    }
+
+    #[test]
+    fn crc8_covers_the_id_ahead_of_the_payload() {
+        // four zero bytes, two of id and two of payload
+        assert_eq!(crc8(StandardId::new(0).unwrap(), &[0, 0]), 0x59);
+        assert_ne!(
+            crc8(StandardId::new(0x100).unwrap(), &[0, 0]),
+            crc8(StandardId::new(0).unwrap(), &[0, 0])
+        );
+    }
+
+    #[test]
+    fn protected_frame_verifies() {
+        let data = E2eHeader::new(42).protect(id(), [1, 2, 3, 4, 5, 6, 0, 0]);
+        assert_eq!(E2eHeader::from_bytes(id(), &data), Ok(E2eHeader::new(42)));
+    }
+
+    #[test]
+    fn corrupted_frame_fails_crc() {
+        let mut data = E2eHeader::new(42).protect(id(), [1, 2, 3, 4, 5, 6, 0, 0]);
+        data[2] ^= 0x10;
+        assert!(!E2eHeader::from_bytes(id(), &data).unwrap().crc_valid);
+
+        // the same payload under another id doesn't verify either
+        let data = E2eHeader::new(42).protect(id(), [1, 2, 3, 4, 5, 6, 0, 0]);
+        let other = StandardId::new(0x124).unwrap();
+        assert!(!E2eHeader::from_bytes(other, &data).unwrap().crc_valid);
+    }
+
+    #[test]
+    fn short_frame_is_truncated() {
+        assert_eq!(
+            E2eHeader::from_bytes(id(), &[0; 7]),
+            Err(DecodeError::Truncated {
+                expected: 8,
+                actual: 7
+            })
+        );
+    }
+
+    #[test]
+    fn receiver_accepts_advancing_counter_across_wrap() {
+        let mut rx = E2eReceiver::new();
+        for counter in [253, 254, 255, 0, 1] {
+            assert_eq!(rx.check(frame(counter)), Ok(()));
+        }
+        assert_eq!(rx.stats().accepted, 5);
+    }
+
+    #[test]
+    fn receiver_tolerates_a_few_lost_frames() {
+        let mut rx = E2eReceiver::new();
+        rx.check(frame(10)).unwrap();
+        assert_eq!(rx.check(frame(10 + MAX_COUNTER_STEP)), Ok(()));
+    }
+
+    #[test]
+    fn receiver_rejects_repeat() {
+        let mut rx = E2eReceiver::new();
+        rx.check(frame(7)).unwrap();
+        assert_eq!(rx.check(frame(7)), Err(DecodeError::RepeatedCounter(7)));
+        assert_eq!(rx.stats().repeated_errors, 1);
+        assert_eq!(rx.check(frame(8)), Ok(()));
+    }
+
+    #[test]
+    fn receiver_rejects_jump_ahead() {
+        let mut rx = E2eReceiver::new();
+        rx.check(frame(7)).unwrap();
+        assert_eq!(
+            rx.check(frame(7 + MAX_COUNTER_STEP + 1)),
+            Err(DecodeError::CounterJump {
+                expected: 8,
+                actual: 7 + MAX_COUNTER_STEP + 1
+            })
+        );
+        assert_eq!(rx.stats().sequence_errors, 1);
+        // the rejected frame doesn't move the window
+        assert_eq!(rx.check(frame(8)), Ok(()));
+    }
+
+    #[test]
+    fn receiver_rejects_replayed_older_counter() {
+        let mut rx = E2eReceiver::new();
+        for counter in 1..=5 {
+            rx.check(frame(counter)).unwrap();
+        }
+        assert_eq!(
+            rx.check(frame(3)),
+            Err(DecodeError::CounterJump {
+                expected: 6,
+                actual: 3
+            })
+        );
+        assert_eq!(rx.stats().sequence_errors, 1);
+        assert_eq!(rx.stats().repeated_errors, 0);
+    }
+
+    #[test]
+    fn receiver_rejects_bad_crc_without_advancing() {
+        let mut rx = E2eReceiver::new();
+        rx.check(frame(1)).unwrap();
+        let corrupted = E2eHeader {
+            counter: 2,
+            crc_valid: false,
+        };
+        assert_eq!(rx.check(corrupted), Err(DecodeError::CrcMismatch));
+        assert_eq!(rx.stats().crc_errors, 1);
+        assert_eq!(rx.check(frame(2)), Ok(()));
+        assert_eq!(rx.stats().accepted, 2);
+    }
+
+    #[test]
+    fn reset_resyncs_to_next_counter() {
+        let mut rx = E2eReceiver::new();
+        rx.check(frame(100)).unwrap();
+        rx.reset();
+        assert_eq!(rx.check(frame(0)), Ok(()));
+        assert_eq!(rx.check(frame(1)), Ok(()));
+    }
+}
@@ -0,0 +1,66 @@
+use core::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum DecodeError {
+    // frame id is not part of the protocol
+    UnknownId(u16),
+    // frame is shorter than the payload requires
+    Truncated { expected: usize, actual: usize },
+    // byte does not map to a variant of the named field
+    InvalidValue { field: &'static str, value: u8 },
+    // float field decoded to NaN, infinity or out of range, kept as raw bits so the error stays Eq
+    InvalidFloat { field: &'static str, bits: u32 },
+    // end-to-end protection CRC does not match the payload
+    CrcMismatch,
+    // end-to-end alive counter did not advance since the last frame
+    RepeatedCounter(u8),
+    // end-to-end alive counter went backwards or skipped more frames than can be lost
+    CounterJump { expected: u8, actual: u8 },
+}
+
+impl DecodeError {
+    // Ensure a payload carries at least `expected` bytes
+    pub fn check_len(data: &[u8], expected: usize) -> Result<(), DecodeError> {
+        if data.len() < expected {
+            Err(DecodeError::Truncated {
+                expected,
+                actual: data.len(),
+            })
+        } else {
+            Ok(())
+        }
+    }
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DecodeError::UnknownId(id) => write!(f, "unknown message id {:#05x}", id),
+            DecodeError::Truncated { expected, actual } => {
+                write!(
+                    f,
+                    "truncated frame: expected {} bytes, got {}",
+                    expected, actual
+                )
+            }
+            DecodeError::InvalidValue { field, value } => {
+                write!(f, "invalid value {} for {}", value, field)
+            }
+            DecodeError::CrcMismatch => write!(f, "e2e crc mismatch"),
+            DecodeError::InvalidFloat { field, bits } => {
+                write!(f, "invalid value {} for {}", f32::from_bits(*bits), field)
+            }
+            DecodeError::RepeatedCounter(counter) => {
+                write!(f, "e2e counter {} repeated", counter)
+            }
+            DecodeError::CounterJump { expected, actual } => {
+                write!(
+                    f,
+                    "e2e counter {} out of sequence, expected {}",
+                    actual, expected
+                )
+            }
+        }
+    }
+}
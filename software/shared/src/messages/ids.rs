@@ -5,3 +5,15 @@ pub const CTL_MESG_ID: StandardId = unsafe { StandardId::new_unchecked(0x02) };
 pub const TRS_MESG_ID: StandardId = unsafe { StandardId::new_unchecked(0x03) };
 pub const UPD_MESG_ID: StandardId = unsafe { StandardId::new_unchecked(0x04) };
 pub const CFG_MESG_ID: StandardId = unsafe { StandardId::new_unchecked(0x05) };
+pub const RGN_MESG_ID: StandardId = unsafe { StandardId::new_unchecked(0x06) };
+pub const CAD_MESG_ID: StandardId = unsafe { StandardId::new_unchecked(0x07) };
+pub const TRQ_MESG_ID: StandardId = unsafe { StandardId::new_unchecked(0x08) };
+pub const TTB_MESG_ID: StandardId = unsafe { StandardId::new_unchecked(0x09) };
+pub const IMU_MESG_ID: StandardId = unsafe { StandardId::new_unchecked(0x0A) };
+pub const ABS_MESG_ID: StandardId = unsafe { StandardId::new_unchecked(0x0B) };
+pub const CRU_MESG_ID: StandardId = unsafe { StandardId::new_unchecked(0x0C) };
+pub const PRF_MESG_ID: StandardId = unsafe { StandardId::new_unchecked(0x0D) };
+pub const TMP_MESG_ID: StandardId = unsafe { StandardId::new_unchecked(0x0E) };
+pub const BPK_MESG_ID: StandardId = unsafe { StandardId::new_unchecked(0x0F) };
+pub const BCL_MESG_ID: StandardId = unsafe { StandardId::new_unchecked(0x10) };
+pub const TRP_MESG_ID: StandardId = unsafe { StandardId::new_unchecked(0x11) };
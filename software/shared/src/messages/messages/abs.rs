@@ -0,0 +1,40 @@
+use crate::{
+    messages::error::DecodeError, subsystems::mcu::abs::AbsPhase, utils::percentage::Percentage,
+};
+
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct AbsMessage {
+    // command for the rear brake actuator
+    pub brake_cmd: Percentage,
+    pub phase: AbsPhase,
+    pub cycles: u8,
+}
+
+impl AbsMessage {
+    pub fn to_bytes(&self) -> [u8; 8] {
+        [
+            self.brake_cmd.into(),
+            self.phase.into(),
+            self.cycles,
+            0,
+            0,
+            0,
+            0,
+            0,
+        ]
+    }
+}
+
+impl TryFrom<&[u8]> for AbsMessage {
+    type Error = DecodeError;
+
+    fn try_from(data: &[u8]) -> Result<Self, Self::Error> {
+        DecodeError::check_len(data, 3)?;
+        Ok(Self {
+            brake_cmd: data[0].into(),
+            phase: data[1].try_into()?,
+            cycles: data[2],
+        })
+    }
+}
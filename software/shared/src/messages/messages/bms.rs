@@ -0,0 +1,87 @@
+use crate::messages::error::DecodeError;
+
+// pack level readings from the BMS
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct BmsPackMessage {
+    pub pack_voltage_v: f32,
+    // positive while discharging
+    pub pack_current_a: f32,
+}
+
+impl BmsPackMessage {
+    pub fn to_bytes(&self) -> [u8; 8] {
+        // sent in 0.01 V and 0.01 A steps
+        let voltage = (self.pack_voltage_v * 100.0).clamp(0.0, u16::MAX as f32) as u16;
+        let current = (self.pack_current_a * 100.0).clamp(i16::MIN as f32, i16::MAX as f32) as i16;
+        let voltage_packets = voltage.to_le_bytes();
+        let current_packets = current.to_le_bytes();
+        [
+            voltage_packets[0],
+            voltage_packets[1],
+            current_packets[0],
+            current_packets[1],
+            0,
+            0,
+            0,
+            0,
+        ]
+    }
+}
+
+impl TryFrom<&[u8]> for BmsPackMessage {
+    type Error = DecodeError;
+
+    fn try_from(data: &[u8]) -> Result<Self, Self::Error> {
+        DecodeError::check_len(data, 4)?;
+        Ok(Self {
+            pack_voltage_v: u16::from_le_bytes([data[0], data[1]]) as f32 / 100.0,
+            pack_current_a: i16::from_le_bytes([data[2], data[3]]) as f32 / 100.0,
+        })
+    }
+}
+
+// cell level readings from the BMS
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct BmsCellMessage {
+    pub cell_min_v: f32,
+    pub cell_max_v: f32,
+    pub temperature_c: f32,
+}
+
+impl BmsCellMessage {
+    pub fn to_bytes(&self) -> [u8; 8] {
+        // cells in millivolts, temperature in 0.1 degree steps
+        let cell_min = (self.cell_min_v * 1000.0).clamp(0.0, u16::MAX as f32) as u16;
+        let cell_max = (self.cell_max_v * 1000.0).clamp(0.0, u16::MAX as f32) as u16;
+        let temperature =
+            (self.temperature_c * 10.0).clamp(i16::MIN as f32, i16::MAX as f32) as i16;
+        let min_packets = cell_min.to_le_bytes();
+        let max_packets = cell_max.to_le_bytes();
+        let temperature_packets = temperature.to_le_bytes();
+        [
+            min_packets[0],
+            min_packets[1],
+            max_packets[0],
+            max_packets[1],
+            temperature_packets[0],
+            temperature_packets[1],
+            0,
+            0,
+        ]
+    }
+}
+
+impl TryFrom<&[u8]> for BmsCellMessage {
+    type Error = DecodeError;
+
+    fn try_from(data: &[u8]) -> Result<Self, Self::Error> {
+        DecodeError::check_len(data, 6)?;
+        Ok(Self {
+            cell_min_v: u16::from_le_bytes([data[0], data[1]]) as f32 / 1000.0,
+            cell_max_v: u16::from_le_bytes([data[2], data[3]]) as f32 / 1000.0,
+            temperature_c: i16::from_le_bytes([data[4], data[5]]) as f32 / 10.0,
+        })
+    }
+}
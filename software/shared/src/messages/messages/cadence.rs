@@ -0,0 +1,25 @@
+use crate::{messages::error::DecodeError, utils::speed::Cadence};
+
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct CadenceMessage {
+    pub cadence: Cadence,
+}
+
+impl CadenceMessage {
+    pub fn to_bytes(&self) -> [u8; 8] {
+        let packets = self.cadence.to_packets();
+        [packets[0], packets[1], 0, 0, 0, 0, 0, 0]
+    }
+}
+
+impl TryFrom<&[u8]> for CadenceMessage {
+    type Error = DecodeError;
+
+    fn try_from(data: &[u8]) -> Result<Self, Self::Error> {
+        DecodeError::check_len(data, 2)?;
+        Ok(Self {
+            cadence: Cadence::from_packets(&[data[0], data[1]]),
+        })
+    }
+}
@@ -3,10 +3,18 @@ use embedded_can::StandardId;
 use crate::{
     config::config::{Config, ConfigDelta},
     messages::{
-        ids::{CFG_MESG_ID, CTL_MESG_ID, ECU_MESG_ID, TRS_MESG_ID, UPD_MESG_ID},
+        error::DecodeError,
+        ids::{
+            ABS_MESG_ID, BCL_MESG_ID, BPK_MESG_ID, CAD_MESG_ID, CFG_MESG_ID, CRU_MESG_ID, CTL_MESG_ID, ECU_MESG_ID, IMU_MESG_ID, PRF_MESG_ID, RGN_MESG_ID, TRQ_MESG_ID,
+            TMP_MESG_ID, TRP_MESG_ID, TRS_MESG_ID, TTB_MESG_ID, UPD_MESG_ID,
+        },
         messages::{
-            control_req::ControlReqMessage, ecu::EcuMessage, tire_status::TireStatus,
-            update::Update,
+            abs::AbsMessage, bms::{BmsCellMessage, BmsPackMessage}, cadence::CadenceMessage, control_req::ControlReqMessage, cruise::CruiseMessage, ecu::EcuMessage, imu::ImuMessage,
+            profile::ProfileMessage,
+            temperature::TemperatureMessage,
+            trip::TripResetMessage,
+            regen::RegenMessage, throttle_table::ThrottleTableMessage, tire_status::TireStatus,
+            torque::TorqueMessage, update::Update,
         },
     },
     utils::percentage::Percentage,
@@ -19,6 +27,18 @@ pub enum Message {
     ControlReqMessage(ControlReqMessage),
     UpdateMessage(Update),
     ConfigMessage(ConfigDelta),
+    RegenMessage(RegenMessage),
+    CadenceMessage(CadenceMessage),
+    TorqueMessage(TorqueMessage),
+    ThrottleTableMessage(ThrottleTableMessage),
+    ImuMessage(ImuMessage),
+    AbsMessage(AbsMessage),
+    CruiseMessage(CruiseMessage),
+    ProfileMessage(ProfileMessage),
+    TemperatureMessage(TemperatureMessage),
+    BmsPackMessage(BmsPackMessage),
+    BmsCellMessage(BmsCellMessage),
+    TripResetMessage(TripResetMessage),
 }
 
 impl Message {
@@ -29,25 +49,63 @@ impl Message {
             Message::ControlReqMessage(msg) => msg.to_bytes(),
             Message::UpdateMessage(msg) => msg.to_bytes(),
             Message::ConfigMessage(msg) => msg.to_bytes(),
+            Message::RegenMessage(msg) => msg.to_bytes(),
+            Message::CadenceMessage(msg) => msg.to_bytes(),
+            Message::TorqueMessage(msg) => msg.to_bytes(),
+            Message::ThrottleTableMessage(msg) => msg.to_bytes(),
+            Message::ImuMessage(msg) => msg.to_bytes(),
+            Message::AbsMessage(msg) => msg.to_bytes(),
+            Message::CruiseMessage(msg) => msg.to_bytes(),
+            Message::ProfileMessage(msg) => msg.to_bytes(),
+            Message::TemperatureMessage(msg) => msg.to_bytes(),
+            Message::BmsPackMessage(msg) => msg.to_bytes(),
+            Message::BmsCellMessage(msg) => msg.to_bytes(),
+            Message::TripResetMessage(msg) => msg.to_bytes(),
         }
     }
 
-    pub fn from_bytes(id: u16, data: &[u8]) -> Option<Self> {
-        let id = unsafe { StandardId::new_unchecked(id) };
+    pub fn from_bytes(id: u16, data: &[u8]) -> Result<Self, DecodeError> {
+        let raw_id = id;
+        // anything past 11 bits isn't a standard id, let alone one of ours
+        let Some(id) = StandardId::new(id) else {
+            return Err(DecodeError::UnknownId(raw_id));
+        };
         if id == ECU_MESG_ID {
-            Some(Message::EcuMessage(EcuMessage::from_bytes(data)))
+            Ok(Message::EcuMessage(data.try_into()?))
         } else if id == CTL_MESG_ID {
-            Some(Message::ControlReqMessage(ControlReqMessage::from_bytes(
-                data,
-            )))
+            Ok(Message::ControlReqMessage(data.try_into()?))
         } else if id == TRS_MESG_ID {
-            Some(Message::TireStatusMessage(TireStatus::from_bytes(data)))
+            Ok(Message::TireStatusMessage(data.try_into()?))
         } else if id == UPD_MESG_ID {
-            Some(Message::UpdateMessage(Update::from_bytes(data)))
+            Ok(Message::UpdateMessage(data.try_into()?))
         } else if id == CFG_MESG_ID {
-            Some(Message::ConfigMessage(ConfigDelta::from_bytes(data)))
+            Ok(Message::ConfigMessage(data.try_into()?))
+        } else if id == RGN_MESG_ID {
+            Ok(Message::RegenMessage(data.try_into()?))
+        } else if id == CAD_MESG_ID {
+            Ok(Message::CadenceMessage(data.try_into()?))
+        } else if id == TRQ_MESG_ID {
+            Ok(Message::TorqueMessage(data.try_into()?))
+        } else if id == TTB_MESG_ID {
+            Ok(Message::ThrottleTableMessage(data.try_into()?))
+        } else if id == IMU_MESG_ID {
+            Ok(Message::ImuMessage(data.try_into()?))
+        } else if id == ABS_MESG_ID {
+            Ok(Message::AbsMessage(data.try_into()?))
+        } else if id == CRU_MESG_ID {
+            Ok(Message::CruiseMessage(data.try_into()?))
+        } else if id == PRF_MESG_ID {
+            Ok(Message::ProfileMessage(data.try_into()?))
+        } else if id == TMP_MESG_ID {
+            Ok(Message::TemperatureMessage(data.try_into()?))
+        } else if id == BPK_MESG_ID {
+            Ok(Message::BmsPackMessage(data.try_into()?))
+        } else if id == BCL_MESG_ID {
+            Ok(Message::BmsCellMessage(data.try_into()?))
+        } else if id == TRP_MESG_ID {
+            Ok(Message::TripResetMessage(data.try_into()?))
         } else {
-            None
+            Err(DecodeError::UnknownId(raw_id))
         }
     }
 
@@ -58,6 +116,18 @@ impl Message {
             Message::ControlReqMessage(_) => CTL_MESG_ID.as_raw(),
             Message::UpdateMessage(_) => UPD_MESG_ID.as_raw(),
             Message::ConfigMessage(_) => CFG_MESG_ID.as_raw(),
+            Message::RegenMessage(_) => RGN_MESG_ID.as_raw(),
+            Message::CadenceMessage(_) => CAD_MESG_ID.as_raw(),
+            Message::TorqueMessage(_) => TRQ_MESG_ID.as_raw(),
+            Message::ThrottleTableMessage(_) => TTB_MESG_ID.as_raw(),
+            Message::ImuMessage(_) => IMU_MESG_ID.as_raw(),
+            Message::AbsMessage(_) => ABS_MESG_ID.as_raw(),
+            Message::CruiseMessage(_) => CRU_MESG_ID.as_raw(),
+            Message::ProfileMessage(_) => PRF_MESG_ID.as_raw(),
+            Message::TemperatureMessage(_) => TMP_MESG_ID.as_raw(),
+            Message::BmsPackMessage(_) => BPK_MESG_ID.as_raw(),
+            Message::BmsCellMessage(_) => BCL_MESG_ID.as_raw(),
+            Message::TripResetMessage(_) => TRP_MESG_ID.as_raw(),
         }
     }
 
@@ -66,6 +136,131 @@ impl Message {
     }
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::messages::e2e::E2eHeader;
+
+    const IDS: [StandardId; 17] = [
+        ECU_MESG_ID,
+        CTL_MESG_ID,
+        TRS_MESG_ID,
+        UPD_MESG_ID,
+        CFG_MESG_ID,
+        RGN_MESG_ID,
+        CAD_MESG_ID,
+        TRQ_MESG_ID,
+        TTB_MESG_ID,
+        IMU_MESG_ID,
+        ABS_MESG_ID,
+        CRU_MESG_ID,
+        PRF_MESG_ID,
+        TMP_MESG_ID,
+        BPK_MESG_ID,
+        BCL_MESG_ID,
+        TRP_MESG_ID,
+    ];
+
+    #[test]
+    fn empty_frame_is_truncated() {
+        for id in IDS {
+            let res = Message::from_bytes(id.as_raw(), &[]);
+            assert!(
+                matches!(res, Err(DecodeError::Truncated { actual: 0, .. })),
+                "id {:#x} gave {:?}",
+                id.as_raw(),
+                res
+            );
+        }
+    }
+
+    #[test]
+    fn short_frame_is_truncated() {
+        let res = Message::from_bytes(ECU_MESG_ID.as_raw(), &[0; 7]);
+        assert!(matches!(
+            res,
+            Err(DecodeError::Truncated {
+                expected: 8,
+                actual: 7
+            })
+        ));
+    }
+
+    #[test]
+    fn unknown_id_is_rejected() {
+        assert!(matches!(
+            Message::from_bytes(0x7FF, &[0; 8]),
+            Err(DecodeError::UnknownId(0x7FF))
+        ));
+        assert!(matches!(
+            Message::from_bytes(0, &[0; 8]),
+            Err(DecodeError::UnknownId(0))
+        ));
+        // wider than a standard id
+        assert!(matches!(
+            Message::from_bytes(0xFFFF, &[0; 8]),
+            Err(DecodeError::UnknownId(0xFFFF))
+        ));
+    }
+
+    #[test]
+    fn out_of_range_enum_is_invalid() {
+        assert!(matches!(
+            Message::from_bytes(TRP_MESG_ID.as_raw(), &[2, 0, 0, 0, 0, 0, 0, 0]),
+            Err(DecodeError::InvalidValue {
+                field: "TripMeter",
+                value: 2
+            })
+        ));
+        assert!(matches!(
+            Message::from_bytes(CRU_MESG_ID.as_raw(), &[9, 0, 0, 0, 0, 0, 0, 0]),
+            Err(DecodeError::InvalidValue {
+                field: "CruiseCommand",
+                value: 9
+            })
+        ));
+        assert!(matches!(
+            Message::from_bytes(RGN_MESG_ID.as_raw(), &[0, 2, 0, 0, 0, 0, 0, 0]),
+            Err(DecodeError::InvalidValue {
+                field: "RegenMessage::slip_limited",
+                value: 2
+            })
+        ));
+
+        // an operating state past the last one, behind a valid crc
+        let data = E2eHeader::new(1).protect(ECU_MESG_ID, [0, 0, 0x0F, 0, 0, 0, 0, 0]);
+        assert!(matches!(
+            Message::from_bytes(ECU_MESG_ID.as_raw(), &data),
+            Err(DecodeError::InvalidValue {
+                field: "McuOperatingState",
+                value: 0x0F
+            })
+        ));
+    }
+
+    #[test]
+    fn garbage_never_panics() {
+        let patterns: [fn(usize) -> u8; 4] = [
+            |_| 0x00,
+            |_| 0xFF,
+            |idx| 0xA5 ^ idx as u8,
+            |idx| (idx as u8).wrapping_mul(37),
+        ];
+        for raw_id in 0..=0x20 {
+            for pattern in patterns {
+                let mut data = [0u8; 8];
+                for (idx, byte) in data.iter_mut().enumerate() {
+                    *byte = pattern(idx);
+                }
+                for len in 0..=data.len() {
+                    let _ = Message::from_bytes(raw_id, &data[..len]);
+                }
+            }
+        }
+    }
+}
+
 #[cfg(feature = "std")]
 extern crate std;
 
@@ -82,17 +277,7 @@ impl From<String> for Message {
         let id = parts[0].parse::<u16>().expect("Invalid ID format");
         let data = hex_to_bytes::<8>(parts[1]).expect("Invalid hex data");
 
-        if id == ECU_MESG_ID.as_raw() {
-            Message::EcuMessage(EcuMessage::from_bytes(&data))
-        } else if id == CTL_MESG_ID.as_raw() {
-            Message::ControlReqMessage(ControlReqMessage::from_bytes(&data))
-        } else if id == TRS_MESG_ID.as_raw() {
-            Message::TireStatusMessage(TireStatus::from_bytes(&data))
-        } else if id == UPD_MESG_ID.as_raw() {
-            Message::UpdateMessage(Update::from_bytes(&data))
-        } else {
-            panic!("Unknown Message ID");
-        }
+        Message::from_bytes(id, &data).expect("Invalid Message data")
     }
 }
 
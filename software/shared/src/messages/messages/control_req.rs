@@ -1,30 +1,59 @@
-use crate::utils::percentage::Percentage;
+use crate::{
+    messages::{e2e::E2eHeader, error::DecodeError, ids::CTL_MESG_ID},
+    utils::percentage::Percentage,
+};
 
 #[derive(Debug, Clone, Copy)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct ControlReqMessage {
     pub throttle_req: Percentage,
     pub brake_req: Percentage,
+    // walk assist button held
+    pub walk_assist: bool,
+    pub e2e: E2eHeader,
 }
 
 impl ControlReqMessage {
+    const WALK_ASSIST_BIT: u8 = 1 << 0;
+    const BUTTON_BITS: u8 = Self::WALK_ASSIST_BIT;
+
     pub fn to_bytes(&self) -> [u8; 8] {
-        [
-            self.throttle_req.into(),
-            self.brake_req.into(),
-            0,
-            0,
-            0,
-            0,
-            0,
-            0,
-        ]
+        self.e2e.protect(
+            CTL_MESG_ID,
+            [
+                self.throttle_req.into(),
+                self.brake_req.into(),
+                if self.walk_assist {
+                    Self::WALK_ASSIST_BIT
+                } else {
+                    0
+                },
+                0,
+                0,
+                0,
+                0,
+                0,
+            ],
+        )
     }
+}
+
+impl TryFrom<&[u8]> for ControlReqMessage {
+    type Error = DecodeError;
 
-    pub fn from_bytes(data: &[u8]) -> Self {
-        Self {
+    fn try_from(data: &[u8]) -> Result<Self, Self::Error> {
+        let e2e = E2eHeader::from_bytes(CTL_MESG_ID, data)?;
+        if data[2] & !Self::BUTTON_BITS != 0 {
+            return Err(DecodeError::InvalidValue {
+                field: "ControlReqMessage::buttons",
+                value: data[2],
+            });
+        }
+        Ok(Self {
             throttle_req: data[0].into(),
             brake_req: data[1].into(),
-        }
+            walk_assist: data[2] & Self::WALK_ASSIST_BIT != 0,
+            e2e,
+        })
     }
 }
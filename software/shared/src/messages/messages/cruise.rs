@@ -0,0 +1,25 @@
+use crate::{messages::error::DecodeError, operations::cruise_control::CruiseCommand};
+
+// sent by the FCU on a cruise button press
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct CruiseMessage {
+    pub command: CruiseCommand,
+}
+
+impl CruiseMessage {
+    pub fn to_bytes(&self) -> [u8; 8] {
+        [self.command.into(), 0, 0, 0, 0, 0, 0, 0]
+    }
+}
+
+impl TryFrom<&[u8]> for CruiseMessage {
+    type Error = DecodeError;
+
+    fn try_from(data: &[u8]) -> Result<Self, Self::Error> {
+        DecodeError::check_len(data, 1)?;
+        Ok(Self {
+            command: data[0].try_into()?,
+        })
+    }
+}
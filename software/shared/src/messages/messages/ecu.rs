@@ -1,19 +1,86 @@
-use crate::utils::percentage::Percentage;
+use micromath::F32Ext;
+
+use crate::{
+    controllers::mcu::McuOperatingState,
+    messages::{e2e::E2eHeader, error::DecodeError, ids::ECU_MESG_ID},
+    operations::{message_timeout::TimeoutStatus, power_limiter::PowerLimitReason},
+    utils::{percentage::Percentage, speed::GroundSpeed},
+};
 
 #[derive(Debug, Clone, Copy)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct EcuMessage {
     pub throttle: Percentage,
+    pub timeouts: TimeoutStatus,
+    pub state: McuOperatingState,
+    // only one of the slips is ever set, they share a byte on the bus
+    pub drive_slip: Percentage,
+    pub brake_slip: Percentage,
+    // latched cruise speed, None while cruise is off
+    pub cruise_target: Option<GroundSpeed>,
+    // share of the output taken away by thermal derating
+    pub derate: Percentage,
+    // why the pack limiter is holding the motor back
+    pub limit_reason: PowerLimitReason,
+    pub e2e: E2eHeader,
 }
 
 impl EcuMessage {
+    // top bit of the timeout byte, set when the slip byte holds brake slip
+    const BRAKE_SLIP_BIT: u8 = 1 << 7;
+    // operating state in the low nibble of the state byte, limit reason in the high one
+    const STATE_MASK: u8 = 0x0F;
+    const LIMIT_REASON_SHIFT: u8 = 4;
+
     pub fn to_bytes(&self) -> [u8; 8] {
-        [self.throttle.into(), 0, 0, 0, 0, 0, 0, 0]
+        let braking = self.brake_slip > self.drive_slip;
+        let timeouts: u8 = self.timeouts.into();
+        self.e2e.protect(
+            ECU_MESG_ID,
+            [
+                self.throttle.into(),
+                if braking {
+                    timeouts | Self::BRAKE_SLIP_BIT
+                } else {
+                    timeouts
+                },
+                u8::from(self.state) | (u8::from(self.limit_reason) << Self::LIMIT_REASON_SHIFT),
+                if braking {
+                    self.brake_slip.into()
+                } else {
+                    self.drive_slip.into()
+                },
+                self.derate.into(),
+                // whole kph, cruise never latches slow enough to need zero
+                self.cruise_target
+                    .map_or(0, |target| target.kph().round().clamp(1.0, 255.0) as u8),
+                0,
+                0,
+            ],
+        )
     }
+}
+
+impl TryFrom<&[u8]> for EcuMessage {
+    type Error = DecodeError;
 
-    pub fn from_bytes(data: &[u8]) -> Self {
-        Self {
+    fn try_from(data: &[u8]) -> Result<Self, Self::Error> {
+        let e2e = E2eHeader::from_bytes(ECU_MESG_ID, data)?;
+        let braking = data[1] & Self::BRAKE_SLIP_BIT != 0;
+        let slip: Percentage = data[3].into();
+        Ok(Self {
             throttle: data[0].into(),
-        }
+            timeouts: (data[1] & !Self::BRAKE_SLIP_BIT).try_into()?,
+            state: (data[2] & Self::STATE_MASK).try_into()?,
+            drive_slip: if braking { Percentage::zero() } else { slip },
+            brake_slip: if braking { slip } else { Percentage::zero() },
+            cruise_target: match data[5] {
+                0 => None,
+                kph => Some(GroundSpeed::from_kph(kph as f32)),
+            },
+            derate: data[4].into(),
+            limit_reason: (data[2] >> Self::LIMIT_REASON_SHIFT).try_into()?,
+            e2e,
+        })
     }
 }
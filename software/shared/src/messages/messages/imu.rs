@@ -0,0 +1,44 @@
+use crate::messages::error::DecodeError;
+
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct ImuMessage {
+    // acceleration along the frame, positive speeding up
+    pub longitudinal_accel_mps2: f32,
+    // frame pitch, positive nose up
+    pub pitch_deg: f32,
+}
+
+impl ImuMessage {
+    pub fn to_bytes(&self) -> [u8; 8] {
+        // sent in 0.01 m/s^2 steps
+        let raw =
+            (self.longitudinal_accel_mps2 * 100.0).clamp(i16::MIN as f32, i16::MAX as f32) as i16;
+        let packets = raw.to_le_bytes();
+        // sent in 0.1 degree steps
+        let pitch = (self.pitch_deg * 10.0).clamp(i16::MIN as f32, i16::MAX as f32) as i16;
+        let pitch_packets = pitch.to_le_bytes();
+        [
+            packets[0],
+            packets[1],
+            pitch_packets[0],
+            pitch_packets[1],
+            0,
+            0,
+            0,
+            0,
+        ]
+    }
+}
+
+impl TryFrom<&[u8]> for ImuMessage {
+    type Error = DecodeError;
+
+    fn try_from(data: &[u8]) -> Result<Self, Self::Error> {
+        DecodeError::check_len(data, 4)?;
+        Ok(Self {
+            longitudinal_accel_mps2: i16::from_le_bytes([data[0], data[1]]) as f32 / 100.0,
+            pitch_deg: i16::from_le_bytes([data[2], data[3]]) as f32 / 10.0,
+        })
+    }
+}
@@ -13,4 +13,37 @@ pub mod ecu;
 #[path = "./update.rs"]
 pub mod update;
 
+#[path = "./regen.rs"]
+pub mod regen;
+
+#[path = "./cadence.rs"]
+pub mod cadence;
+
+#[path = "./torque.rs"]
+pub mod torque;
+
+#[path = "./throttle_table.rs"]
+pub mod throttle_table;
+
+#[path = "./imu.rs"]
+pub mod imu;
+
+#[path = "./abs.rs"]
+pub mod abs;
+
+#[path = "./cruise.rs"]
+pub mod cruise;
+
+#[path = "./profile.rs"]
+pub mod profile;
+
+#[path = "./temperature.rs"]
+pub mod temperature;
+
+#[path = "./bms.rs"]
+pub mod bms;
+
+#[path = "./trip.rs"]
+pub mod trip;
+
 pub use common::Message;
@@ -0,0 +1,25 @@
+use crate::{config::profile::RideProfileId, messages::error::DecodeError};
+
+// sent by the FCU to switch the active ride profile
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct ProfileMessage {
+    pub profile: RideProfileId,
+}
+
+impl ProfileMessage {
+    pub fn to_bytes(&self) -> [u8; 8] {
+        [self.profile.into(), 0, 0, 0, 0, 0, 0, 0]
+    }
+}
+
+impl TryFrom<&[u8]> for ProfileMessage {
+    type Error = DecodeError;
+
+    fn try_from(data: &[u8]) -> Result<Self, Self::Error> {
+        DecodeError::check_len(data, 1)?;
+        Ok(Self {
+            profile: data[0].try_into()?,
+        })
+    }
+}
@@ -0,0 +1,36 @@
+use crate::{messages::error::DecodeError, utils::percentage::Percentage};
+
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct RegenMessage {
+    pub regen: Percentage,
+    pub slip_limited: bool,
+}
+
+impl RegenMessage {
+    pub fn to_bytes(&self) -> [u8; 8] {
+        [self.regen.into(), self.slip_limited as u8, 0, 0, 0, 0, 0, 0]
+    }
+}
+
+impl TryFrom<&[u8]> for RegenMessage {
+    type Error = DecodeError;
+
+    fn try_from(data: &[u8]) -> Result<Self, Self::Error> {
+        DecodeError::check_len(data, 2)?;
+        let slip_limited = match data[1] {
+            0 => false,
+            1 => true,
+            value => {
+                return Err(DecodeError::InvalidValue {
+                    field: "RegenMessage::slip_limited",
+                    value,
+                });
+            }
+        };
+        Ok(Self {
+            regen: data[0].into(),
+            slip_limited,
+        })
+    }
+}
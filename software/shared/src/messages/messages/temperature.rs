@@ -0,0 +1,29 @@
+use crate::{messages::error::DecodeError, operations::thermal_derate::TemperatureSensor};
+
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct TemperatureMessage {
+    pub sensor: TemperatureSensor,
+    pub temperature_c: f32,
+}
+
+impl TemperatureMessage {
+    pub fn to_bytes(&self) -> [u8; 8] {
+        // sent in 0.1 degree steps
+        let raw = (self.temperature_c * 10.0).clamp(i16::MIN as f32, i16::MAX as f32) as i16;
+        let packets = raw.to_le_bytes();
+        [self.sensor.into(), packets[0], packets[1], 0, 0, 0, 0, 0]
+    }
+}
+
+impl TryFrom<&[u8]> for TemperatureMessage {
+    type Error = DecodeError;
+
+    fn try_from(data: &[u8]) -> Result<Self, Self::Error> {
+        DecodeError::check_len(data, 3)?;
+        Ok(Self {
+            sensor: data[0].try_into()?,
+            temperature_c: i16::from_le_bytes([data[1], data[2]]) as f32 / 10.0,
+        })
+    }
+}
@@ -0,0 +1,65 @@
+use crate::{
+    messages::error::DecodeError,
+    operations::throttle_map::{
+        THROTTLE_TABLE_POINTS_PER_FRAME, TableInterpolation, ThrottleBreakpoint,
+    },
+};
+
+// one frame of a throttle table upload, see ThrottleTableUpload
+#[derive(Debug, Clone, Copy)]
+pub struct ThrottleTableMessage {
+    pub index: u8,
+    pub point_count: u8,
+    pub interpolation: TableInterpolation,
+    pub points: [Option<ThrottleBreakpoint>; THROTTLE_TABLE_POINTS_PER_FRAME],
+}
+
+impl ThrottleTableMessage {
+    pub fn to_bytes(&self) -> [u8; 8] {
+        let mut data = [
+            self.index,
+            self.point_count,
+            self.interpolation.into(),
+            0,
+            0,
+            0,
+            0,
+            0,
+        ];
+        for (i, point) in self.points.iter().flatten().enumerate() {
+            data[3] += 1;
+            data[4 + i * 2] = point.input.into();
+            data[5 + i * 2] = point.output.into();
+        }
+        data
+    }
+}
+
+impl TryFrom<&[u8]> for ThrottleTableMessage {
+    type Error = DecodeError;
+
+    fn try_from(data: &[u8]) -> Result<Self, Self::Error> {
+        DecodeError::check_len(data, 8)?;
+        let in_frame = data[3] as usize;
+        if in_frame > THROTTLE_TABLE_POINTS_PER_FRAME {
+            return Err(DecodeError::InvalidValue {
+                field: "ThrottleTableMessage::points",
+                value: data[3],
+            });
+        }
+
+        let mut points = [None; THROTTLE_TABLE_POINTS_PER_FRAME];
+        for (i, point) in points.iter_mut().enumerate().take(in_frame) {
+            *point = Some(ThrottleBreakpoint::new(
+                data[4 + i * 2].into(),
+                data[5 + i * 2].into(),
+            ));
+        }
+        Ok(Self {
+            index: data[0],
+            point_count: data[1],
+            interpolation: data[2].try_into()?,
+            points,
+        })
+    }
+}
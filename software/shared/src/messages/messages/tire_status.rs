@@ -1,4 +1,7 @@
-use crate::utils::{parts::Wheel, speed::WheelSpeed};
+use crate::{
+    messages::error::DecodeError,
+    utils::{parts::Wheel, speed::WheelSpeed},
+};
 
 #[derive(Debug, Clone, Copy)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
@@ -15,11 +18,16 @@ impl TireStatus {
         let packets = self.ws.to_packets();
         [self.wheel.into(), packets[0], packets[1], 0, 0, 0, 0, 0]
     }
+}
+
+impl TryFrom<&[u8]> for TireStatus {
+    type Error = DecodeError;
 
-    pub fn from_bytes(data: &[u8]) -> Self {
-        Self {
-            wheel: data[0].into(),
+    fn try_from(data: &[u8]) -> Result<Self, Self::Error> {
+        DecodeError::check_len(data, 3)?;
+        Ok(Self {
+            wheel: data[0].try_into()?,
             ws: WheelSpeed::from_packets(&[data[1], data[2]]),
-        }
+        })
     }
 }
@@ -0,0 +1,25 @@
+use crate::{messages::error::DecodeError, utils::torque::Torque};
+
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct TorqueMessage {
+    pub rider_torque: Torque,
+}
+
+impl TorqueMessage {
+    pub fn to_bytes(&self) -> [u8; 8] {
+        let packets = self.rider_torque.to_packets();
+        [packets[0], packets[1], 0, 0, 0, 0, 0, 0]
+    }
+}
+
+impl TryFrom<&[u8]> for TorqueMessage {
+    type Error = DecodeError;
+
+    fn try_from(data: &[u8]) -> Result<Self, Self::Error> {
+        DecodeError::check_len(data, 2)?;
+        Ok(Self {
+            rider_torque: Torque::from_packets(&[data[0], data[1]]),
+        })
+    }
+}
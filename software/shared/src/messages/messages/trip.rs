@@ -0,0 +1,25 @@
+use crate::{controllers::fcu::TripMeter, messages::error::DecodeError};
+
+// zeroes one of the FCU trip meters
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct TripResetMessage {
+    pub trip: TripMeter,
+}
+
+impl TripResetMessage {
+    pub fn to_bytes(&self) -> [u8; 8] {
+        [self.trip.into(), 0, 0, 0, 0, 0, 0, 0]
+    }
+}
+
+impl TryFrom<&[u8]> for TripResetMessage {
+    type Error = DecodeError;
+
+    fn try_from(data: &[u8]) -> Result<Self, Self::Error> {
+        DecodeError::check_len(data, 1)?;
+        Ok(Self {
+            trip: data[0].try_into()?,
+        })
+    }
+}
@@ -1,5 +1,6 @@
 use crate::{
     config::config::Config,
+    messages::error::DecodeError,
     operations::throttle_map::ThottleMapMode,
     utils::{parts::Wheel, speed::WheelSpeed},
 };
@@ -10,18 +11,31 @@ pub enum UpdateField {
     TMM(), // Throttle Map Mode
     TCM(), // Traction Control Mode
     DSL(), // Desired Slip Level
+    LCM(), // Launch Control Mode
+    TKP(), // Traction Control Proportional Gain
+    TKI(), // Traction Control Integral Gain
+    TKD(), // Traction Control Derivative Gain
 
            // Add ECU settings later
            // ESP(), // Engine Subsystem Poll Time
 }
 
-impl From<u8> for UpdateField {
-    fn from(value: u8) -> Self {
+impl TryFrom<u8> for UpdateField {
+    type Error = DecodeError;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
         match value {
-            0 => UpdateField::TMM(),
-            1 => UpdateField::TCM(),
-            2 => UpdateField::DSL(),
-            _ => UpdateField::TMM(),
+            0 => Ok(UpdateField::TMM()),
+            1 => Ok(UpdateField::TCM()),
+            2 => Ok(UpdateField::DSL()),
+            3 => Ok(UpdateField::LCM()),
+            4 => Ok(UpdateField::TKP()),
+            5 => Ok(UpdateField::TKI()),
+            6 => Ok(UpdateField::TKD()),
+            _ => Err(DecodeError::InvalidValue {
+                field: "UpdateField",
+                value,
+            }),
         }
     }
 }
@@ -32,22 +46,60 @@ impl Into<u8> for UpdateField {
             UpdateField::TMM() => 0,
             UpdateField::TCM() => 1,
             UpdateField::DSL() => 2,
+            UpdateField::LCM() => 3,
+            UpdateField::TKP() => 4,
+            UpdateField::TKI() => 5,
+            UpdateField::TKD() => 6,
         }
     }
 }
 impl UpdateField {
-    pub fn update_config(&self, config: &mut Config, data: [u8; 7]) {
+    pub fn update_config(&self, config: &mut Config, data: [u8; 7]) -> Result<(), DecodeError> {
         match self {
             UpdateField::TMM() => {
-                config.engine.throttle_map_mode = data[0].into();
+                config.engine.throttle_map_mode = data[0].try_into()?;
             }
             UpdateField::TCM() => {
-                config.engine.traction_control_mode = data[0].into();
+                config.engine.traction_control_mode = data[0].try_into()?;
             }
             UpdateField::DSL() => {
                 config.engine.desired_slip = data[0].into();
             }
+            UpdateField::LCM() => {
+                config.engine.launch_control_mode = data[0].try_into()?;
+            }
+            // the gains only take effect once TCM is set to custom
+            UpdateField::TKP() => {
+                config.engine.traction_control_gains.kp = Self::gain(data)?;
+            }
+            UpdateField::TKI() => {
+                config.engine.traction_control_gains.ki = Self::gain(data)?;
+            }
+            UpdateField::TKD() => {
+                config.engine.traction_control_gains.kd = Self::gain(data)?;
+            }
         }
+        Ok(())
+    }
+
+    // gains are sent as little endian f32 in the first four data bytes
+    fn gain(data: [u8; 7]) -> Result<f32, DecodeError> {
+        let gain = f32::from_le_bytes([data[0], data[1], data[2], data[3]]);
+        if gain.is_finite() && gain >= 0.0 {
+            Ok(gain)
+        } else {
+            Err(DecodeError::InvalidFloat {
+                field: "UpdateField::gain",
+                bits: gain.to_bits(),
+            })
+        }
+    }
+
+    pub fn is_gain(&self) -> bool {
+        matches!(
+            self,
+            UpdateField::TKP() | UpdateField::TKI() | UpdateField::TKD()
+        )
     }
 
     pub fn to_small_str(&self) -> &str {
@@ -55,6 +107,10 @@ impl UpdateField {
             UpdateField::DSL() => "DSL:",
             UpdateField::TCM() => "TCM:",
             UpdateField::TMM() => "TMM:",
+            UpdateField::LCM() => "LCM:",
+            UpdateField::TKP() => "TKP:",
+            UpdateField::TKI() => "TKI:",
+            UpdateField::TKD() => "TKD:",
         }
     }
 }
@@ -80,14 +136,6 @@ impl Update {
         ]
     }
 
-    pub fn from_bytes(data: &[u8]) -> Self {
-        let parsed_data: [u8; 7] = data[1..8].try_into().unwrap();
-        Self {
-            field: data[0].into(),
-            data: parsed_data,
-        }
-    }
-
     pub fn new(field: UpdateField, data: &[u8]) -> Self {
         let parsed_data: [u8; 7] = data[0..7].try_into().unwrap();
         Self {
@@ -96,7 +144,23 @@ impl Update {
         }
     }
 
-    pub fn update(&self, config: &mut Config) {
-        self.field.update_config(config, self.data);
+    pub fn update(&self, config: &mut Config) -> Result<(), DecodeError> {
+        self.field.update_config(config, self.data)?;
+        config.capture_custom_profile();
+        Ok(())
+    }
+}
+
+impl TryFrom<&[u8]> for Update {
+    type Error = DecodeError;
+
+    fn try_from(data: &[u8]) -> Result<Self, Self::Error> {
+        DecodeError::check_len(data, 8)?;
+        let mut parsed_data = [0u8; 7];
+        parsed_data.copy_from_slice(&data[1..8]);
+        Ok(Self {
+            field: data[0].try_into()?,
+            data: parsed_data,
+        })
     }
 }
@@ -1,5 +1,11 @@
 #[path = "./ids.rs"]
 pub mod ids;
 
+#[path = "./error.rs"]
+pub mod error;
+
+#[path = "./e2e.rs"]
+pub mod e2e;
+
 #[path = "./messages/mod.rs"]
 pub mod messages;
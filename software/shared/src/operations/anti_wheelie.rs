@@ -0,0 +1,355 @@
+use crate::{
+    messages::error::DecodeError,
+    utils::{percentage::Percentage, speed::WheelSpeed, time::Timestamp},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AntiWheelieMode {
+    Off(),
+    Level0(), // lets the front come up a little before stepping in
+    Level1(), // keeps the front planted
+}
+
+impl AntiWheelieMode {
+    // front wheel deceleration, in rpm per second, that only a lifting wheel can reach
+    pub fn front_decel_limit(&self) -> f32 {
+        match self {
+            AntiWheelieMode::Off() => f32::MAX,
+            AntiWheelieMode::Level0() => 400.0,
+            AntiWheelieMode::Level1() => 250.0,
+        }
+    }
+    // how far the front may sit under the reference speed before it still counts as lifted
+    pub fn lift_gap(&self) -> f32 {
+        match self {
+            AntiWheelieMode::Off() => 1.0,
+            AntiWheelieMode::Level0() => 0.3,
+            AntiWheelieMode::Level1() => 0.2,
+        }
+    }
+    // IMU nose-up pitch in degrees treated as a wheelie
+    pub fn pitch_limit_deg(&self) -> f32 {
+        match self {
+            AntiWheelieMode::Off() => f32::MAX,
+            AntiWheelieMode::Level0() => 25.0,
+            AntiWheelieMode::Level1() => 15.0,
+        }
+    }
+    // largest share of the throttle taken away while lifted
+    pub fn max_cut(&self) -> f32 {
+        match self {
+            AntiWheelieMode::Off() => 0.0,
+            AntiWheelieMode::Level0() => 0.5,
+            AntiWheelieMode::Level1() => 0.8,
+        }
+    }
+    // how quickly the cut builds up and lets go, per second
+    pub fn cut_rate(&self) -> f32 {
+        match self {
+            AntiWheelieMode::Off() => 0.0,
+            AntiWheelieMode::Level0() => 2.0,
+            AntiWheelieMode::Level1() => 4.0,
+        }
+    }
+    pub fn release_rate(&self) -> f32 {
+        1.0
+    }
+
+    pub fn to_small_str(&self) -> &str {
+        match self {
+            AntiWheelieMode::Off() => "OFF",
+            AntiWheelieMode::Level0() => "000",
+            AntiWheelieMode::Level1() => "001",
+        }
+    }
+}
+
+impl From<AntiWheelieMode> for u8 {
+    fn from(mode: AntiWheelieMode) -> u8 {
+        match mode {
+            AntiWheelieMode::Off() => 0,
+            AntiWheelieMode::Level0() => 1,
+            AntiWheelieMode::Level1() => 2,
+        }
+    }
+}
+
+impl TryFrom<u8> for AntiWheelieMode {
+    type Error = DecodeError;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(AntiWheelieMode::Off()),
+            1 => Ok(AntiWheelieMode::Level0()),
+            2 => Ok(AntiWheelieMode::Level1()),
+            _ => Err(DecodeError::InvalidValue {
+                field: "AntiWheelieMode",
+                value,
+            }),
+        }
+    }
+}
+
+pub struct AntiWheelie {
+    pub mode: AntiWheelieMode,
+    lifted: bool,
+    cut: f32,
+    prev_front: Option<f32>,
+    prev_rear: Option<f32>,
+    prev_timestamp: Option<Timestamp>,
+}
+
+impl AntiWheelie {
+    pub fn new(mode: AntiWheelieMode) -> Self {
+        AntiWheelie {
+            mode,
+            lifted: false,
+            cut: 0.0,
+            prev_front: None,
+            prev_rear: None,
+            prev_timestamp: None,
+        }
+    }
+
+    pub fn update_mode(&mut self, mode: AntiWheelieMode) {
+        self.mode = mode;
+        self.reset();
+    }
+
+    pub fn is_lifted(&self) -> bool {
+        self.lifted
+    }
+
+    // reference is the slip estimator's vehicle speed, pitch comes from the IMU
+    pub fn update(
+        &mut self,
+        curr_time: Timestamp,
+        front_ws: Option<WheelSpeed>,
+        rear_ws: Option<WheelSpeed>,
+        reference: Option<WheelSpeed>,
+        pitch_deg: Option<f32>,
+    ) -> bool {
+        let dt_s = if let Some(prev_time) = self.prev_timestamp {
+            curr_time.as_micros().saturating_sub(prev_time.as_micros()) as f32 / 1_000_000.0
+        } else {
+            0.0
+        };
+        self.prev_timestamp = Some(curr_time);
+
+        let front = front_ws.map(Into::<f32>::into);
+        let rear = rear_ws.map(Into::<f32>::into);
+
+        // a wheel that stops faster than brakes could manage while the rear keeps
+        // driving has left the ground
+        let decel_lift = match (front, self.prev_front, rear, self.prev_rear) {
+            (Some(front), Some(prev_front), Some(rear), Some(prev_rear)) if dt_s > 0.0 => {
+                let front_decel = (prev_front - front) / dt_s;
+                let rear_decel = (prev_rear - rear) / dt_s;
+                front_decel > self.mode.front_decel_limit()
+                    && rear_decel < self.mode.front_decel_limit() / 4.0
+            }
+            _ => false,
+        };
+        self.prev_front = front;
+        self.prev_rear = rear;
+
+        // stay lifted until the front spins back up to the bike's speed
+        let still_lifted = match (front, reference) {
+            (Some(front), Some(reference)) => {
+                front < Into::<f32>::into(reference) * (1.0 - self.mode.lift_gap())
+            }
+            _ => false,
+        };
+        let pitch_lift = pitch_deg.is_some_and(|pitch| pitch > self.mode.pitch_limit_deg());
+
+        self.lifted = self.mode != AntiWheelieMode::Off()
+            && (pitch_lift || decel_lift || (self.lifted && still_lifted));
+
+        // ease the cut in and out so the front comes down without a jolt
+        self.cut = if self.lifted {
+            (self.cut + self.mode.cut_rate() * dt_s).min(self.mode.max_cut())
+        } else {
+            (self.cut - self.mode.release_rate() * dt_s).max(0.0)
+        };
+        self.lifted
+    }
+
+    pub fn run_algo(&self, curr_req: Percentage) -> Percentage {
+        (curr_req.to_fractional() * (1.0 - self.cut)).into()
+    }
+
+    pub fn reset(&mut self) {
+        self.lifted = false;
+        self.cut = 0.0;
+        self.prev_front = None;
+        self.prev_rear = None;
+        self.prev_timestamp = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const STEP_MS: u64 = 20;
+
+    struct Bench {
+        anti_wheelie: AntiWheelie,
+        now_ms: u64,
+    }
+
+    impl Bench {
+        fn new(mode: AntiWheelieMode) -> Self {
+            Bench {
+                anti_wheelie: AntiWheelie::new(mode),
+                now_ms: 0,
+            }
+        }
+
+        // one control cycle with the reference at the rear wheel's speed
+        fn step(&mut self, front: u16, rear: u16, pitch_deg: f32) -> bool {
+            let lifted = self.anti_wheelie.update(
+                Timestamp::from_micros(self.now_ms * 1000),
+                Some(WheelSpeed::from(front)),
+                Some(WheelSpeed::from(rear)),
+                Some(WheelSpeed::from(rear)),
+                Some(pitch_deg),
+            );
+            self.now_ms += STEP_MS;
+            lifted
+        }
+
+        fn output(&self) -> Percentage {
+            self.anti_wheelie.run_algo(Percentage::full())
+        }
+    }
+
+    fn assert_near(actual: Percentage, expected: f32) {
+        assert!(
+            (actual.to_fractional() - expected).abs() < 1e-3,
+            "expected {} got {}",
+            expected,
+            actual.to_fractional()
+        );
+    }
+
+    #[test]
+    fn front_stopping_alone_is_a_lift() {
+        let mut bench = Bench::new(AntiWheelieMode::Level1());
+        assert!(!bench.step(200, 200, 0.0));
+        // 10 rpm in 20 ms is 500 rpm/s, past the 250 rpm/s limit
+        assert!(bench.step(190, 200, 0.0));
+    }
+
+    #[test]
+    fn braking_both_wheels_is_not_a_lift() {
+        let mut bench = Bench::new(AntiWheelieMode::Level1());
+        bench.step(200, 200, 0.0);
+        assert!(!bench.step(190, 190, 0.0));
+    }
+
+    #[test]
+    fn gentle_front_decel_is_not_a_lift() {
+        let mut bench = Bench::new(AntiWheelieMode::Level1());
+        bench.step(200, 200, 0.0);
+        // 4 rpm in 20 ms is 200 rpm/s
+        assert!(!bench.step(196, 200, 0.0));
+        // the same drop is well inside the softer mode's limit
+        let mut bench = Bench::new(AntiWheelieMode::Level0());
+        bench.step(200, 200, 0.0);
+        assert!(!bench.step(194, 200, 0.0));
+    }
+
+    #[test]
+    fn pitch_over_the_limit_is_a_lift() {
+        let mut bench = Bench::new(AntiWheelieMode::Level1());
+        assert!(!bench.step(200, 200, 14.0));
+        assert!(bench.step(200, 200, 16.0));
+        assert!(!bench.step(200, 200, 10.0));
+
+        let mut bench = Bench::new(AntiWheelieMode::Level0());
+        assert!(!bench.step(200, 200, 20.0));
+        assert!(bench.step(200, 200, 26.0));
+    }
+
+    #[test]
+    fn pitch_alone_needs_no_wheel_speeds() {
+        let mut anti_wheelie = AntiWheelie::new(AntiWheelieMode::Level1());
+        assert!(anti_wheelie.update(Timestamp::from_micros(0), None, None, None, Some(20.0)));
+        assert!(!anti_wheelie.update(Timestamp::from_micros(20_000), None, None, None, None));
+    }
+
+    #[test]
+    fn stays_lifted_until_front_catches_up() {
+        let mut bench = Bench::new(AntiWheelieMode::Level1());
+        bench.step(200, 200, 0.0);
+        assert!(bench.step(150, 200, 0.0));
+        // no more decel, but the front is still more than 20% under the reference
+        for _ in 0..10 {
+            assert!(bench.step(150, 200, 0.0));
+        }
+        assert!(bench.step(155, 200, 0.0));
+        // back within 20% of the reference
+        assert!(!bench.step(165, 200, 0.0));
+        // a slow front on its own does not lift again
+        assert!(!bench.step(165, 200, 0.0));
+    }
+
+    #[test]
+    fn cut_builds_then_releases_at_mode_rates() {
+        let mut bench = Bench::new(AntiWheelieMode::Level1());
+        // the first cycle has no elapsed time
+        bench.step(200, 200, 20.0);
+        assert_near(bench.output(), 1.0);
+
+        // 4 per second builds 0.08 a cycle
+        for cycle in 1..=5 {
+            bench.step(200, 200, 20.0);
+            assert_near(bench.output(), 1.0 - 0.08 * cycle as f32);
+        }
+        // capped at 80%
+        for _ in 0..10 {
+            bench.step(200, 200, 20.0);
+        }
+        assert_near(bench.output(), 0.2);
+
+        // 1 per second lets go 0.02 a cycle
+        for cycle in 1..=10 {
+            bench.step(200, 200, 0.0);
+            assert_near(bench.output(), 0.2 + 0.02 * cycle as f32);
+        }
+        for _ in 0..30 {
+            bench.step(200, 200, 0.0);
+        }
+        assert_near(bench.output(), 1.0);
+    }
+
+    #[test]
+    fn softer_mode_cuts_less() {
+        let mut bench = Bench::new(AntiWheelieMode::Level0());
+        for _ in 0..50 {
+            bench.step(200, 200, 30.0);
+        }
+        assert_near(bench.output(), 0.5);
+    }
+
+    #[test]
+    fn off_never_cuts() {
+        let mut bench = Bench::new(AntiWheelieMode::Off());
+        bench.step(200, 200, 90.0);
+        assert!(!bench.step(0, 200, 90.0));
+        assert_near(bench.output(), 1.0);
+    }
+
+    #[test]
+    fn mode_change_drops_the_cut() {
+        let mut bench = Bench::new(AntiWheelieMode::Level1());
+        for _ in 0..5 {
+            bench.step(200, 200, 20.0);
+        }
+        assert!(bench.anti_wheelie.is_lifted());
+        bench.anti_wheelie.update_mode(AntiWheelieMode::Level0());
+        assert!(!bench.anti_wheelie.is_lifted());
+        assert_near(bench.output(), 1.0);
+    }
+}
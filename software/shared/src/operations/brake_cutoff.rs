@@ -0,0 +1,48 @@
+use crate::utils::percentage::Percentage;
+
+// Motor cut-off on brake application. Engages once the brake request exceeds the
+// threshold and only releases once it drops to threshold - hysteresis so a brake
+// lever resting on the threshold doesn't chatter the motor on and off.
+pub struct BrakeCutoff {
+    threshold: Percentage,
+    hysteresis: Percentage,
+    engaged: bool,
+}
+
+impl BrakeCutoff {
+    pub fn new(threshold: Percentage, hysteresis: Percentage) -> Self {
+        BrakeCutoff {
+            threshold,
+            hysteresis,
+            engaged: false,
+        }
+    }
+
+    pub fn update_threshold(&mut self, threshold: Percentage, hysteresis: Percentage) {
+        self.threshold = threshold;
+        self.hysteresis = hysteresis;
+    }
+
+    fn release_point(&self) -> Percentage {
+        if self.hysteresis >= self.threshold {
+            Percentage::zero()
+        } else {
+            self.threshold - self.hysteresis
+        }
+    }
+
+    pub fn run_algo(&mut self, brake_req: Percentage) -> bool {
+        if self.engaged {
+            if brake_req <= self.release_point() {
+                self.engaged = false;
+            }
+        } else if brake_req > self.threshold {
+            self.engaged = true;
+        }
+        self.engaged
+    }
+
+    pub fn is_engaged(&self) -> bool {
+        self.engaged
+    }
+}
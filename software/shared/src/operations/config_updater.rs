@@ -5,6 +5,7 @@ use crate::{
         update::{Update, UpdateField},
     },
     operations::{
+        launch_control::LaunchControlMode,
         throttle_map::{ThottleMap, ThottleMapMode},
         traction_control::TractionControlMode,
     },
@@ -12,11 +13,20 @@ use crate::{
 };
 use micromath::F32Ext;
 
+// top of the knob range for each traction control gain
+const MAX_KP: f32 = 5.0;
+const MAX_KI: f32 = 20.0;
+const MAX_KD: f32 = 0.5;
+
 #[derive(Debug, Copy, Clone, PartialEq)]
 pub enum ConfigUpdateOptions {
     DSL(Percentage),
     TCM(TractionControlMode),
     TMM(ThottleMapMode),
+    LCM(LaunchControlMode),
+    TKP(f32),
+    TKI(f32),
+    TKD(f32),
 }
 
 impl ConfigUpdateOptions {
@@ -25,6 +35,13 @@ impl ConfigUpdateOptions {
             ConfigUpdateOptions::DSL(per) => [Percentage::into(*per), 0, 0, 0, 0, 0, 0],
             ConfigUpdateOptions::TCM(tcm) => [TractionControlMode::into(*tcm), 0, 0, 0, 0, 0, 0],
             ConfigUpdateOptions::TMM(tmm) => [ThottleMapMode::into(*tmm), 0, 0, 0, 0, 0, 0],
+            ConfigUpdateOptions::LCM(lcm) => [u8::from(*lcm), 0, 0, 0, 0, 0, 0],
+            ConfigUpdateOptions::TKP(gain)
+            | ConfigUpdateOptions::TKI(gain)
+            | ConfigUpdateOptions::TKD(gain) => {
+                let [b0, b1, b2, b3] = gain.to_le_bytes();
+                [b0, b1, b2, b3, 0, 0, 0]
+            }
         }
     }
 }
@@ -37,31 +54,49 @@ pub struct ConfigUpdateState {
 impl ConfigUpdateState {
     pub fn new(field_per: Percentage, val_per: Percentage) -> Self {
         let raw_val = field_per.to_fractional();
-        let field = if raw_val <= 0.33 {
-            UpdateField::DSL()
-        } else if raw_val <= 0.66 {
-            UpdateField::TCM()
-        } else {
-            UpdateField::TMM()
+        let field = match (raw_val * 7.0) as u8 {
+            0 => UpdateField::DSL(),
+            1 => UpdateField::TCM(),
+            2 => UpdateField::TMM(),
+            3 => UpdateField::LCM(),
+            4 => UpdateField::TKP(),
+            5 => UpdateField::TKI(),
+            _ => UpdateField::TKD(),
         };
+        // gains move in twentieths of their range so a noisy knob does not spam updates
+        let gain_step = (val_per.to_fractional() * 20.0).round() / 20.0;
 
         let fractional = val_per.to_fractional();
         let data = match field {
             UpdateField::DSL() => {
                 ConfigUpdateOptions::DSL(Percentage::from_int((val_per.to_int() / 10)).into())
             }
-            UpdateField::TCM() => ConfigUpdateOptions::TCM(if fractional <= 0.5 {
+            UpdateField::TCM() => ConfigUpdateOptions::TCM(if fractional <= 0.33 {
                 TractionControlMode::Level0()
-            } else {
+            } else if fractional <= 0.66 {
                 TractionControlMode::Level1()
+            } else {
+                TractionControlMode::Custom()
             }),
-            UpdateField::TMM() => ConfigUpdateOptions::TMM(if fractional <= 0.33 {
+            UpdateField::TMM() => ConfigUpdateOptions::TMM(if fractional <= 0.25 {
                 ThottleMapMode::Level0()
-            } else if fractional <= 0.66 {
+            } else if fractional <= 0.5 {
                 ThottleMapMode::Level1()
-            } else {
+            } else if fractional <= 0.75 {
                 ThottleMapMode::Level2()
+            } else {
+                ThottleMapMode::Table()
             }),
+            UpdateField::LCM() => ConfigUpdateOptions::LCM(if fractional <= 0.33 {
+                LaunchControlMode::Off()
+            } else if fractional <= 0.66 {
+                LaunchControlMode::Level0()
+            } else {
+                LaunchControlMode::Level1()
+            }),
+            UpdateField::TKP() => ConfigUpdateOptions::TKP(gain_step * MAX_KP),
+            UpdateField::TKI() => ConfigUpdateOptions::TKI(gain_step * MAX_KI),
+            UpdateField::TKD() => ConfigUpdateOptions::TKD(gain_step * MAX_KD),
         };
         Self { field, val: data }
     }
@@ -83,7 +118,88 @@ impl ConfigUpdater {
     pub fn new() -> Self {
         Self {}
     }
-    pub fn run(&self, state: ConfigUpdateState) -> Message {
-        Message::UpdateMessage(Update::new(state.field, &state.val.to_bytes()))
+    // a gain is only written when the value knob moves with that gain already selected,
+    // sweeping the field knob across the gains must not overwrite them
+    pub fn run(&self, prev: ConfigUpdateState, state: ConfigUpdateState) -> Option<Message> {
+        if state.field.is_gain() && state.field != prev.field {
+            return None;
+        }
+        Some(Message::UpdateMessage(Update::new(
+            state.field,
+            &state.val.to_bytes(),
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // field knob positions, one per seventh of the range
+    const FIELD_TCM: f32 = 1.5 / 7.0;
+    const FIELD_TKP: f32 = 4.5 / 7.0;
+    const FIELD_TKD: f32 = 6.5 / 7.0;
+
+    fn state(field: f32, val: f32) -> ConfigUpdateState {
+        ConfigUpdateState::new(
+            Percentage::from_fractional(field),
+            Percentage::from_fractional(val),
+        )
+    }
+
+    fn apply(msg: Option<Message>, config: &mut Config) {
+        if let Some(Message::UpdateMessage(req)) = msg {
+            req.update(config).unwrap();
+        }
+    }
+
+    #[test]
+    fn sweeping_field_knob_leaves_gains_alone() {
+        let updater = ConfigUpdater::new();
+        let mut config = Config::default();
+        let gains = config.engine.traction_control_gains;
+        let mut prev = state(0.0, 0.0);
+        for field in [FIELD_TCM, FIELD_TKP, FIELD_TKD] {
+            let next = state(field, 0.0);
+            let msg = updater.run(prev, next);
+            if next.field.is_gain() {
+                assert!(msg.is_none());
+            }
+            apply(msg, &mut config);
+            prev = next;
+        }
+        assert_eq!(config.engine.traction_control_gains.kp, gains.kp);
+        assert_eq!(config.engine.traction_control_gains.kd, gains.kd);
+        // landing on TCM with the value knob low picks level 0, never custom
+        assert_ne!(
+            config.engine.traction_control_mode,
+            TractionControlMode::Custom()
+        );
+    }
+
+    #[test]
+    fn gain_written_without_switching_mode() {
+        let updater = ConfigUpdater::new();
+        let mut config = Config::default();
+        let mode = config.engine.traction_control_mode;
+
+        let selected = state(FIELD_TKP, 0.0);
+        let turned = state(FIELD_TKP, 0.5);
+        apply(updater.run(selected, turned), &mut config);
+        assert_eq!(config.engine.traction_control_gains.kp, 0.5 * MAX_KP);
+        assert_eq!(config.engine.traction_control_mode, mode);
+    }
+
+    #[test]
+    fn bad_gain_is_reported_in_full() {
+        let mut config = Config::default();
+        let req = Update::new(UpdateField::TKI(), &[0, 0, 0xC0, 0xBF, 0, 0, 0]);
+        assert_eq!(
+            req.update(&mut config),
+            Err(crate::messages::error::DecodeError::InvalidFloat {
+                field: "UpdateField::gain",
+                bits: (-1.5f32).to_bits(),
+            })
+        );
     }
 }
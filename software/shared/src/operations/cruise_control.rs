@@ -0,0 +1,256 @@
+use crate::{
+    messages::error::DecodeError,
+    utils::{percentage::Percentage, speed::GroundSpeed, time::Timestamp},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum CruiseCommand {
+    Set(),    // latch the current speed
+    Cancel(), // drop back to the rider's throttle
+}
+
+impl From<CruiseCommand> for u8 {
+    fn from(cmd: CruiseCommand) -> u8 {
+        match cmd {
+            CruiseCommand::Set() => 0,
+            CruiseCommand::Cancel() => 1,
+        }
+    }
+}
+
+impl TryFrom<u8> for CruiseCommand {
+    type Error = DecodeError;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(CruiseCommand::Set()),
+            1 => Ok(CruiseCommand::Cancel()),
+            _ => Err(DecodeError::InvalidValue {
+                field: "CruiseCommand",
+                value,
+            }),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct CruiseControlConfig {
+    // throttle per kph of speed error
+    pub kp: f32,
+    // throttle per kph second of speed error
+    pub ki: f32,
+    pub integral_limit: f32,
+    // cruise won't latch below this speed
+    pub min_speed_kph: f32,
+}
+
+impl Default for CruiseControlConfig {
+    fn default() -> Self {
+        CruiseControlConfig {
+            kp: 0.08,
+            ki: 0.05,
+            integral_limit: 1.0,
+            min_speed_kph: 8.0,
+        }
+    }
+}
+
+pub struct CruiseControl {
+    config: CruiseControlConfig,
+    target: Option<GroundSpeed>,
+    integral: f32,
+    prev_timestamp: Option<Timestamp>,
+    // the grip was open on the last update, closing it from there disengages
+    grip_open: bool,
+}
+
+impl CruiseControl {
+    pub fn new(config: CruiseControlConfig) -> Self {
+        CruiseControl {
+            config,
+            target: None,
+            integral: 0.0,
+            prev_timestamp: None,
+            grip_open: false,
+        }
+    }
+
+    pub fn update_config(&mut self, config: CruiseControlConfig) {
+        self.config = config;
+    }
+
+    pub fn target(&self) -> Option<GroundSpeed> {
+        self.target
+    }
+
+    pub fn is_engaged(&self) -> bool {
+        self.target.is_some()
+    }
+
+    // the current throttle seeds the integrator so the motor doesn't drop out on latch
+    pub fn engage(&mut self, speed: Option<GroundSpeed>, curr_req: Percentage) -> bool {
+        match speed {
+            Some(speed) if speed.kph() >= self.config.min_speed_kph => {
+                self.target = Some(speed);
+                self.integral = curr_req
+                    .to_fractional()
+                    .clamp(-self.config.integral_limit, self.config.integral_limit);
+                self.prev_timestamp = None;
+                self.grip_open = curr_req > Percentage::zero();
+                true
+            }
+            _ => false,
+        }
+    }
+
+    pub fn disengage(&mut self) {
+        self.target = None;
+        self.integral = 0.0;
+        self.prev_timestamp = None;
+    }
+
+    // rolling the grip off disengages, whether it was held through the latch or opened
+    // later to ride over the cruise speed. Latching with the grip already closed holds
+    // the speed hands off until the grip is next used
+    pub fn update_rider(&mut self, throttle_req: Percentage) {
+        if !self.is_engaged() {
+            return;
+        }
+        let grip_open = throttle_req > Percentage::zero();
+        if self.grip_open && !grip_open {
+            self.disengage();
+        }
+        self.grip_open = grip_open;
+    }
+
+    pub fn run_algo(&mut self, curr_time: Timestamp, speed: GroundSpeed) -> Percentage {
+        let target = if let Some(target) = self.target {
+            target
+        } else {
+            return Percentage::zero();
+        };
+
+        let dt_s = if let Some(prev_time) = self.prev_timestamp {
+            curr_time.as_micros().saturating_sub(prev_time.as_micros()) as f32 / 1_000_000.0
+        } else {
+            0.0
+        };
+        self.prev_timestamp = Some(curr_time);
+
+        let error = target.kph() - speed.kph();
+        let proportional = self.config.kp * error;
+        let unclamped = proportional + self.integral;
+        // hold the integrator while the output is pinned and the error pushes further
+        let saturated_high = unclamped >= 1.0 && error > 0.0;
+        let saturated_low = unclamped <= 0.0 && error < 0.0;
+        if !saturated_high && !saturated_low {
+            self.integral = (self.integral + self.config.ki * error * dt_s)
+                .clamp(-self.config.integral_limit, self.config.integral_limit);
+        }
+        Percentage::from_fractional((proportional + self.integral).clamp(0.0, 1.0))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const STEP_MS: u64 = 20;
+
+    fn cruise() -> CruiseControl {
+        CruiseControl::new(CruiseControlConfig::default())
+    }
+
+    fn kph(kph: f32) -> GroundSpeed {
+        GroundSpeed::from_kph(kph)
+    }
+
+    fn pct(value: f32) -> Percentage {
+        Percentage::from_fractional(value)
+    }
+
+    #[test]
+    fn refuses_to_engage_below_min_speed() {
+        let mut cruise = cruise();
+        let min_speed_kph = CruiseControlConfig::default().min_speed_kph;
+        assert!(!cruise.engage(Some(kph(min_speed_kph - 1.0)), pct(0.5)));
+        assert!(!cruise.engage(None, pct(0.5)));
+        assert!(!cruise.is_engaged());
+
+        assert!(cruise.engage(Some(kph(min_speed_kph)), pct(0.5)));
+        assert!((cruise.target().unwrap().kph() - min_speed_kph).abs() < 1e-3);
+    }
+
+    #[test]
+    fn roll_off_after_latching_disengages() {
+        let mut cruise = cruise();
+        cruise.engage(Some(kph(20.0)), pct(0.5));
+        cruise.update_rider(pct(0.5));
+        assert!(cruise.is_engaged());
+        cruise.update_rider(Percentage::zero());
+        assert!(!cruise.is_engaged());
+    }
+
+    #[test]
+    fn latched_with_grip_closed_holds_until_grip_is_used() {
+        let mut cruise = cruise();
+        cruise.engage(Some(kph(20.0)), Percentage::zero());
+        for _ in 0..10 {
+            cruise.update_rider(Percentage::zero());
+        }
+        assert!(cruise.is_engaged());
+
+        // riding over the cruise speed keeps it latched, letting go again hands back
+        cruise.update_rider(pct(0.8));
+        assert!(cruise.is_engaged());
+        cruise.update_rider(Percentage::zero());
+        assert!(!cruise.is_engaged());
+    }
+
+    #[test]
+    fn disengaged_cruise_ignores_the_grip() {
+        let mut cruise = cruise();
+        cruise.update_rider(pct(0.5));
+        cruise.update_rider(Percentage::zero());
+        assert!(!cruise.is_engaged());
+        assert_eq!(
+            cruise.run_algo(Timestamp::from_micros(0), kph(20.0)),
+            Percentage::zero()
+        );
+    }
+
+    #[test]
+    fn integrator_holds_while_output_is_pinned_high() {
+        let mut cruise = cruise();
+        cruise.engage(Some(kph(20.0)), pct(0.9));
+        // well below the target on a climb, the output is pinned at full
+        for step in 0..50 {
+            let out = cruise.run_algo(Timestamp::from_micros(step * STEP_MS * 1000), kph(10.0));
+            assert_eq!(out, Percentage::full());
+        }
+        assert_eq!(cruise.integral, 0.9);
+    }
+
+    #[test]
+    fn integrator_holds_while_output_is_pinned_low() {
+        let mut cruise = cruise();
+        cruise.engage(Some(kph(20.0)), Percentage::zero());
+        // running away downhill, the motor is already off
+        for step in 0..50 {
+            let out = cruise.run_algo(Timestamp::from_micros(step * STEP_MS * 1000), kph(30.0));
+            assert_eq!(out, Percentage::zero());
+        }
+        assert_eq!(cruise.integral, 0.0);
+    }
+
+    #[test]
+    fn integrator_builds_inside_the_output_range() {
+        let mut cruise = cruise();
+        cruise.engage(Some(kph(20.0)), pct(0.3));
+        for step in 0..50 {
+            cruise.run_algo(Timestamp::from_micros(step * STEP_MS * 1000), kph(19.0));
+        }
+        assert!(cruise.integral > 0.3);
+    }
+}
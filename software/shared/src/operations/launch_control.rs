@@ -0,0 +1,335 @@
+use crate::{
+    messages::error::DecodeError,
+    utils::{
+        percentage::Percentage,
+        speed::WheelSpeed,
+        time::{Duration, Timestamp},
+    },
+};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LaunchControlMode {
+    Off(),
+    Level0(), // gentle ramp for loose surfaces
+    Level1(), // aggressive ramp for grippy surfaces
+}
+
+impl LaunchControlMode {
+    // throttle allowed the moment the bike starts rolling
+    pub fn initial_throttle(&self) -> Percentage {
+        match self {
+            LaunchControlMode::Off() => Percentage::full(),
+            LaunchControlMode::Level0() => Percentage::from_fractional(0.3),
+            LaunchControlMode::Level1() => Percentage::from_fractional(0.5),
+        }
+    }
+    // time to ramp from the initial throttle to full
+    pub fn ramp_time(&self) -> Duration {
+        match self {
+            LaunchControlMode::Off() => Duration::from_millis(0),
+            LaunchControlMode::Level0() => Duration::from_millis(1500),
+            LaunchControlMode::Level1() => Duration::from_millis(800),
+        }
+    }
+    // added to the desired slip while launching
+    pub fn slip_offset(&self) -> Percentage {
+        match self {
+            LaunchControlMode::Off() => Percentage::zero(),
+            LaunchControlMode::Level0() => Percentage::from_fractional(0.05),
+            LaunchControlMode::Level1() => Percentage::from_fractional(0.1),
+        }
+    }
+    // front wheel speed at which launch hands over to normal traction control
+    pub fn handover_speed(&self) -> WheelSpeed {
+        match self {
+            LaunchControlMode::Off() => WheelSpeed::zero(),
+            LaunchControlMode::Level0() => WheelSpeed::from(60u16),
+            LaunchControlMode::Level1() => WheelSpeed::from(90u16),
+        }
+    }
+
+    pub fn to_small_str(&self) -> &str {
+        match self {
+            LaunchControlMode::Off() => "OFF",
+            LaunchControlMode::Level0() => "000",
+            LaunchControlMode::Level1() => "001",
+        }
+    }
+}
+
+impl From<LaunchControlMode> for u8 {
+    fn from(mode: LaunchControlMode) -> u8 {
+        match mode {
+            LaunchControlMode::Off() => 0,
+            LaunchControlMode::Level0() => 1,
+            LaunchControlMode::Level1() => 2,
+        }
+    }
+}
+
+impl TryFrom<u8> for LaunchControlMode {
+    type Error = DecodeError;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(LaunchControlMode::Off()),
+            1 => Ok(LaunchControlMode::Level0()),
+            2 => Ok(LaunchControlMode::Level1()),
+            _ => Err(DecodeError::InvalidValue {
+                field: "LaunchControlMode",
+                value,
+            }),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LaunchPhase {
+    Idle,
+    Armed,             // stopped with no request, ready to launch
+    Active(Timestamp), // launching since the given time
+    Done,              // handed over to traction control until the request drops
+}
+
+pub struct LaunchControl {
+    pub mode: LaunchControlMode,
+    phase: LaunchPhase,
+}
+
+impl LaunchControl {
+    pub fn new(mode: LaunchControlMode) -> Self {
+        LaunchControl {
+            mode,
+            phase: LaunchPhase::Idle,
+        }
+    }
+
+    pub fn update_mode(&mut self, mode: LaunchControlMode) {
+        self.mode = mode;
+        self.reset();
+    }
+
+    pub fn phase(&self) -> LaunchPhase {
+        self.phase
+    }
+
+    pub fn is_active(&self) -> bool {
+        matches!(self.phase, LaunchPhase::Active(_))
+    }
+
+    fn at_standstill(front_ws: Option<WheelSpeed>, rear_ws: Option<WheelSpeed>) -> bool {
+        // both wheels have to report, a missing sensor never counts as stopped
+        front_ws == Some(WheelSpeed::zero()) && rear_ws == Some(WheelSpeed::zero())
+    }
+
+    pub fn update(
+        &mut self,
+        curr_time: Timestamp,
+        front_ws: Option<WheelSpeed>,
+        rear_ws: Option<WheelSpeed>,
+        rider_req: Percentage,
+    ) -> LaunchPhase {
+        if self.mode == LaunchControlMode::Off() {
+            self.phase = LaunchPhase::Idle;
+            return self.phase;
+        }
+
+        let requested = rider_req > Percentage::zero();
+        let standstill = Self::at_standstill(front_ws, rear_ws);
+        self.phase = match self.phase {
+            LaunchPhase::Idle | LaunchPhase::Done if !requested => {
+                if standstill {
+                    LaunchPhase::Armed
+                } else {
+                    LaunchPhase::Idle
+                }
+            }
+            LaunchPhase::Armed if requested => LaunchPhase::Active(curr_time),
+            LaunchPhase::Armed if !standstill => LaunchPhase::Idle,
+            LaunchPhase::Active(_) if !requested => LaunchPhase::Idle,
+            LaunchPhase::Active(start) => match front_ws {
+                Some(front_ws) if front_ws >= self.mode.handover_speed() => LaunchPhase::Done,
+                None => LaunchPhase::Done,
+                _ => LaunchPhase::Active(start),
+            },
+            phase => phase,
+        };
+        self.phase
+    }
+
+    // Cap the throttle along the launch ramp, passes through outside of a launch
+    pub fn run_algo(&self, curr_time: Timestamp, curr_req: Percentage) -> Percentage {
+        let start = if let LaunchPhase::Active(start) = self.phase {
+            start
+        } else {
+            return curr_req;
+        };
+
+        let elapsed_ms = curr_time.as_micros().saturating_sub(start.as_micros()) / 1000;
+        let ramp_ms = self.mode.ramp_time().as_millis();
+        let initial = self.mode.initial_throttle().to_fractional();
+        let cap = if ramp_ms == 0 || elapsed_ms >= ramp_ms {
+            1.0
+        } else {
+            initial + (1.0 - initial) * (elapsed_ms as f32 / ramp_ms as f32)
+        };
+
+        if curr_req.to_fractional() > cap {
+            Percentage::from_fractional(cap)
+        } else {
+            curr_req
+        }
+    }
+
+    pub fn reset(&mut self) {
+        self.phase = LaunchPhase::Idle;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MODE: LaunchControlMode = LaunchControlMode::Level0();
+
+    fn at(ms: u64) -> Timestamp {
+        Timestamp::from_micros(ms * 1000)
+    }
+
+    fn stopped() -> Option<WheelSpeed> {
+        Some(WheelSpeed::zero())
+    }
+
+    fn rolling(rpm: u16) -> Option<WheelSpeed> {
+        Some(WheelSpeed::from(rpm))
+    }
+
+    fn pct(value: f32) -> Percentage {
+        Percentage::from_fractional(value)
+    }
+
+    // armed at a standstill, then launched at 100 ms
+    fn launched() -> LaunchControl {
+        let mut launch = LaunchControl::new(MODE);
+        launch.update(at(0), stopped(), stopped(), Percentage::zero());
+        assert_eq!(
+            launch.update(at(100), stopped(), stopped(), pct(1.0)),
+            LaunchPhase::Active(at(100))
+        );
+        launch
+    }
+
+    fn assert_near(actual: Percentage, expected: f32) {
+        assert!(
+            (actual.to_fractional() - expected).abs() < 1e-3,
+            "expected {} got {}",
+            expected,
+            actual.to_fractional()
+        );
+    }
+
+    #[test]
+    fn off_never_arms() {
+        let mut launch = LaunchControl::new(LaunchControlMode::Off());
+        launch.update(at(0), stopped(), stopped(), Percentage::zero());
+        assert_eq!(
+            launch.update(at(20), stopped(), stopped(), pct(1.0)),
+            LaunchPhase::Idle
+        );
+        assert_eq!(launch.run_algo(at(20), pct(1.0)), pct(1.0));
+    }
+
+    #[test]
+    fn arms_only_with_both_wheels_stopped() {
+        let mut launch = LaunchControl::new(MODE);
+        assert_eq!(
+            launch.update(at(0), stopped(), None, Percentage::zero()),
+            LaunchPhase::Idle
+        );
+        assert_eq!(
+            launch.update(at(20), stopped(), rolling(5), Percentage::zero()),
+            LaunchPhase::Idle
+        );
+        assert_eq!(
+            launch.update(at(40), stopped(), stopped(), Percentage::zero()),
+            LaunchPhase::Armed
+        );
+
+        // rolling away without a request disarms
+        assert_eq!(
+            launch.update(at(60), rolling(5), rolling(5), Percentage::zero()),
+            LaunchPhase::Idle
+        );
+    }
+
+    #[test]
+    fn request_without_arming_is_not_a_launch() {
+        let mut launch = LaunchControl::new(MODE);
+        // the request was already there when the bike stopped
+        assert_eq!(
+            launch.update(at(0), stopped(), stopped(), pct(1.0)),
+            LaunchPhase::Idle
+        );
+        assert_eq!(launch.run_algo(at(0), pct(1.0)), pct(1.0));
+    }
+
+    #[test]
+    fn roll_off_aborts() {
+        let mut launch = launched();
+        assert_eq!(
+            launch.update(at(200), rolling(20), rolling(25), Percentage::zero()),
+            LaunchPhase::Idle
+        );
+        assert_eq!(launch.run_algo(at(200), pct(1.0)), pct(1.0));
+    }
+
+    #[test]
+    fn hands_over_at_handover_speed() {
+        let handover_rpm: u16 = MODE.handover_speed().into();
+        let mut launch = launched();
+        assert!(matches!(
+            launch.update(at(200), rolling(handover_rpm - 1), rolling(80), pct(1.0)),
+            LaunchPhase::Active(_)
+        ));
+        assert_eq!(
+            launch.update(at(300), rolling(handover_rpm), rolling(80), pct(1.0)),
+            LaunchPhase::Done
+        );
+
+        // done until the request drops, then ready for the next stop
+        assert_eq!(
+            launch.update(at(400), stopped(), stopped(), pct(1.0)),
+            LaunchPhase::Done
+        );
+        assert_eq!(
+            launch.update(at(500), stopped(), stopped(), Percentage::zero()),
+            LaunchPhase::Armed
+        );
+    }
+
+    #[test]
+    fn lost_front_wheel_hands_over() {
+        let mut launch = launched();
+        assert_eq!(
+            launch.update(at(200), None, rolling(20), pct(1.0)),
+            LaunchPhase::Done
+        );
+    }
+
+    #[test]
+    fn ramp_caps_throttle() {
+        let launch = launched();
+        let initial = MODE.initial_throttle().to_fractional();
+        let ramp_ms = MODE.ramp_time().as_millis();
+
+        assert_near(launch.run_algo(at(100), pct(1.0)), initial);
+        assert_near(
+            launch.run_algo(at(100 + ramp_ms / 2), pct(1.0)),
+            initial + (1.0 - initial) / 2.0,
+        );
+        assert_near(launch.run_algo(at(100 + ramp_ms), pct(1.0)), 1.0);
+
+        // a request under the cap passes as is
+        assert_eq!(launch.run_algo(at(100), pct(0.1)), pct(0.1));
+    }
+}
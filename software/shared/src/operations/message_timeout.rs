@@ -0,0 +1,142 @@
+use crate::{
+    messages::error::DecodeError,
+    utils::time::{Duration, Timestamp},
+};
+
+// Tracks how long ago a periodic message was last received. Receptions are only
+// flagged when processed and get stamped on the next `update`, so all supervision
+// runs off the same clock as the engine subsystem.
+#[derive(Debug, Clone, Copy)]
+pub struct MessageTimeout {
+    last_seen: Option<Timestamp>,
+    pending: bool,
+    timed_out: bool,
+}
+
+impl MessageTimeout {
+    pub fn new() -> Self {
+        Self {
+            last_seen: None,
+            pending: false,
+            // nothing received yet so there is nothing fresh to act on
+            timed_out: true,
+        }
+    }
+
+    pub fn received(&mut self) {
+        self.pending = true;
+    }
+
+    pub fn update(&mut self, now: Timestamp, timeout: Duration) -> bool {
+        if self.pending {
+            self.pending = false;
+            self.last_seen = Some(now);
+        }
+        self.timed_out = if let Some(last_seen) = self.last_seen {
+            let elapsed_ms = now.as_micros().saturating_sub(last_seen.as_micros()) / 1000;
+            elapsed_ms > timeout.as_millis()
+        } else {
+            true
+        };
+        self.timed_out
+    }
+
+    pub fn is_timed_out(&self) -> bool {
+        self.timed_out
+    }
+}
+
+impl Default for MessageTimeout {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct TimeoutStatus {
+    pub ctl: bool,
+    pub front_ws: bool,
+    pub rear_ws: bool,
+    pub cadence: bool,
+    pub torque: bool,
+    pub imu: bool,
+    pub temperature: bool,
+}
+
+impl TimeoutStatus {
+    const CTL_BIT: u8 = 1 << 0;
+    const FRONT_WS_BIT: u8 = 1 << 1;
+    const REAR_WS_BIT: u8 = 1 << 2;
+    const CADENCE_BIT: u8 = 1 << 3;
+    const TORQUE_BIT: u8 = 1 << 4;
+    const IMU_BIT: u8 = 1 << 5;
+    const TEMPERATURE_BIT: u8 = 1 << 6;
+    const ALL_BITS: u8 = Self::CTL_BIT
+        | Self::FRONT_WS_BIT
+        | Self::REAR_WS_BIT
+        | Self::CADENCE_BIT
+        | Self::TORQUE_BIT
+        | Self::IMU_BIT
+        | Self::TEMPERATURE_BIT;
+
+    pub fn any(&self) -> bool {
+        self.ctl
+            || self.front_ws
+            || self.rear_ws
+            || self.cadence
+            || self.torque
+            || self.imu
+            || self.temperature
+    }
+}
+
+impl From<TimeoutStatus> for u8 {
+    fn from(status: TimeoutStatus) -> u8 {
+        let mut flags = 0;
+        if status.ctl {
+            flags |= TimeoutStatus::CTL_BIT;
+        }
+        if status.front_ws {
+            flags |= TimeoutStatus::FRONT_WS_BIT;
+        }
+        if status.rear_ws {
+            flags |= TimeoutStatus::REAR_WS_BIT;
+        }
+        if status.cadence {
+            flags |= TimeoutStatus::CADENCE_BIT;
+        }
+        if status.torque {
+            flags |= TimeoutStatus::TORQUE_BIT;
+        }
+        if status.imu {
+            flags |= TimeoutStatus::IMU_BIT;
+        }
+        if status.temperature {
+            flags |= TimeoutStatus::TEMPERATURE_BIT;
+        }
+        flags
+    }
+}
+
+impl TryFrom<u8> for TimeoutStatus {
+    type Error = DecodeError;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        if value & !Self::ALL_BITS != 0 {
+            return Err(DecodeError::InvalidValue {
+                field: "TimeoutStatus",
+                value,
+            });
+        }
+        Ok(Self {
+            ctl: value & Self::CTL_BIT != 0,
+            front_ws: value & Self::FRONT_WS_BIT != 0,
+            rear_ws: value & Self::REAR_WS_BIT != 0,
+            cadence: value & Self::CADENCE_BIT != 0,
+            torque: value & Self::TORQUE_BIT != 0,
+            imu: value & Self::IMU_BIT != 0,
+            temperature: value & Self::TEMPERATURE_BIT != 0,
+        })
+    }
+}
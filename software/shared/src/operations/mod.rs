@@ -6,3 +6,42 @@ pub mod traction_control;
 
 #[path = "./config_updater.rs"]
 pub mod config_updater;
+
+#[path = "./message_timeout.rs"]
+pub mod message_timeout;
+
+#[path = "./brake_cutoff.rs"]
+pub mod brake_cutoff;
+
+#[path = "./torque_assist.rs"]
+pub mod torque_assist;
+
+#[path = "./speed_limiter.rs"]
+pub mod speed_limiter;
+
+#[path = "./launch_control.rs"]
+pub mod launch_control;
+
+#[path = "./slew_limiter.rs"]
+pub mod slew_limiter;
+
+#[path = "./slip_estimator.rs"]
+pub mod slip_estimator;
+
+#[path = "./anti_wheelie.rs"]
+pub mod anti_wheelie;
+
+#[path = "./cruise_control.rs"]
+pub mod cruise_control;
+
+#[path = "./walk_assist.rs"]
+pub mod walk_assist;
+
+#[path = "./thermal_derate.rs"]
+pub mod thermal_derate;
+
+#[path = "./soc_estimator.rs"]
+pub mod soc_estimator;
+
+#[path = "./power_limiter.rs"]
+pub mod power_limiter;
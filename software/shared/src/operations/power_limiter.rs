@@ -0,0 +1,160 @@
+use crate::{
+    messages::error::DecodeError,
+    utils::{percentage::Percentage, time::Timestamp},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum PowerLimitReason {
+    None(),
+    Current(), // pack current over the limit
+    Voltage(), // pack sagging under the undervoltage threshold
+}
+
+impl PowerLimitReason {
+    pub fn to_small_str(&self) -> &str {
+        match self {
+            PowerLimitReason::None() => "---",
+            PowerLimitReason::Current() => "CUR",
+            PowerLimitReason::Voltage() => "UVL",
+        }
+    }
+}
+
+impl From<PowerLimitReason> for u8 {
+    fn from(reason: PowerLimitReason) -> u8 {
+        match reason {
+            PowerLimitReason::None() => 0,
+            PowerLimitReason::Current() => 1,
+            PowerLimitReason::Voltage() => 2,
+        }
+    }
+}
+
+impl TryFrom<u8> for PowerLimitReason {
+    type Error = DecodeError;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(PowerLimitReason::None()),
+            1 => Ok(PowerLimitReason::Current()),
+            2 => Ok(PowerLimitReason::Voltage()),
+            _ => Err(DecodeError::InvalidValue {
+                field: "PowerLimitReason",
+                value,
+            }),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct PowerLimiterConfig {
+    pub max_current_a: f32,
+    // 13s pack at 3.0 V a cell
+    pub min_voltage_v: f32,
+    // how fast the ceiling comes down, per second for each amp over / volt under
+    pub current_gain: f32,
+    pub voltage_gain: f32,
+    // how fast the ceiling lifts again once back inside the limits, per second
+    pub release_rate: f32,
+}
+
+impl Default for PowerLimiterConfig {
+    fn default() -> Self {
+        PowerLimiterConfig {
+            max_current_a: 30.0,
+            min_voltage_v: 39.0,
+            current_gain: 0.05,
+            voltage_gain: 0.2,
+            release_rate: 0.5,
+        }
+    }
+}
+
+// Integrating ceiling on the motor command, driven down while the pack is outside its
+// limits and let back up slowly once it recovers
+pub struct PowerLimiter {
+    config: PowerLimiterConfig,
+    ceiling: f32,
+    last_output: f32,
+    reason: PowerLimitReason,
+    prev_timestamp: Option<Timestamp>,
+}
+
+impl PowerLimiter {
+    pub fn new(config: PowerLimiterConfig) -> Self {
+        PowerLimiter {
+            config,
+            ceiling: 1.0,
+            last_output: 0.0,
+            reason: PowerLimitReason::None(),
+            prev_timestamp: None,
+        }
+    }
+
+    pub fn update_config(&mut self, config: PowerLimiterConfig) {
+        self.config = config;
+    }
+
+    pub fn update(
+        &mut self,
+        curr_time: Timestamp,
+        pack_voltage_v: Option<f32>,
+        pack_current_a: Option<f32>,
+    ) {
+        let dt_s = if let Some(prev_time) = self.prev_timestamp {
+            curr_time.as_micros().saturating_sub(prev_time.as_micros()) as f32 / 1_000_000.0
+        } else {
+            0.0
+        };
+        self.prev_timestamp = Some(curr_time);
+
+        // how fast each limit wants the ceiling down, negative when inside it
+        let current_rate = pack_current_a
+            .map(|current_a| (current_a - self.config.max_current_a) * self.config.current_gain);
+        let voltage_rate = pack_voltage_v
+            .map(|voltage_v| (self.config.min_voltage_v - voltage_v) * self.config.voltage_gain);
+        let (rate, reason) = match (current_rate, voltage_rate) {
+            (Some(current), Some(voltage)) if voltage > current => {
+                (voltage, PowerLimitReason::Voltage())
+            }
+            (Some(current), _) => (current, PowerLimitReason::Current()),
+            (None, Some(voltage)) => (voltage, PowerLimitReason::Voltage()),
+            // no readings, nothing to hold the ceiling down
+            (None, None) => (-self.config.release_rate, PowerLimitReason::None()),
+        };
+
+        if rate > 0.0 {
+            // start from what the motor is getting so the limit bites straight away
+            if self.reason == PowerLimitReason::None() {
+                self.ceiling = self.ceiling.min(self.last_output);
+            }
+            self.ceiling -= rate * dt_s;
+            self.reason = reason;
+        } else {
+            self.ceiling += self.config.release_rate.min(-rate) * dt_s;
+        }
+        self.ceiling = self.ceiling.clamp(0.0, 1.0);
+        // the reason stays up until the ceiling has fully lifted
+        if rate <= 0.0 && self.ceiling >= 1.0 {
+            self.reason = PowerLimitReason::None();
+        }
+    }
+
+    pub fn reason(&self) -> PowerLimitReason {
+        self.reason
+    }
+
+    pub fn run_algo(&mut self, curr_req: Percentage) -> Percentage {
+        let output = curr_req.to_fractional().min(self.ceiling);
+        self.last_output = output;
+        Percentage::from_fractional(output)
+    }
+
+    pub fn reset(&mut self) {
+        self.ceiling = 1.0;
+        self.last_output = 0.0;
+        self.reason = PowerLimitReason::None();
+        self.prev_timestamp = None;
+    }
+}
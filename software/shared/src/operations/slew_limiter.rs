@@ -0,0 +1,96 @@
+use crate::utils::{percentage::Percentage, time::Timestamp};
+
+#[derive(Debug, Clone, Copy)]
+pub struct SlewLimiterConfig {
+    // full throttle per second the output may climb, 0 disables the limit
+    pub rise_per_s: f32,
+    // full throttle per second the output may drop, kept faster than the rise
+    pub fall_per_s: f32,
+    // low pass time constant applied before the rate limit, 0 disables smoothing
+    pub smoothing_tau_ms: u16,
+}
+
+impl SlewLimiterConfig {
+    // passes the throttle straight through
+    pub fn off() -> Self {
+        SlewLimiterConfig {
+            rise_per_s: 0.0,
+            fall_per_s: 0.0,
+            smoothing_tau_ms: 0,
+        }
+    }
+}
+
+impl Default for SlewLimiterConfig {
+    fn default() -> Self {
+        SlewLimiterConfig {
+            rise_per_s: 2.0,
+            fall_per_s: 8.0,
+            smoothing_tau_ms: 40,
+        }
+    }
+}
+
+pub struct SlewLimiter {
+    config: SlewLimiterConfig,
+    smoothed: f32,
+    output: f32,
+    prev_timestamp: Option<Timestamp>,
+}
+
+impl SlewLimiter {
+    pub fn new(config: SlewLimiterConfig) -> Self {
+        SlewLimiter {
+            config,
+            smoothed: 0.0,
+            output: 0.0,
+            prev_timestamp: None,
+        }
+    }
+
+    pub fn update_config(&mut self, config: SlewLimiterConfig) {
+        self.config = config;
+    }
+
+    pub fn run_algo(&mut self, curr_time: Timestamp, target: Percentage) -> Percentage {
+        let target = target.to_fractional();
+        let dt_s = if let Some(prev_time) = self.prev_timestamp {
+            curr_time.as_micros().saturating_sub(prev_time.as_micros()) as f32 / 1_000_000.0
+        } else {
+            0.0
+        };
+        self.prev_timestamp = Some(curr_time);
+
+        let tau_s = self.config.smoothing_tau_ms as f32 / 1000.0;
+        self.smoothed = if tau_s <= 0.0 {
+            target
+        } else {
+            self.smoothed + dt_s / (tau_s + dt_s) * (target - self.smoothed)
+        };
+
+        let step = self.smoothed - self.output;
+        let rate = if step > 0.0 {
+            self.config.rise_per_s
+        } else {
+            self.config.fall_per_s
+        };
+        let max_step = rate * dt_s;
+        self.output = if rate <= 0.0 || step.abs() <= max_step {
+            self.smoothed
+        } else {
+            self.output + max_step.copysign(step)
+        };
+        Percentage::from_fractional(self.output.clamp(0.0, 1.0))
+    }
+
+    pub fn output(&self) -> Percentage {
+        Percentage::from_fractional(self.output)
+    }
+
+    // drops the output straight to zero, used when the motor has to be cut now
+    pub fn reset(&mut self) {
+        self.smoothed = 0.0;
+        self.output = 0.0;
+        self.prev_timestamp = None;
+    }
+}
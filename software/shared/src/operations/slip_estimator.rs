@@ -0,0 +1,294 @@
+use crate::utils::{percentage::Percentage, speed::WheelSpeed, time::Timestamp};
+
+#[derive(Debug, Clone, Copy)]
+pub struct SlipEstimatorConfig {
+    // below this reference speed the wheel sensors are too coarse to trust
+    pub min_speed_rpm: u16,
+    // how far the reference may rise or fall per second when the front wheel disagrees
+    pub max_accel_mps2: f32,
+    pub max_decel_mps2: f32,
+    // weight of the IMU prediction over the front wheel, 0 ignores the IMU
+    pub imu_weight: f32,
+}
+
+impl Default for SlipEstimatorConfig {
+    fn default() -> Self {
+        SlipEstimatorConfig {
+            min_speed_rpm: 30,
+            max_accel_mps2: 4.0,
+            max_decel_mps2: 10.0,
+            imu_weight: 0.9,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct SlipEstimate {
+    pub reference: WheelSpeed,
+    // rear wheel spinning faster than the bike
+    pub drive_slip: Percentage,
+    // rear wheel dragging slower than the bike
+    pub brake_slip: Percentage,
+    // false while stopped, below the minimum speed or without wheel speeds
+    pub valid: bool,
+}
+
+impl SlipEstimate {
+    pub fn none() -> Self {
+        SlipEstimate {
+            reference: WheelSpeed::zero(),
+            drive_slip: Percentage::zero(),
+            brake_slip: Percentage::zero(),
+            valid: false,
+        }
+    }
+}
+
+pub struct SlipEstimator {
+    config: SlipEstimatorConfig,
+    wheel_diameter_inch: f32,
+    reference_rpm: Option<f32>,
+    prev_timestamp: Option<Timestamp>,
+}
+
+impl SlipEstimator {
+    pub fn new(config: SlipEstimatorConfig, wheel_diameter_inch: f32) -> Self {
+        SlipEstimator {
+            config,
+            wheel_diameter_inch,
+            reference_rpm: None,
+            prev_timestamp: None,
+        }
+    }
+
+    pub fn update_config(&mut self, config: SlipEstimatorConfig, wheel_diameter_inch: f32) {
+        self.config = config;
+        self.wheel_diameter_inch = wheel_diameter_inch;
+    }
+
+    // wheel rpm gained per second at the given acceleration
+    fn accel_to_rpm_per_s(&self, accel_mps2: f32) -> f32 {
+        let circumference_m = core::f32::consts::PI * self.wheel_diameter_inch * 0.0254;
+        if circumference_m <= 0.0 {
+            return 0.0;
+        }
+        accel_mps2 / circumference_m * 60.0
+    }
+
+    fn update_reference(
+        &mut self,
+        dt_s: f32,
+        front_rpm: f32,
+        rear_rpm: f32,
+        accel_mps2: Option<f32>,
+        front_lifted: bool,
+    ) -> f32 {
+        let prev_rpm = if let Some(prev_rpm) = self.reference_rpm {
+            prev_rpm
+        } else {
+            // first sample seeds the reference
+            self.reference_rpm = Some(front_rpm);
+            return front_rpm;
+        };
+
+        // the bike can't change speed faster than this, anything more is the front
+        // wheel lifting or locking
+        let min_rpm =
+            (prev_rpm - self.accel_to_rpm_per_s(self.config.max_decel_mps2) * dt_s).max(0.0);
+        let max_rpm =
+            (prev_rpm + self.accel_to_rpm_per_s(self.config.max_accel_mps2) * dt_s).max(0.0);
+
+        // a front wheel below the band is locked or in the air, and as the reference
+        // doesn't follow it down it stays below until it spins back up to the bike's
+        // speed. With both wheels below the minimum speed the bike has just stopped
+        let min_speed_rpm = self.config.min_speed_rpm as f32;
+        let stopped = front_rpm < min_speed_rpm && rear_rpm < min_speed_rpm;
+        let front_held = (front_lifted || front_rpm < min_rpm) && !stopped;
+
+        let predicted =
+            accel_mps2.map(|accel_mps2| prev_rpm + self.accel_to_rpm_per_s(accel_mps2) * dt_s);
+        let reference_rpm = if front_held {
+            // carry the reference on the IMU, or hold it where it was without one
+            predicted.map_or(prev_rpm, |predicted| predicted.clamp(min_rpm, max_rpm))
+        } else {
+            let front_rpm = front_rpm.clamp(min_rpm, max_rpm);
+            if let Some(predicted) = predicted {
+                let weight = self.config.imu_weight.clamp(0.0, 1.0);
+                weight * predicted + (1.0 - weight) * front_rpm
+            } else {
+                front_rpm
+            }
+        }
+        .max(0.0);
+        self.reference_rpm = Some(reference_rpm);
+        reference_rpm
+    }
+
+    pub fn run_algo(
+        &mut self,
+        curr_time: Timestamp,
+        front_ws: Option<WheelSpeed>,
+        rear_ws: Option<WheelSpeed>,
+        accel_mps2: Option<f32>,
+        front_lifted: bool,
+    ) -> SlipEstimate {
+        let (front_ws, rear_ws) = match (front_ws, rear_ws) {
+            (Some(front_ws), Some(rear_ws)) => (front_ws, rear_ws),
+            _ => {
+                self.reset();
+                return SlipEstimate::none();
+            }
+        };
+
+        let dt_s = if let Some(prev_time) = self.prev_timestamp {
+            curr_time.as_micros().saturating_sub(prev_time.as_micros()) as f32 / 1_000_000.0
+        } else {
+            0.0
+        };
+        self.prev_timestamp = Some(curr_time);
+
+        let rear_rpm: f32 = rear_ws.into();
+        let reference_rpm =
+            self.update_reference(dt_s, front_ws.into(), rear_rpm, accel_mps2, front_lifted);
+        let reference = WheelSpeed::from(reference_rpm);
+        if reference_rpm < self.config.min_speed_rpm as f32
+            && rear_rpm < self.config.min_speed_rpm as f32
+        {
+            return SlipEstimate {
+                reference,
+                ..SlipEstimate::none()
+            };
+        }
+
+        let (drive_slip, brake_slip) = if rear_rpm > reference_rpm {
+            ((rear_rpm - reference_rpm) / rear_rpm, 0.0)
+        } else if reference_rpm > 0.0 {
+            (0.0, (reference_rpm - rear_rpm) / reference_rpm)
+        } else {
+            (0.0, 0.0)
+        };
+        SlipEstimate {
+            reference,
+            drive_slip: Percentage::from_fractional(drive_slip.clamp(0.0, 1.0)),
+            brake_slip: Percentage::from_fractional(brake_slip.clamp(0.0, 1.0)),
+            valid: true,
+        }
+    }
+
+    pub fn reset(&mut self) {
+        self.reference_rpm = None;
+        self.prev_timestamp = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const STEP_MS: u64 = 20;
+
+    struct Bench {
+        estimator: SlipEstimator,
+        now_ms: u64,
+    }
+
+    impl Bench {
+        fn new() -> Self {
+            Bench {
+                estimator: SlipEstimator::new(SlipEstimatorConfig::default(), 26.0),
+                now_ms: 0,
+            }
+        }
+
+        fn run(
+            &mut self,
+            steps: usize,
+            front_rpm: u16,
+            rear_rpm: u16,
+            accel_mps2: Option<f32>,
+            front_lifted: bool,
+        ) -> SlipEstimate {
+            let mut slip = SlipEstimate::none();
+            for _ in 0..steps {
+                self.now_ms += STEP_MS;
+                slip = self.estimator.run_algo(
+                    Timestamp::from_micros(self.now_ms * 1000),
+                    Some(WheelSpeed::from(front_rpm)),
+                    Some(WheelSpeed::from(rear_rpm)),
+                    accel_mps2,
+                    front_lifted,
+                );
+            }
+            slip
+        }
+    }
+
+    fn reference(slip: SlipEstimate) -> f32 {
+        slip.reference.into()
+    }
+
+    #[test]
+    fn follows_front_wheel() {
+        let mut bench = Bench::new();
+        let slip = bench.run(10, 300, 330, None, false);
+        assert!(slip.valid);
+        assert_eq!(reference(slip), 300.0);
+        assert!((slip.drive_slip.to_fractional() - 30.0 / 330.0).abs() < 0.01);
+    }
+
+    #[test]
+    fn front_dropping_out_holds_reference() {
+        let mut bench = Bench::new();
+        bench.run(10, 300, 300, None, false);
+
+        // front stops dead in the air while the rear keeps driving, a second and a half
+        // later the reference is still the bike's speed
+        let slip = bench.run(75, 0, 320, None, false);
+        assert_eq!(reference(slip), 300.0);
+        assert!(slip.drive_slip.to_fractional() < 0.1);
+
+        // and it picks the front back up once it spins up to speed again
+        let slip = bench.run(20, 305, 320, None, false);
+        assert_eq!(reference(slip), 305.0);
+    }
+
+    #[test]
+    fn lifted_front_is_not_followed() {
+        let mut bench = Bench::new();
+        bench.run(10, 300, 300, None, false);
+
+        // spinning down slowly enough to look plausible, but the wheelie check says it's up
+        let slip = bench.run(50, 250, 300, None, true);
+        assert_eq!(reference(slip), 300.0);
+        let slip = bench.run(5, 250, 300, None, false);
+        assert_eq!(reference(slip), 300.0);
+        let slip = bench.run(5, 310, 310, None, false);
+        assert!(reference(slip) > 300.0);
+    }
+
+    #[test]
+    fn held_reference_follows_imu() {
+        let mut bench = Bench::new();
+        bench.run(10, 300, 300, Some(0.0), false);
+
+        // a second of 1 m/s² is 29 rpm on a 26 inch wheel
+        let slip = bench.run(50, 0, 330, Some(1.0), false);
+        assert!((reference(slip) - 329.0).abs() < 1.5, "{}", reference(slip));
+    }
+
+    #[test]
+    fn locked_front_under_braking_keeps_brake_slip() {
+        let mut bench = Bench::new();
+        bench.run(10, 300, 300, None, false);
+
+        let slip = bench.run(10, 0, 150, None, false);
+        assert!(slip.valid);
+        assert!((slip.brake_slip.to_fractional() - 0.5).abs() < 0.01);
+
+        // both wheels at a stand means the bike has stopped, not that the front locked
+        let slip = bench.run(100, 0, 0, None, false);
+        assert!(!slip.valid);
+        assert_eq!(reference(slip), 0.0);
+    }
+}
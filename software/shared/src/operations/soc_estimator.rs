@@ -0,0 +1,286 @@
+use crate::utils::{percentage::Percentage, time::Timestamp};
+
+// resting cell voltage against state of charge for a typical NMC cell
+const OCV_TABLE: [(f32, f32); 12] = [
+    (3.00, 0.00),
+    (3.45, 0.05),
+    (3.55, 0.10),
+    (3.62, 0.20),
+    (3.68, 0.30),
+    (3.74, 0.40),
+    (3.80, 0.50),
+    (3.87, 0.60),
+    (3.94, 0.70),
+    (4.01, 0.80),
+    (4.08, 0.90),
+    (4.20, 1.00),
+];
+
+#[derive(Debug, Clone, Copy)]
+pub struct SocEstimatorConfig {
+    pub capacity_ah: f32,
+    // below this the pack counts as resting and its voltage can be trusted
+    pub rest_current_a: f32,
+    // how long the pack has to rest before the voltage is used
+    pub rest_time_ms: u64,
+    // time constant of the pull towards the resting voltage estimate
+    pub correction_tau_ms: u64,
+}
+
+impl Default for SocEstimatorConfig {
+    fn default() -> Self {
+        SocEstimatorConfig {
+            capacity_ah: 14.0,
+            rest_current_a: 0.5,
+            rest_time_ms: 30_000,
+            correction_tau_ms: 60_000,
+        }
+    }
+}
+
+pub struct SocEstimator {
+    config: SocEstimatorConfig,
+    soc: Option<f32>,
+    resting_ms: u64,
+    prev_timestamp: Option<Timestamp>,
+}
+
+impl SocEstimator {
+    pub fn new(config: SocEstimatorConfig) -> Self {
+        SocEstimator {
+            config,
+            soc: None,
+            resting_ms: 0,
+            prev_timestamp: None,
+        }
+    }
+
+    pub fn update_config(&mut self, config: SocEstimatorConfig) {
+        self.config = config;
+    }
+
+    // state of charge read off the resting voltage of the weakest cell
+    fn soc_from_voltage(cell_v: f32) -> f32 {
+        let (first_v, first_soc) = OCV_TABLE[0];
+        if cell_v <= first_v {
+            return first_soc;
+        }
+        for window in OCV_TABLE.windows(2) {
+            let (low_v, low_soc) = window[0];
+            let (high_v, high_soc) = window[1];
+            if cell_v <= high_v {
+                return low_soc + (cell_v - low_v) / (high_v - low_v) * (high_soc - low_soc);
+            }
+        }
+        OCV_TABLE[OCV_TABLE.len() - 1].1
+    }
+
+    pub fn run_algo(
+        &mut self,
+        curr_time: Timestamp,
+        pack_current_a: f32,
+        cell_min_v: f32,
+    ) -> Percentage {
+        let dt_ms = if let Some(prev_time) = self.prev_timestamp {
+            curr_time.as_micros().saturating_sub(prev_time.as_micros()) / 1000
+        } else {
+            0
+        };
+        self.prev_timestamp = Some(curr_time);
+
+        let voltage_soc = Self::soc_from_voltage(cell_min_v);
+        let soc = if let Some(soc) = self.soc {
+            soc
+        } else {
+            // first sample seeds the estimate, close enough after the pack sat powered off
+            self.soc = Some(voltage_soc);
+            return Percentage::from_fractional(voltage_soc);
+        };
+
+        // coulomb count, discharge current is positive
+        let mut soc = if self.config.capacity_ah > 0.0 {
+            soc - pack_current_a * dt_ms as f32 / (self.config.capacity_ah * 3_600_000.0)
+        } else {
+            soc
+        };
+
+        // the count drifts, pull it back to the voltage once the pack has settled
+        if pack_current_a.abs() < self.config.rest_current_a {
+            self.resting_ms = self.resting_ms.saturating_add(dt_ms);
+        } else {
+            self.resting_ms = 0;
+        }
+        if self.resting_ms >= self.config.rest_time_ms {
+            let weight = if self.config.correction_tau_ms == 0 {
+                1.0
+            } else {
+                (dt_ms as f32 / self.config.correction_tau_ms as f32).min(1.0)
+            };
+            soc += (voltage_soc - soc) * weight;
+        }
+
+        let soc = soc.clamp(0.0, 1.0);
+        self.soc = Some(soc);
+        Percentage::from_fractional(soc)
+    }
+
+    pub fn soc(&self) -> Option<Percentage> {
+        self.soc.map(Percentage::from_fractional)
+    }
+
+    // stop counting over a gap in the readings, the estimate itself is kept
+    pub fn hold(&mut self) {
+        self.prev_timestamp = None;
+        self.resting_ms = 0;
+    }
+
+    pub fn reset(&mut self) {
+        self.soc = None;
+        self.hold();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const STEP_MS: u64 = 1000;
+
+    struct Bench {
+        estimator: SocEstimator,
+        now_ms: u64,
+    }
+
+    impl Bench {
+        // seeded at half charge
+        fn new() -> Self {
+            let mut bench = Bench {
+                estimator: SocEstimator::new(SocEstimatorConfig::default()),
+                now_ms: 0,
+            };
+            bench.step(0.0, 3.80);
+            bench
+        }
+
+        fn step(&mut self, pack_current_a: f32, cell_min_v: f32) -> f32 {
+            let soc = self.estimator.run_algo(
+                Timestamp::from_micros(self.now_ms * 1000),
+                pack_current_a,
+                cell_min_v,
+            );
+            self.now_ms += STEP_MS;
+            soc.to_fractional()
+        }
+    }
+
+    fn assert_near(actual: f32, expected: f32) {
+        assert!(
+            (actual - expected).abs() < 1e-4,
+            "expected {} got {}",
+            expected,
+            actual
+        );
+    }
+
+    #[test]
+    fn ocv_table_lookup() {
+        assert_near(SocEstimator::soc_from_voltage(3.80), 0.5);
+        assert_near(SocEstimator::soc_from_voltage(3.77), 0.45);
+        assert_near(SocEstimator::soc_from_voltage(4.14), 0.95);
+        // clamped past either end
+        assert_near(SocEstimator::soc_from_voltage(2.5), 0.0);
+        assert_near(SocEstimator::soc_from_voltage(4.20), 1.0);
+        assert_near(SocEstimator::soc_from_voltage(4.35), 1.0);
+
+        let mut prev = 0.0;
+        for mv in 3000..=4200 {
+            let soc = SocEstimator::soc_from_voltage(mv as f32 / 1000.0);
+            assert!(soc >= prev, "dips to {} at {} mV", soc, mv);
+            prev = soc;
+        }
+    }
+
+    #[test]
+    fn first_sample_seeds_from_voltage() {
+        let mut estimator = SocEstimator::new(SocEstimatorConfig::default());
+        assert!(estimator.soc().is_none());
+        // seeded from the voltage even under load
+        let soc = estimator.run_algo(Timestamp::from_micros(0), 20.0, 3.94);
+        assert_near(soc.to_fractional(), 0.7);
+    }
+
+    #[test]
+    fn counts_charge_out_and_in() {
+        let mut bench = Bench::new();
+        // 14 A for 36 s is 0.14 Ah, 1% of the 14 Ah pack, the sagging voltage is ignored
+        for _ in 0..35 {
+            bench.step(14.0, 3.50);
+        }
+        assert_near(bench.step(14.0, 3.50), 0.49);
+        // regen puts it back
+        for _ in 0..35 {
+            bench.step(-14.0, 3.90);
+        }
+        assert_near(bench.step(-14.0, 3.90), 0.5);
+    }
+
+    #[test]
+    fn voltage_only_trusted_after_rest() {
+        let mut bench = Bench::new();
+        // the voltage reads 70% but the pack has not rested long enough
+        for _ in 0..29 {
+            assert_near(bench.step(0.0, 3.94), 0.5);
+        }
+        // 30 s of rest, pulled a sixtieth of the way per second
+        assert_near(bench.step(0.0, 3.94), 0.5 + 0.2 / 60.0);
+        let mut soc = 0.0;
+        for _ in 0..600 {
+            soc = bench.step(0.0, 3.94);
+        }
+        assert_near(soc, 0.7);
+    }
+
+    #[test]
+    fn load_restarts_the_rest_timer() {
+        let mut bench = Bench::new();
+        for _ in 0..20 {
+            bench.step(0.0, 3.94);
+        }
+        let loaded = bench.step(1.0, 3.94);
+        for _ in 0..29 {
+            assert_near(bench.step(0.0, 3.94), loaded);
+        }
+        assert!(bench.step(0.0, 3.94) > loaded);
+    }
+
+    #[test]
+    fn hold_skips_the_gap() {
+        let mut bench = Bench::new();
+        bench.estimator.hold();
+        // an hour later at 14 A would have drained the whole pack
+        bench.now_ms += 3_600_000;
+        assert_near(bench.step(14.0, 3.80), 0.5);
+        assert_near(bench.estimator.soc().unwrap().to_fractional(), 0.5);
+        // counting picks up again from the next sample
+        assert_near(bench.step(14.0, 3.80), 0.5 - 1.0 / 3600.0);
+    }
+
+    #[test]
+    fn hold_restarts_the_rest_timer() {
+        let mut bench = Bench::new();
+        for _ in 0..29 {
+            bench.step(0.0, 3.94);
+        }
+        bench.estimator.hold();
+        bench.step(0.0, 3.94);
+        assert_near(bench.step(0.0, 3.94), 0.5);
+    }
+
+    #[test]
+    fn reset_reseeds() {
+        let mut bench = Bench::new();
+        bench.estimator.reset();
+        assert!(bench.estimator.soc().is_none());
+        assert_near(bench.step(0.0, 4.20), 1.0);
+    }
+}
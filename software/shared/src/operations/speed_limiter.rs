@@ -0,0 +1,180 @@
+use crate::{
+    messages::error::DecodeError,
+    utils::{
+        percentage::Percentage,
+        speed::{GroundSpeed, WheelSpeed},
+    },
+};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SpeedLimitClass {
+    Off(),      // no limit, off-road use only
+    Eu(),       // EN 15194 pedelec, 25 km/h
+    UsClass1(), // pedal assist, 20 mph
+    UsClass2(), // throttle, 20 mph
+    UsClass3(), // speed pedelec, 28 mph
+}
+
+impl SpeedLimitClass {
+    // (start, cut-off) of the assist fade in km/h
+    pub fn fade_kph(&self) -> Option<(f32, f32)> {
+        match self {
+            SpeedLimitClass::Off() => None,
+            SpeedLimitClass::Eu() => Some((23.0, 25.0)),
+            SpeedLimitClass::UsClass1() | SpeedLimitClass::UsClass2() => Some((
+                GroundSpeed { mph: 19.0 }.kph(),
+                GroundSpeed { mph: 20.0 }.kph(),
+            )),
+            SpeedLimitClass::UsClass3() => Some((
+                GroundSpeed { mph: 27.0 }.kph(),
+                GroundSpeed { mph: 28.0 }.kph(),
+            )),
+        }
+    }
+
+    pub fn to_small_str(&self) -> &str {
+        match self {
+            SpeedLimitClass::Off() => "OFF",
+            SpeedLimitClass::Eu() => "EU",
+            SpeedLimitClass::UsClass1() => "US1",
+            SpeedLimitClass::UsClass2() => "US2",
+            SpeedLimitClass::UsClass3() => "US3",
+        }
+    }
+}
+
+impl From<SpeedLimitClass> for u8 {
+    fn from(class: SpeedLimitClass) -> u8 {
+        match class {
+            SpeedLimitClass::Off() => 0,
+            SpeedLimitClass::Eu() => 1,
+            SpeedLimitClass::UsClass1() => 2,
+            SpeedLimitClass::UsClass2() => 3,
+            SpeedLimitClass::UsClass3() => 4,
+        }
+    }
+}
+
+impl TryFrom<u8> for SpeedLimitClass {
+    type Error = DecodeError;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(SpeedLimitClass::Off()),
+            1 => Ok(SpeedLimitClass::Eu()),
+            2 => Ok(SpeedLimitClass::UsClass1()),
+            3 => Ok(SpeedLimitClass::UsClass2()),
+            4 => Ok(SpeedLimitClass::UsClass3()),
+            _ => Err(DecodeError::InvalidValue {
+                field: "SpeedLimitClass",
+                value,
+            }),
+        }
+    }
+}
+
+pub struct SpeedLimiter {
+    pub class: SpeedLimitClass,
+    wheel_diameter_inch: f32,
+}
+
+impl SpeedLimiter {
+    pub fn new(class: SpeedLimitClass, wheel_diameter_inch: f32) -> Self {
+        SpeedLimiter {
+            class,
+            wheel_diameter_inch,
+        }
+    }
+
+    pub fn update_config(&mut self, class: SpeedLimitClass, wheel_diameter_inch: f32) {
+        self.class = class;
+        self.wheel_diameter_inch = wheel_diameter_inch;
+    }
+
+    // Fraction of the request allowed at this speed, fading linearly from full at the
+    // start speed to nothing at the cut-off
+    fn assist_factor(&self, speed_kph: f32) -> f32 {
+        let (start_kph, cutoff_kph) = if let Some(fade) = self.class.fade_kph() {
+            fade
+        } else {
+            return 1.0;
+        };
+        if speed_kph <= start_kph {
+            1.0
+        } else if speed_kph >= cutoff_kph {
+            0.0
+        } else {
+            (cutoff_kph - speed_kph) / (cutoff_kph - start_kph)
+        }
+    }
+
+    pub fn run_algo(&self, front_ws: Option<WheelSpeed>, curr_req: Percentage) -> Percentage {
+        // the front wheel isn't driven so it tracks ground speed even under wheel spin. With
+        // no speed there is no telling we're under the limit, so a limited class gets nothing
+        let front_ws = match front_ws {
+            Some(front_ws) => front_ws,
+            None if self.class.fade_kph().is_some() => return Percentage::zero(),
+            None => return curr_req,
+        };
+        let speed = GroundSpeed::from_wheel_speed(front_ws, self.wheel_diameter_inch);
+        Percentage::from_fractional(curr_req.to_fractional() * self.assist_factor(speed.kph()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const WHEEL_DIAMETER_INCH: f32 = 26.0;
+
+    // wheel speed closest to the given road speed on the test wheel
+    fn wheel_speed(kph: f32) -> WheelSpeed {
+        let per_rpm = GroundSpeed::from_wheel_speed(WheelSpeed::from(1u16), WHEEL_DIAMETER_INCH);
+        WheelSpeed::from((kph / per_rpm.kph()).round())
+    }
+
+    fn assist(class: SpeedLimitClass, front_ws: Option<WheelSpeed>) -> f32 {
+        SpeedLimiter::new(class, WHEEL_DIAMETER_INCH)
+            .run_algo(front_ws, Percentage::full())
+            .to_fractional()
+    }
+
+    #[test]
+    fn fade_band_is_linear() {
+        let limiter = SpeedLimiter::new(SpeedLimitClass::Eu(), WHEEL_DIAMETER_INCH);
+        assert_eq!(limiter.assist_factor(20.0), 1.0);
+        assert_eq!(limiter.assist_factor(23.0), 1.0);
+        assert_eq!(limiter.assist_factor(23.5), 0.75);
+        assert_eq!(limiter.assist_factor(24.0), 0.5);
+        assert_eq!(limiter.assist_factor(25.0), 0.0);
+        assert_eq!(limiter.assist_factor(40.0), 0.0);
+
+        // the US classes fade over the last mph before the limit
+        let (start, cutoff) = SpeedLimitClass::UsClass3().fade_kph().unwrap();
+        assert!((start - 27.0 * 1.609344).abs() < 1e-3);
+        assert!((cutoff - 28.0 * 1.609344).abs() < 1e-3);
+    }
+
+    #[test]
+    fn assist_follows_front_wheel() {
+        assert_eq!(assist(SpeedLimitClass::Eu(), Some(wheel_speed(15.0))), 1.0);
+        let mid = assist(SpeedLimitClass::Eu(), Some(wheel_speed(24.0)));
+        assert!(mid > 0.3 && mid < 0.7, "{}", mid);
+        assert_eq!(assist(SpeedLimitClass::Eu(), Some(wheel_speed(30.0))), 0.0);
+        assert_eq!(assist(SpeedLimitClass::Off(), Some(wheel_speed(60.0))), 1.0);
+    }
+
+    #[test]
+    fn missing_speed_fails_safe() {
+        for class in [
+            SpeedLimitClass::Eu(),
+            SpeedLimitClass::UsClass1(),
+            SpeedLimitClass::UsClass2(),
+            SpeedLimitClass::UsClass3(),
+        ] {
+            assert_eq!(assist(class, None), 0.0);
+        }
+        // nothing to enforce off-road
+        assert_eq!(assist(SpeedLimitClass::Off(), None), 1.0);
+    }
+}
@@ -0,0 +1,227 @@
+use crate::{messages::error::DecodeError, utils::percentage::Percentage};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum TemperatureSensor {
+    MotorWinding(),
+    ControllerMosfet(),
+}
+
+impl From<TemperatureSensor> for u8 {
+    fn from(sensor: TemperatureSensor) -> u8 {
+        match sensor {
+            TemperatureSensor::MotorWinding() => 0,
+            TemperatureSensor::ControllerMosfet() => 1,
+        }
+    }
+}
+
+impl TryFrom<u8> for TemperatureSensor {
+    type Error = DecodeError;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(TemperatureSensor::MotorWinding()),
+            1 => Ok(TemperatureSensor::ControllerMosfet()),
+            _ => Err(DecodeError::InvalidValue {
+                field: "TemperatureSensor",
+                value,
+            }),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct ThermalLimit {
+    // full output up to here, then a straight line down
+    pub derate_start_c: f32,
+    // no output at or above this
+    pub cutoff_c: f32,
+}
+
+impl ThermalLimit {
+    // fraction of full output allowed at this temperature
+    fn allowed(&self, temp_c: f32) -> f32 {
+        if temp_c >= self.cutoff_c {
+            0.0
+        } else if temp_c <= self.derate_start_c {
+            1.0
+        } else {
+            (self.cutoff_c - temp_c) / (self.cutoff_c - self.derate_start_c)
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct ThermalDerateConfig {
+    pub motor: ThermalLimit,
+    pub controller: ThermalLimit,
+}
+
+impl Default for ThermalDerateConfig {
+    fn default() -> Self {
+        ThermalDerateConfig {
+            // class H windings, leave margin for the sensor sitting off the hot spot
+            motor: ThermalLimit {
+                derate_start_c: 110.0,
+                cutoff_c: 140.0,
+            },
+            controller: ThermalLimit {
+                derate_start_c: 75.0,
+                cutoff_c: 95.0,
+            },
+        }
+    }
+}
+
+pub struct ThermalDerate {
+    config: ThermalDerateConfig,
+    motor_allowed: f32,
+    controller_allowed: f32,
+}
+
+impl ThermalDerate {
+    pub fn new(config: ThermalDerateConfig) -> Self {
+        ThermalDerate {
+            config,
+            motor_allowed: 1.0,
+            controller_allowed: 1.0,
+        }
+    }
+
+    pub fn update_config(&mut self, config: ThermalDerateConfig) {
+        self.config = config;
+    }
+
+    // a sensor that drops out keeps its last limit, one that never reported never limits
+    pub fn update(&mut self, motor_temp_c: Option<f32>, controller_temp_c: Option<f32>) {
+        if let Some(temp_c) = motor_temp_c {
+            self.motor_allowed = self.config.motor.allowed(temp_c);
+        }
+        if let Some(temp_c) = controller_temp_c {
+            self.controller_allowed = self.config.controller.allowed(temp_c);
+        }
+    }
+
+    pub fn allowed(&self) -> Percentage {
+        Percentage::from_fractional(self.motor_allowed.min(self.controller_allowed))
+    }
+
+    // share of the output taken away, zero when running cool
+    pub fn derate(&self) -> Percentage {
+        Percentage::from_fractional(1.0 - self.motor_allowed.min(self.controller_allowed))
+    }
+
+    pub fn run_algo(&self, curr_req: Percentage) -> Percentage {
+        let allowed = self.allowed();
+        if curr_req > allowed {
+            allowed
+        } else {
+            curr_req
+        }
+    }
+
+    pub fn reset(&mut self) {
+        self.motor_allowed = 1.0;
+        self.controller_allowed = 1.0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn derate() -> ThermalDerate {
+        ThermalDerate::new(ThermalDerateConfig::default())
+    }
+
+    fn assert_near(actual: Percentage, expected: f32) {
+        assert!(
+            (actual.to_fractional() - expected).abs() < 1e-3,
+            "expected {} got {}",
+            expected,
+            actual.to_fractional()
+        );
+    }
+
+    #[test]
+    fn full_output_when_cool() {
+        let mut thermal = derate();
+        thermal.update(Some(25.0), Some(25.0));
+        assert_near(thermal.allowed(), 1.0);
+        assert_near(thermal.derate(), 0.0);
+        thermal.update(Some(110.0), Some(75.0));
+        assert_near(thermal.allowed(), 1.0);
+    }
+
+    #[test]
+    fn motor_derates_linearly_to_cutoff() {
+        let mut thermal = derate();
+        thermal.update(Some(120.0), None);
+        assert_near(thermal.allowed(), 2.0 / 3.0);
+        thermal.update(Some(125.0), None);
+        assert_near(thermal.allowed(), 0.5);
+        assert_near(thermal.derate(), 0.5);
+        thermal.update(Some(140.0), None);
+        assert_near(thermal.allowed(), 0.0);
+        thermal.update(Some(160.0), None);
+        assert_near(thermal.allowed(), 0.0);
+    }
+
+    #[test]
+    fn controller_derates_linearly_to_cutoff() {
+        let mut thermal = derate();
+        thermal.update(None, Some(80.0));
+        assert_near(thermal.allowed(), 0.75);
+        thermal.update(None, Some(90.0));
+        assert_near(thermal.allowed(), 0.25);
+        thermal.update(None, Some(95.0));
+        assert_near(thermal.allowed(), 0.0);
+    }
+
+    #[test]
+    fn hotter_sensor_sets_the_limit() {
+        let mut thermal = derate();
+        // motor allows half, controller a quarter
+        thermal.update(Some(125.0), Some(90.0));
+        assert_near(thermal.allowed(), 0.25);
+        thermal.update(Some(125.0), Some(80.0));
+        assert_near(thermal.allowed(), 0.5);
+    }
+
+    #[test]
+    fn dropped_sensor_holds_its_last_limit() {
+        let mut thermal = derate();
+        thermal.update(Some(125.0), Some(25.0));
+        thermal.update(None, Some(25.0));
+        assert_near(thermal.allowed(), 0.5);
+        thermal.update(None, None);
+        assert_near(thermal.allowed(), 0.5);
+        // and picks up again when it comes back
+        thermal.update(Some(30.0), None);
+        assert_near(thermal.allowed(), 1.0);
+    }
+
+    #[test]
+    fn silent_sensors_never_limit() {
+        let mut thermal = derate();
+        thermal.update(None, None);
+        assert_near(thermal.allowed(), 1.0);
+    }
+
+    #[test]
+    fn caps_the_request() {
+        let mut thermal = derate();
+        thermal.update(Some(125.0), None);
+        assert_near(thermal.run_algo(Percentage::full()), 0.5);
+        assert_near(thermal.run_algo(Percentage::from_fractional(0.3)), 0.3);
+    }
+
+    #[test]
+    fn reset_clears_the_held_limit() {
+        let mut thermal = derate();
+        thermal.update(Some(150.0), Some(100.0));
+        thermal.reset();
+        assert_near(thermal.allowed(), 1.0);
+    }
+}
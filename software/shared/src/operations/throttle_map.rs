@@ -1,6 +1,13 @@
-use crate::utils::percentage::Percentage;
+use crate::{
+    messages::{error::DecodeError, messages::throttle_table::ThrottleTableMessage},
+    utils::percentage::Percentage,
+};
 use micromath::F32Ext;
 
+pub const THROTTLE_TABLE_MAX_POINTS: usize = 8;
+// breakpoints carried by one throttle table frame
+pub const THROTTLE_TABLE_POINTS_PER_FRAME: usize = 2;
+
 // aggressive throttle application first
 fn level_0(req: Percentage) -> Percentage {
     ((Into::<f32>::into(req)).powf(0.5)).into()
@@ -21,14 +28,16 @@ pub enum ThottleMapMode {
     Level0(),
     Level1(),
     Level2(),
+    Table(), // user supplied lookup table
 }
 
 impl ThottleMapMode {
-    pub fn update(&self, req: Percentage) -> Percentage {
+    pub fn update(&self, req: Percentage, table: &ThrottleTable) -> Percentage {
         match self {
             ThottleMapMode::Level0() => level_0(req),
             ThottleMapMode::Level1() => level_1(req),
             ThottleMapMode::Level2() => level_2(req),
+            ThottleMapMode::Table() => table.lookup(req),
         }
     }
 
@@ -37,6 +46,7 @@ impl ThottleMapMode {
             ThottleMapMode::Level0() => "000",
             ThottleMapMode::Level1() => "001",
             ThottleMapMode::Level2() => "002",
+            ThottleMapMode::Table() => "TBL",
         }
     }
 }
@@ -47,34 +57,601 @@ impl Into<u8> for ThottleMapMode {
             ThottleMapMode::Level0() => 0,
             ThottleMapMode::Level1() => 1,
             ThottleMapMode::Level2() => 2,
+            ThottleMapMode::Table() => 3,
+        }
+    }
+}
+
+impl TryFrom<u8> for ThottleMapMode {
+    type Error = DecodeError;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(ThottleMapMode::Level0()),
+            1 => Ok(ThottleMapMode::Level1()),
+            2 => Ok(ThottleMapMode::Level2()),
+            3 => Ok(ThottleMapMode::Table()),
+            _ => Err(DecodeError::InvalidValue {
+                field: "ThottleMapMode",
+                value,
+            }),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TableInterpolation {
+    Linear(),
+    MonotoneCubic(), // smooth, never overshoots between breakpoints
+}
+
+impl From<TableInterpolation> for u8 {
+    fn from(interpolation: TableInterpolation) -> u8 {
+        match interpolation {
+            TableInterpolation::Linear() => 0,
+            TableInterpolation::MonotoneCubic() => 1,
+        }
+    }
+}
+
+impl TryFrom<u8> for TableInterpolation {
+    type Error = DecodeError;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(TableInterpolation::Linear()),
+            1 => Ok(TableInterpolation::MonotoneCubic()),
+            _ => Err(DecodeError::InvalidValue {
+                field: "TableInterpolation",
+                value,
+            }),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ThrottleBreakpoint {
+    pub input: Percentage,
+    pub output: Percentage,
+}
+
+impl ThrottleBreakpoint {
+    pub fn new(input: Percentage, output: Percentage) -> Self {
+        ThrottleBreakpoint { input, output }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ThrottleTable {
+    points: [ThrottleBreakpoint; THROTTLE_TABLE_MAX_POINTS],
+    len: usize,
+    pub interpolation: TableInterpolation,
+}
+
+impl ThrottleTable {
+    // needs at least two breakpoints with strictly increasing inputs
+    pub fn new(
+        breakpoints: &[ThrottleBreakpoint],
+        interpolation: TableInterpolation,
+    ) -> Result<Self, DecodeError> {
+        let len = breakpoints.len();
+        if !(2..=THROTTLE_TABLE_MAX_POINTS).contains(&len) {
+            return Err(DecodeError::InvalidValue {
+                field: "ThrottleTable::len",
+                value: len as u8,
+            });
+        }
+        for pair in breakpoints.windows(2) {
+            if pair[1].input <= pair[0].input {
+                return Err(DecodeError::InvalidValue {
+                    field: "ThrottleTable::input",
+                    value: pair[1].input.into(),
+                });
+            }
+        }
+
+        let mut points = [ThrottleBreakpoint::new(Percentage::zero(), Percentage::zero());
+            THROTTLE_TABLE_MAX_POINTS];
+        points[..len].copy_from_slice(breakpoints);
+        Ok(ThrottleTable {
+            points,
+            len,
+            interpolation,
+        })
+    }
+
+    // samples one of the fixed curves so it can be used as a starting point
+    pub fn from_preset(mode: ThottleMapMode) -> Self {
+        let mode = match mode {
+            ThottleMapMode::Table() => ThottleMapMode::Level1(),
+            mode => mode,
+        };
+        let mut points = [ThrottleBreakpoint::new(Percentage::zero(), Percentage::zero());
+            THROTTLE_TABLE_MAX_POINTS];
+        for (i, point) in points.iter_mut().enumerate() {
+            let input =
+                Percentage::from_fractional(i as f32 / (THROTTLE_TABLE_MAX_POINTS - 1) as f32);
+            // the fixed curves never look at the table
+            let output = match mode {
+                ThottleMapMode::Level0() => level_0(input),
+                ThottleMapMode::Level2() => level_2(input),
+                _ => level_1(input),
+            };
+            *point = ThrottleBreakpoint::new(input, output);
+        }
+        ThrottleTable {
+            points,
+            len: THROTTLE_TABLE_MAX_POINTS,
+            interpolation: TableInterpolation::MonotoneCubic(),
         }
     }
+
+    pub fn breakpoints(&self) -> &[ThrottleBreakpoint] {
+        &self.points[..self.len]
+    }
+
+    pub fn lookup(&self, req: Percentage) -> Percentage {
+        let points = self.breakpoints();
+        let first = points[0];
+        let last = points[self.len - 1];
+        if req <= first.input {
+            return first.output;
+        }
+        if req >= last.input {
+            return last.output;
+        }
+
+        // find the segment holding the request
+        let mut seg = 0;
+        while seg + 2 < self.len && req >= points[seg + 1].input {
+            seg += 1;
+        }
+        let x = req.to_fractional();
+        let x0 = points[seg].input.to_fractional();
+        let x1 = points[seg + 1].input.to_fractional();
+        let y0 = points[seg].output.to_fractional();
+        let y1 = points[seg + 1].output.to_fractional();
+        let h = x1 - x0;
+        let t = (x - x0) / h;
+
+        let y = match self.interpolation {
+            TableInterpolation::Linear() => y0 + t * (y1 - y0),
+            TableInterpolation::MonotoneCubic() => {
+                let tangents = self.tangents();
+                let t2 = t * t;
+                let t3 = t2 * t;
+                (2.0 * t3 - 3.0 * t2 + 1.0) * y0
+                    + (t3 - 2.0 * t2 + t) * h * tangents[seg]
+                    + (-2.0 * t3 + 3.0 * t2) * y1
+                    + (t3 - t2) * h * tangents[seg + 1]
+            }
+        };
+        y.clamp(0.0, 1.0).into()
+    }
+
+    // Fritsch-Carlson tangents, limited so each segment stays monotone
+    fn tangents(&self) -> [f32; THROTTLE_TABLE_MAX_POINTS] {
+        let points = self.breakpoints();
+        let n = self.len;
+        let mut secants = [0.0f32; THROTTLE_TABLE_MAX_POINTS];
+        for k in 0..n - 1 {
+            let dx = points[k + 1].input.to_fractional() - points[k].input.to_fractional();
+            let dy = points[k + 1].output.to_fractional() - points[k].output.to_fractional();
+            secants[k] = dy / dx;
+        }
+
+        let mut tangents = [0.0f32; THROTTLE_TABLE_MAX_POINTS];
+        tangents[0] = secants[0];
+        tangents[n - 1] = secants[n - 2];
+        for k in 1..n - 1 {
+            tangents[k] = if secants[k - 1] * secants[k] <= 0.0 {
+                0.0
+            } else {
+                (secants[k - 1] + secants[k]) / 2.0
+            };
+        }
+
+        for k in 0..n - 1 {
+            if secants[k] == 0.0 {
+                tangents[k] = 0.0;
+                tangents[k + 1] = 0.0;
+                continue;
+            }
+            let alpha = tangents[k] / secants[k];
+            let beta = tangents[k + 1] / secants[k];
+            let mag = alpha * alpha + beta * beta;
+            if mag > 9.0 {
+                let tau = 3.0 / mag.sqrt();
+                tangents[k] = tau * alpha * secants[k];
+                tangents[k + 1] = tau * beta * secants[k];
+            }
+        }
+        tangents
+    }
+
+    pub fn frame_count(&self) -> usize {
+        self.len.div_ceil(THROTTLE_TABLE_POINTS_PER_FRAME)
+    }
+
+    // splits the table into frames for a multi-frame upload
+    pub fn to_messages(&self) -> impl Iterator<Item = ThrottleTableMessage> + '_ {
+        (0..self.frame_count()).map(move |index| {
+            let start = index * THROTTLE_TABLE_POINTS_PER_FRAME;
+            let end = (start + THROTTLE_TABLE_POINTS_PER_FRAME).min(self.len);
+            let mut points = [None; THROTTLE_TABLE_POINTS_PER_FRAME];
+            for (slot, point) in points.iter_mut().zip(&self.points[start..end]) {
+                *slot = Some(*point);
+            }
+            ThrottleTableMessage {
+                index: index as u8,
+                point_count: self.len as u8,
+                interpolation: self.interpolation,
+                points,
+            }
+        })
+    }
+}
+
+impl Default for ThrottleTable {
+    fn default() -> Self {
+        ThrottleTable::from_preset(ThottleMapMode::Level1())
+    }
+}
+
+// reassembles a throttle table from its frames, frames must arrive in order
+#[derive(Debug, Clone, Copy)]
+pub struct ThrottleTableUpload {
+    points: [ThrottleBreakpoint; THROTTLE_TABLE_MAX_POINTS],
+    point_count: u8,
+    interpolation: TableInterpolation,
+    next_index: Option<u8>,
 }
 
-impl From<u8> for ThottleMapMode {
-    fn from(value: u8) -> Self {
-        if value == 2 {
-            ThottleMapMode::Level2()
-        } else if value == 1 {
-            ThottleMapMode::Level1()
-        } else {
-            ThottleMapMode::Level0()
+impl ThrottleTableUpload {
+    pub fn new() -> Self {
+        ThrottleTableUpload {
+            points: [ThrottleBreakpoint::new(Percentage::zero(), Percentage::zero());
+                THROTTLE_TABLE_MAX_POINTS],
+            point_count: 0,
+            interpolation: TableInterpolation::Linear(),
+            next_index: None,
+        }
+    }
+
+    pub fn in_progress(&self) -> bool {
+        self.next_index.is_some()
+    }
+
+    // returns the finished table once the last frame is in
+    pub fn push(
+        &mut self,
+        msg: ThrottleTableMessage,
+    ) -> Result<Option<ThrottleTable>, DecodeError> {
+        if msg.index == 0 {
+            // a first frame always restarts the transfer
+            self.point_count = msg.point_count;
+            self.interpolation = msg.interpolation;
+            self.next_index = Some(0);
         }
+
+        let expected = match self.next_index {
+            Some(index)
+                if index == msg.index
+                    && self.point_count == msg.point_count
+                    && self.interpolation == msg.interpolation =>
+            {
+                index
+            }
+            _ => {
+                self.reset();
+                return Err(DecodeError::InvalidValue {
+                    field: "ThrottleTableMessage::index",
+                    value: msg.index,
+                });
+            }
+        };
+
+        let start = expected as usize * THROTTLE_TABLE_POINTS_PER_FRAME;
+        for (i, point) in msg.points.iter().enumerate() {
+            let idx = start + i;
+            if idx >= self.point_count as usize {
+                break;
+            }
+            match point {
+                Some(point) if idx < THROTTLE_TABLE_MAX_POINTS => self.points[idx] = *point,
+                _ => {
+                    self.reset();
+                    return Err(DecodeError::InvalidValue {
+                        field: "ThrottleTableMessage::point_count",
+                        value: msg.point_count,
+                    });
+                }
+            }
+        }
+
+        let received = (expected as usize + 1) * THROTTLE_TABLE_POINTS_PER_FRAME;
+        if received < self.point_count as usize {
+            self.next_index = Some(expected + 1);
+            return Ok(None);
+        }
+
+        let count = self.point_count as usize;
+        let interpolation = self.interpolation;
+        self.reset();
+        ThrottleTable::new(&self.points[..count], interpolation).map(Some)
+    }
+
+    pub fn reset(&mut self) {
+        self.next_index = None;
+    }
+}
+
+impl Default for ThrottleTableUpload {
+    fn default() -> Self {
+        Self::new()
     }
 }
 
 pub struct ThottleMap {
     pub mode: ThottleMapMode,
+    pub table: ThrottleTable,
 }
 
 impl ThottleMap {
-    pub fn new(mode: ThottleMapMode) -> Self {
-        ThottleMap { mode }
+    pub fn new(mode: ThottleMapMode, table: ThrottleTable) -> Self {
+        ThottleMap { mode, table }
     }
     pub fn update_mode(&mut self, mode: ThottleMapMode) {
         self.mode = mode;
     }
+    pub fn update_table(&mut self, table: ThrottleTable) {
+        self.table = table;
+    }
     pub fn run_algo(&self, req: Percentage) -> Percentage {
-        self.mode.update(req)
+        self.mode.update(req, &self.table)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn point(input: f32, output: f32) -> ThrottleBreakpoint {
+        ThrottleBreakpoint::new(
+            Percentage::from_fractional(input),
+            Percentage::from_fractional(output),
+        )
+    }
+
+    fn table(points: &[(f32, f32)], interpolation: TableInterpolation) -> ThrottleTable {
+        let mut breakpoints = [point(0.0, 0.0); THROTTLE_TABLE_MAX_POINTS];
+        for (slot, (input, output)) in breakpoints.iter_mut().zip(points) {
+            *slot = point(*input, *output);
+        }
+        ThrottleTable::new(&breakpoints[..points.len()], interpolation).unwrap()
+    }
+
+    fn lookup(table: &ThrottleTable, req: f32) -> f32 {
+        table
+            .lookup(Percentage::from_fractional(req))
+            .to_fractional()
+    }
+
+    fn assert_near(actual: f32, expected: f32) {
+        assert!(
+            (actual - expected).abs() < 1e-4,
+            "expected {} got {}",
+            expected,
+            actual
+        );
+    }
+
+    // a fine sweep of the whole throttle range
+    fn sweep() -> impl Iterator<Item = f32> {
+        (0..=1000).map(|i| i as f32 / 1000.0)
+    }
+
+    // slow start then a sharp rise, the kind of table a plain cubic overshoots on
+    const STEEP: [(f32, f32); 4] = [(0.0, 0.0), (0.4, 0.05), (0.5, 0.9), (1.0, 1.0)];
+
+    #[test]
+    fn linear_interpolates_between_breakpoints() {
+        let table = table(
+            &[(0.0, 0.0), (0.5, 0.2), (1.0, 1.0)],
+            TableInterpolation::Linear(),
+        );
+        assert_near(lookup(&table, 0.25), 0.1);
+        assert_near(lookup(&table, 0.5), 0.2);
+        assert_near(lookup(&table, 0.75), 0.6);
+    }
+
+    #[test]
+    fn holds_end_values_outside_the_table() {
+        for interpolation in [
+            TableInterpolation::Linear(),
+            TableInterpolation::MonotoneCubic(),
+        ] {
+            let table = table(&[(0.1, 0.05), (0.5, 0.4), (0.9, 0.8)], interpolation);
+            assert_near(lookup(&table, 0.0), 0.05);
+            assert_near(lookup(&table, 0.1), 0.05);
+            assert_near(lookup(&table, 0.9), 0.8);
+            assert_near(lookup(&table, 1.0), 0.8);
+        }
+    }
+
+    #[test]
+    fn cubic_passes_through_breakpoints() {
+        let table = table(&STEEP, TableInterpolation::MonotoneCubic());
+        for (input, output) in STEEP {
+            assert_near(lookup(&table, input), output);
+        }
+    }
+
+    #[test]
+    fn cubic_is_monotone_without_overshoot() {
+        let steep = table(&STEEP, TableInterpolation::MonotoneCubic());
+        for table in [
+            steep,
+            ThrottleTable::from_preset(ThottleMapMode::Level0()),
+            ThrottleTable::from_preset(ThottleMapMode::Level2()),
+        ] {
+            let mut prev = 0.0;
+            for req in sweep() {
+                let out = lookup(&table, req);
+                assert!(out >= prev, "dips to {} at {}", out, req);
+                prev = out;
+            }
+        }
+
+        // each segment stays between its own breakpoints
+        for pair in STEEP.windows(2) {
+            let (x0, y0) = pair[0];
+            let (x1, y1) = pair[1];
+            for req in sweep().filter(|req| *req >= x0 && *req <= x1) {
+                let out = lookup(&steep, req);
+                assert!(out >= y0 - 1e-6 && out <= y1 + 1e-6, "{} at {}", out, req);
+            }
+        }
+    }
+
+    #[test]
+    fn cubic_keeps_flat_segments_flat() {
+        let table = table(
+            &[(0.0, 0.0), (0.3, 0.5), (0.6, 0.5), (1.0, 1.0)],
+            TableInterpolation::MonotoneCubic(),
+        );
+        for req in sweep().filter(|req| *req >= 0.3 && *req <= 0.6) {
+            assert_near(lookup(&table, req), 0.5);
+        }
+    }
+
+    #[test]
+    fn preset_matches_its_curve() {
+        let table = ThrottleTable::from_preset(ThottleMapMode::Level2());
+        for point in table.breakpoints() {
+            assert_near(
+                point.output.to_fractional(),
+                level_2(point.input).to_fractional(),
+            );
+        }
+        assert_eq!(
+            ThrottleTable::from_preset(ThottleMapMode::Table()),
+            ThrottleTable::from_preset(ThottleMapMode::Level1())
+        );
+    }
+
+    #[test]
+    fn table_needs_two_to_max_points() {
+        let points = [point(0.0, 0.0); THROTTLE_TABLE_MAX_POINTS + 1];
+        assert_eq!(
+            ThrottleTable::new(&points[..1], TableInterpolation::Linear()),
+            Err(DecodeError::InvalidValue {
+                field: "ThrottleTable::len",
+                value: 1
+            })
+        );
+        assert!(ThrottleTable::new(&points, TableInterpolation::Linear()).is_err());
+    }
+
+    #[test]
+    fn table_inputs_must_increase() {
+        let repeated = [point(0.0, 0.0), point(0.5, 0.2), point(0.5, 0.6)];
+        assert!(matches!(
+            ThrottleTable::new(&repeated, TableInterpolation::Linear()),
+            Err(DecodeError::InvalidValue {
+                field: "ThrottleTable::input",
+                ..
+            })
+        ));
+        let backwards = [point(0.5, 0.0), point(0.2, 1.0)];
+        assert!(ThrottleTable::new(&backwards, TableInterpolation::Linear()).is_err());
+    }
+
+    fn upload(upload: &mut ThrottleTableUpload, table: &ThrottleTable) -> Option<ThrottleTable> {
+        let mut result = None;
+        for msg in table.to_messages() {
+            assert!(result.is_none(), "finished before the last frame");
+            result = upload.push(msg).unwrap();
+        }
+        result
+    }
+
+    #[test]
+    fn upload_reassembles_the_table() {
+        let mut rx = ThrottleTableUpload::new();
+        let full = ThrottleTable::from_preset(ThottleMapMode::Level0());
+        assert_eq!(upload(&mut rx, &full), Some(full));
+        assert!(!rx.in_progress());
+
+        // an odd count leaves the last frame half empty
+        let odd = table(
+            &[(0.0, 0.0), (0.5, 0.2), (1.0, 1.0)],
+            TableInterpolation::Linear(),
+        );
+        assert_eq!(upload(&mut rx, &odd), Some(odd));
+    }
+
+    #[test]
+    fn upload_rejects_out_of_order_frames() {
+        let mut rx = ThrottleTableUpload::new();
+        let table = ThrottleTable::from_preset(ThottleMapMode::Level2());
+        let frames: [ThrottleTableMessage; 4] =
+            core::array::from_fn(|index| table.to_messages().nth(index).unwrap());
+
+        assert_eq!(rx.push(frames[0]), Ok(None));
+        assert!(rx.in_progress());
+        assert_eq!(
+            rx.push(frames[2]),
+            Err(DecodeError::InvalidValue {
+                field: "ThrottleTableMessage::index",
+                value: 2
+            })
+        );
+        assert!(!rx.in_progress());
+        // nothing to continue from without a first frame
+        assert!(rx.push(frames[1]).is_err());
+
+        // a new first frame starts over
+        assert_eq!(upload(&mut rx, &table), Some(table));
+    }
+
+    #[test]
+    fn upload_rejects_a_changed_header() {
+        let mut rx = ThrottleTableUpload::new();
+        let table = ThrottleTable::from_preset(ThottleMapMode::Level2());
+        let mut frames = table.to_messages();
+        rx.push(frames.next().unwrap()).unwrap();
+        let second = ThrottleTableMessage {
+            interpolation: TableInterpolation::Linear(),
+            ..frames.next().unwrap()
+        };
+        assert!(rx.push(second).is_err());
+    }
+
+    #[test]
+    fn upload_validates_the_finished_table() {
+        let mut rx = ThrottleTableUpload::new();
+        let msg = ThrottleTableMessage {
+            index: 0,
+            point_count: 2,
+            interpolation: TableInterpolation::Linear(),
+            points: [Some(point(0.6, 0.0)), Some(point(0.4, 1.0))],
+        };
+        assert!(rx.push(msg).is_err());
+
+        // too many points for the table
+        let msg = ThrottleTableMessage {
+            point_count: THROTTLE_TABLE_MAX_POINTS as u8 + 2,
+            points: [Some(point(0.0, 0.0)), Some(point(0.1, 0.1))],
+            ..msg
+        };
+        let mut result = rx.push(msg);
+        for index in 1..=(THROTTLE_TABLE_MAX_POINTS / THROTTLE_TABLE_POINTS_PER_FRAME) as u8 {
+            if result.is_err() {
+                break;
+            }
+            result = rx.push(ThrottleTableMessage { index, ..msg });
+        }
+        assert!(result.is_err());
     }
 }
@@ -0,0 +1,195 @@
+use crate::utils::{percentage::Percentage, time::Timestamp, torque::Torque};
+
+#[derive(Debug, Clone, Copy)]
+pub struct TorqueAssistConfig {
+    // motor torque per unit of rider torque
+    pub assist_ratio: f32,
+    // motor torque that maps to full throttle
+    pub max_motor_torque_nm: f32,
+    // rider torque below this is treated as resting feet on the pedals
+    pub start_torque_nm: f32,
+    // low pass time constant smoothing the twice-per-revolution pedal pulses
+    pub filter_tau_ms: u16,
+}
+
+impl Default for TorqueAssistConfig {
+    fn default() -> Self {
+        TorqueAssistConfig {
+            assist_ratio: 1.5,
+            max_motor_torque_nm: 80.0,
+            start_torque_nm: 2.0,
+            filter_tau_ms: 250,
+        }
+    }
+}
+
+pub struct TorqueAssist {
+    config: TorqueAssistConfig,
+    filtered_nm: f32,
+    prev_timestamp: Option<Timestamp>,
+}
+
+impl TorqueAssist {
+    pub fn new(config: TorqueAssistConfig) -> Self {
+        TorqueAssist {
+            config,
+            filtered_nm: 0.0,
+            prev_timestamp: None,
+        }
+    }
+
+    pub fn update_config(&mut self, config: TorqueAssistConfig) {
+        self.config = config;
+    }
+
+    fn filter(&mut self, curr_time: Timestamp, rider_torque: Torque) -> f32 {
+        let raw_nm = rider_torque.as_nm().max(0.0);
+        let dt_ms = if let Some(prev_time) = self.prev_timestamp {
+            curr_time.as_micros().saturating_sub(prev_time.as_micros()) as f32 / 1000.0
+        } else {
+            // first sample seeds the filter
+            self.filtered_nm = raw_nm;
+            0.0
+        };
+        self.prev_timestamp = Some(curr_time);
+
+        let tau_ms = self.config.filter_tau_ms as f32;
+        let alpha = if tau_ms <= 0.0 {
+            1.0
+        } else {
+            dt_ms / (tau_ms + dt_ms)
+        };
+        self.filtered_nm += alpha * (raw_nm - self.filtered_nm);
+        self.filtered_nm
+    }
+
+    pub fn run_algo(&mut self, curr_time: Timestamp, rider_torque: Option<Torque>) -> Percentage {
+        let rider_torque = if let Some(rider_torque) = rider_torque {
+            rider_torque
+        } else {
+            self.reset();
+            return Percentage::zero();
+        };
+
+        let filtered_nm = self.filter(curr_time, rider_torque);
+        if filtered_nm < self.config.start_torque_nm || self.config.max_motor_torque_nm <= 0.0 {
+            return Percentage::zero();
+        }
+
+        let motor_nm = filtered_nm * self.config.assist_ratio;
+        Percentage::from_fractional((motor_nm / self.config.max_motor_torque_nm).min(1.0))
+    }
+
+    pub fn reset(&mut self) {
+        self.filtered_nm = 0.0;
+        self.prev_timestamp = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const STEP_MS: u64 = 20;
+
+    struct Bench {
+        assist: TorqueAssist,
+        now_ms: u64,
+    }
+
+    impl Bench {
+        fn new() -> Self {
+            Bench {
+                assist: TorqueAssist::new(TorqueAssistConfig::default()),
+                now_ms: 0,
+            }
+        }
+
+        fn step(&mut self, rider_nm: Option<f32>) -> Percentage {
+            self.now_ms += STEP_MS;
+            self.assist.run_algo(
+                Timestamp::from_micros(self.now_ms * 1000),
+                rider_nm.map(Torque::from_nm),
+            )
+        }
+    }
+
+    fn assert_near(actual: Percentage, expected: f32) {
+        assert!(
+            (actual.to_fractional() - expected).abs() < 1e-3,
+            "expected {} got {}",
+            expected,
+            actual.to_fractional()
+        );
+    }
+
+    // motor share for a rider torque with the default ratio and ceiling
+    fn share(rider_nm: f32) -> f32 {
+        let config = TorqueAssistConfig::default();
+        rider_nm * config.assist_ratio / config.max_motor_torque_nm
+    }
+
+    #[test]
+    fn first_sample_seeds_the_filter() {
+        let mut bench = Bench::new();
+        assert_near(bench.step(Some(20.0)), share(20.0));
+    }
+
+    #[test]
+    fn step_input_follows_the_time_constant() {
+        let mut bench = Bench::new();
+        bench.step(Some(10.0));
+
+        let tau_ms = TorqueAssistConfig::default().filter_tau_ms as f32;
+        let alpha = STEP_MS as f32 / (tau_ms + STEP_MS as f32);
+        assert_near(bench.step(Some(30.0)), share(10.0 + alpha * 20.0));
+
+        let mut prev = Percentage::zero();
+        let mut out = Percentage::zero();
+        // about one time constant in, most of the way but not all
+        for _ in 0..(tau_ms as u64 / STEP_MS) {
+            prev = out;
+            out = bench.step(Some(30.0));
+            assert!(out >= prev);
+        }
+        assert!(out > Percentage::from_fractional(share(10.0 + 0.6 * 20.0)));
+        assert!(out < Percentage::from_fractional(share(10.0 + 0.7 * 20.0)));
+
+        for _ in 0..100 {
+            out = bench.step(Some(30.0));
+        }
+        assert_near(out, share(30.0));
+    }
+
+    #[test]
+    fn resting_feet_give_no_assist() {
+        let start_torque_nm = TorqueAssistConfig::default().start_torque_nm;
+        let mut bench = Bench::new();
+        assert_eq!(bench.step(Some(start_torque_nm - 0.1)), Percentage::zero());
+
+        let mut bench = Bench::new();
+        assert_near(bench.step(Some(start_torque_nm)), share(start_torque_nm));
+
+        // pushing back on the pedals is no torque at all
+        let mut bench = Bench::new();
+        assert_eq!(bench.step(Some(-30.0)), Percentage::zero());
+    }
+
+    #[test]
+    fn saturates_at_max_motor_torque() {
+        let mut bench = Bench::new();
+        assert_eq!(bench.step(Some(200.0)), Percentage::full());
+    }
+
+    #[test]
+    fn missing_torque_resets() {
+        let mut bench = Bench::new();
+        for _ in 0..50 {
+            bench.step(Some(40.0));
+        }
+        assert_eq!(bench.step(None), Percentage::zero());
+
+        // the next reading seeds afresh instead of decaying from the old one
+        assert_near(bench.step(Some(5.0)), share(5.0));
+    }
+}
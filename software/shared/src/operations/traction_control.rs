@@ -1,15 +1,13 @@
-use pid_lite::Controller;
-
-use crate::utils::{
-    percentage::Percentage,
-    speed::WheelSpeed,
-    time::{Duration, Timestamp},
+use crate::{
+    messages::error::DecodeError,
+    utils::{percentage::Percentage, time::Timestamp},
 };
 
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum TractionControlMode {
     Level0(),
     Level1(),
+    Custom(), // gains from the config, tunable over CAN
 }
 
 impl Into<u8> for TractionControlMode {
@@ -17,43 +15,79 @@ impl Into<u8> for TractionControlMode {
         match self {
             TractionControlMode::Level0() => 0,
             TractionControlMode::Level1() => 1,
+            TractionControlMode::Custom() => 2,
         }
     }
 }
 
-impl From<u8> for TractionControlMode {
-    fn from(value: u8) -> Self {
-        if value == 1 {
-            TractionControlMode::Level1()
-        } else {
-            TractionControlMode::Level0()
+impl TryFrom<u8> for TractionControlMode {
+    type Error = DecodeError;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(TractionControlMode::Level0()),
+            1 => Ok(TractionControlMode::Level1()),
+            2 => Ok(TractionControlMode::Custom()),
+            _ => Err(DecodeError::InvalidValue {
+                field: "TractionControlMode",
+                value,
+            }),
         }
     }
 }
 
-impl TractionControlMode {
-    pub fn prop_gain(&self) -> f64 {
-        match self {
-            TractionControlMode::Level0() => 0.1,
-            TractionControlMode::Level1() => 0.5,
+// the controller output is how much throttle to take away, as a fraction of full throttle
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TractionControlGains {
+    pub kp: f32,
+    pub ki: f32,
+    pub kd: f32,
+    // low pass time constant on the derivative term
+    pub derivative_tau_ms: u16,
+    // largest throttle cut the integrator may hold on its own
+    pub integral_limit: f32,
+    pub output_min: f32,
+    pub output_max: f32,
+}
+
+impl TractionControlGains {
+    pub const fn level_0() -> Self {
+        TractionControlGains {
+            kp: 1.0,
+            ki: 2.0,
+            kd: 0.02,
+            derivative_tau_ms: 20,
+            integral_limit: 0.5,
+            output_min: 0.0,
+            output_max: 0.8,
         }
     }
-    pub fn int_gain(&self) -> f64 {
-        match self {
-            TractionControlMode::Level0() => 0.0,
-            TractionControlMode::Level1() => 0.0,
+
+    pub const fn level_1() -> Self {
+        TractionControlGains {
+            kp: 2.5,
+            ki: 5.0,
+            kd: 0.05,
+            derivative_tau_ms: 20,
+            integral_limit: 0.8,
+            output_min: 0.0,
+            output_max: 1.0,
         }
     }
-    pub fn der_gain(&self) -> f64 {
-        match self {
-            TractionControlMode::Level0() => 0.0,
-            TractionControlMode::Level1() => 0.0,
-        }
+}
+
+impl Default for TractionControlGains {
+    fn default() -> Self {
+        TractionControlGains::level_1()
     }
-    pub fn scale_factor(&self) -> f64 {
+}
+
+impl TractionControlMode {
+    pub fn gains(&self, custom: TractionControlGains) -> TractionControlGains {
         match self {
-            TractionControlMode::Level0() => 1.0,
-            TractionControlMode::Level1() => 1.0,
+            TractionControlMode::Level0() => TractionControlGains::level_0(),
+            TractionControlMode::Level1() => TractionControlGains::level_1(),
+            TractionControlMode::Custom() => custom,
         }
     }
 
@@ -61,41 +95,49 @@ impl TractionControlMode {
         match self {
             TractionControlMode::Level0() => "000",
             TractionControlMode::Level1() => "001",
+            TractionControlMode::Custom() => "CST",
         }
     }
 }
 
 pub struct TractionControl {
     pub mode: TractionControlMode,
-    prev_timestamp: Option<Timestamp>,
-    controller: Controller,
+    custom_gains: TractionControlGains,
+    gains: TractionControlGains,
     desired_slip: Percentage,
+    prev_timestamp: Option<Timestamp>,
+    prev_slip: f32,
+    integral: f32,
+    derivative: f32,
 }
 
 impl TractionControl {
-    pub fn new(mode: TractionControlMode, desired_slip: Percentage) -> Self {
-        let controller = Controller::new(
-            Into::<f32>::into(desired_slip) as f64,
-            mode.prop_gain(),
-            mode.int_gain(),
-            mode.der_gain(),
-        );
+    pub fn new(
+        mode: TractionControlMode,
+        custom_gains: TractionControlGains,
+        desired_slip: Percentage,
+    ) -> Self {
         TractionControl {
             mode,
+            custom_gains,
+            gains: mode.gains(custom_gains),
+            desired_slip,
             prev_timestamp: None,
-            controller: controller,
-            desired_slip: desired_slip,
+            prev_slip: 0.0,
+            integral: 0.0,
+            derivative: 0.0,
         }
     }
     pub fn update_mode(&mut self, mode: TractionControlMode) {
-        self.controller.set_derivative_gain(mode.der_gain());
-        self.controller.set_integral_gain(mode.int_gain());
-        self.controller.set_proportional_gain(mode.prop_gain());
         self.mode = mode;
+        self.gains = mode.gains(self.custom_gains);
+    }
+    pub fn update_gains(&mut self, custom_gains: TractionControlGains) {
+        self.custom_gains = custom_gains;
+        self.gains = self.mode.gains(custom_gains);
     }
     pub fn update_desired_slip(&mut self, desired_slip: Percentage) {
         self.desired_slip = desired_slip;
-        self.controller.set_target(Into::<f64>::into(desired_slip));
     }
 
     pub fn run_algo(
@@ -104,26 +146,48 @@ impl TractionControl {
         current_slip: Percentage,
         curr_req: Percentage,
     ) -> Percentage {
-        let elapsed_time = if let Some(prev_time) = self.prev_timestamp {
-            Duration::from_millis((curr_time - prev_time).as_micros() / 100)
+        let gains = self.gains;
+        let slip = current_slip.to_fractional();
+        // positive error means more slip than we want
+        let error = slip - self.desired_slip.to_fractional();
+
+        let dt_s = if let Some(prev_time) = self.prev_timestamp {
+            curr_time.as_micros().saturating_sub(prev_time.as_micros()) as f32 / 1_000_000.0
         } else {
-            Duration::from_millis(0)
+            0.0
         };
-        let adjustment = self.controller.update_elapsed(
-            current_slip.into(),
-            core::time::Duration::from_millis(elapsed_time.as_millis()),
-        );
-
-        if adjustment <= 0.0 {
-            curr_req
-        // only modify adjustment if it's negative (e.g. we should reduce our slip angle)
-        } else {
-            ((adjustment * self.mode.scale_factor()) + Into::<f64>::into(curr_req)).into()
+
+        // derivative on the measurement so a new slip target does not kick the output
+        if dt_s > 0.0 {
+            let raw_derivative = (slip - self.prev_slip) / dt_s;
+            let tau_s = gains.derivative_tau_ms as f32 / 1000.0;
+            let alpha = dt_s / (tau_s + dt_s);
+            self.derivative += alpha * (raw_derivative - self.derivative);
+        }
+        self.prev_timestamp = Some(curr_time);
+        self.prev_slip = slip;
+
+        let proportional = gains.kp * error;
+        let derivative = gains.kd * self.derivative;
+        let unclamped = proportional + self.integral + derivative;
+
+        // anti-windup: stop integrating while saturated in the direction of the error
+        let saturated_high = unclamped >= gains.output_max && error > 0.0;
+        let saturated_low = unclamped <= gains.output_min && error < 0.0;
+        if !saturated_high && !saturated_low {
+            self.integral = (self.integral + gains.ki * error * dt_s)
+                .clamp(-gains.integral_limit, gains.integral_limit);
         }
+
+        let cut =
+            (proportional + self.integral + derivative).clamp(gains.output_min, gains.output_max);
+        (curr_req.to_fractional() - cut).clamp(0.0, 1.0).into()
     }
 
     pub fn reset(&mut self) {
         self.prev_timestamp = None;
-        self.controller.reset();
+        self.prev_slip = 0.0;
+        self.integral = 0.0;
+        self.derivative = 0.0;
     }
 }
@@ -0,0 +1,161 @@
+use crate::utils::{percentage::Percentage, speed::GroundSpeed};
+
+#[derive(Debug, Clone, Copy)]
+pub struct WalkAssistConfig {
+    // motor command while walking, well under normal riding
+    pub command: Percentage,
+    // legal push assist limit
+    pub max_speed_kph: f32,
+    // command taken away per kph inside the taper below the limit
+    pub taper_kph: f32,
+}
+
+impl Default for WalkAssistConfig {
+    fn default() -> Self {
+        WalkAssistConfig {
+            command: Percentage::from_fractional(0.15),
+            max_speed_kph: 6.0,
+            taper_kph: 1.0,
+        }
+    }
+}
+
+pub struct WalkAssist {
+    config: WalkAssistConfig,
+    active: bool,
+    // rider input ended the walk, wait for the button to be let go
+    locked_out: bool,
+}
+
+impl WalkAssist {
+    pub fn new(config: WalkAssistConfig) -> Self {
+        WalkAssist {
+            config,
+            active: false,
+            locked_out: false,
+        }
+    }
+
+    pub fn update_config(&mut self, config: WalkAssistConfig) {
+        self.config = config;
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.active
+    }
+
+    // rider_req is the throttle or pedal request, any of it ends walk assist
+    pub fn update(&mut self, button: bool, rider_req: Percentage) -> bool {
+        if !button {
+            self.locked_out = false;
+        } else if rider_req > Percentage::zero() {
+            self.locked_out = true;
+        }
+        self.active = button && !self.locked_out;
+        self.active
+    }
+
+    // fixed command tapering to zero at the speed limit, no speed means no assist
+    pub fn run_algo(&self, speed: Option<GroundSpeed>) -> Percentage {
+        let speed = match speed {
+            Some(speed) if self.active => speed,
+            _ => return Percentage::zero(),
+        };
+        let headroom = self.config.max_speed_kph - speed.kph();
+        let scale = if self.config.taper_kph <= 0.0 {
+            if headroom > 0.0 { 1.0 } else { 0.0 }
+        } else {
+            (headroom / self.config.taper_kph).clamp(0.0, 1.0)
+        };
+        Percentage::from_fractional(self.config.command.to_fractional() * scale)
+    }
+
+    pub fn reset(&mut self) {
+        self.active = false;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(kph: f32) -> Option<GroundSpeed> {
+        Some(GroundSpeed::from_kph(kph))
+    }
+
+    fn walking() -> WalkAssist {
+        let mut walk = WalkAssist::new(WalkAssistConfig::default());
+        assert!(walk.update(true, Percentage::zero()));
+        walk
+    }
+
+    fn assert_near(actual: Percentage, expected: f32) {
+        assert!(
+            (actual.to_fractional() - expected).abs() < 1e-3,
+            "expected {} got {}",
+            expected,
+            actual.to_fractional()
+        );
+    }
+
+    #[test]
+    fn full_command_below_the_taper() {
+        let walk = walking();
+        assert_near(walk.run_algo(at(0.0)), 0.15);
+        assert_near(walk.run_algo(at(5.0)), 0.15);
+    }
+
+    #[test]
+    fn tapers_to_zero_at_six_kph() {
+        let walk = walking();
+        assert_near(walk.run_algo(at(5.5)), 0.075);
+        assert_near(walk.run_algo(at(5.9)), 0.015);
+        assert_near(walk.run_algo(at(6.0)), 0.0);
+        assert_near(walk.run_algo(at(8.0)), 0.0);
+    }
+
+    #[test]
+    fn no_taper_cuts_at_the_limit() {
+        let mut walk = walking();
+        walk.update_config(WalkAssistConfig {
+            taper_kph: 0.0,
+            ..WalkAssistConfig::default()
+        });
+        assert_near(walk.run_algo(at(5.9)), 0.15);
+        assert_near(walk.run_algo(at(6.0)), 0.0);
+    }
+
+    #[test]
+    fn no_speed_means_no_assist() {
+        let walk = walking();
+        assert_near(walk.run_algo(None), 0.0);
+    }
+
+    #[test]
+    fn idle_without_the_button() {
+        let mut walk = WalkAssist::new(WalkAssistConfig::default());
+        assert!(!walk.update(false, Percentage::zero()));
+        assert_near(walk.run_algo(at(2.0)), 0.0);
+    }
+
+    #[test]
+    fn rider_input_locks_out_until_release() {
+        let mut walk = walking();
+        assert!(!walk.update(true, Percentage::from_fractional(0.1)));
+        assert_near(walk.run_algo(at(2.0)), 0.0);
+        // letting go of the throttle is not enough
+        assert!(!walk.update(true, Percentage::zero()));
+        assert!(!walk.is_active());
+        // the button has to be released and pressed again
+        assert!(!walk.update(false, Percentage::zero()));
+        assert!(walk.update(true, Percentage::zero()));
+        assert_near(walk.run_algo(at(2.0)), 0.15);
+    }
+
+    #[test]
+    fn rider_input_before_the_button_locks_out() {
+        let mut walk = WalkAssist::new(WalkAssistConfig::default());
+        assert!(!walk.update(true, Percentage::from_fractional(0.3)));
+        assert!(!walk.update(true, Percentage::zero()));
+    }
+}
@@ -0,0 +1,2 @@
+#[path = "./storage.rs"]
+pub mod storage;
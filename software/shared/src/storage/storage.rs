@@ -0,0 +1,53 @@
+use crate::messages::error::DecodeError;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum StorageKey {
+    Odometer(),
+    Config(),
+}
+
+impl StorageKey {
+    // short enough for backends with limited key names (ESP NVS allows 15 characters)
+    pub fn name(&self) -> &'static str {
+        match self {
+            StorageKey::Odometer() => "odometer",
+            StorageKey::Config() => "config",
+        }
+    }
+}
+
+// Record oriented non-volatile storage, each key holds one blob that is replaced whole
+pub trait Storage {
+    type Error: core::fmt::Debug;
+
+    // copy the stored record into buf and return its length, None if never written
+    fn read(&mut self, key: StorageKey, buf: &mut [u8]) -> Result<Option<usize>, Self::Error>;
+
+    fn write(&mut self, key: StorageKey, data: &[u8]) -> Result<(), Self::Error>;
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum StorageError<E> {
+    // the backend failed to read or write
+    Backend(E),
+    // a record was found but doesn't check out
+    Corrupt(DecodeError),
+}
+
+// CRC-16/CCITT-FALSE over a stored record
+pub fn crc16(data: &[u8]) -> u16 {
+    let mut crc: u16 = 0xFFFF;
+    for byte in data {
+        crc ^= (*byte as u16) << 8;
+        for _ in 0..8 {
+            crc = if crc & 0x8000 != 0 {
+                (crc << 1) ^ 0x1021
+            } else {
+                crc << 1
+            };
+        }
+    }
+    crc
+}
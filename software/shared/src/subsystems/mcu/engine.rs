@@ -1,10 +1,27 @@
 use crate::{
+    messages::error::DecodeError,
     operations::{
-        throttle_map::{ThottleMap, ThottleMapMode},
-        traction_control::{TractionControl, TractionControlMode},
+        anti_wheelie::{AntiWheelie, AntiWheelieMode},
+        brake_cutoff::BrakeCutoff,
+        cruise_control::{CruiseCommand, CruiseControl, CruiseControlConfig},
+        launch_control::{LaunchControl, LaunchControlMode},
+        power_limiter::{PowerLimitReason, PowerLimiter, PowerLimiterConfig},
+        slew_limiter::{SlewLimiter, SlewLimiterConfig},
+        slip_estimator::{SlipEstimate, SlipEstimator, SlipEstimatorConfig},
+        speed_limiter::{SpeedLimitClass, SpeedLimiter},
+        thermal_derate::{ThermalDerate, ThermalDerateConfig},
+        throttle_map::{ThottleMap, ThottleMapMode, ThrottleTable},
+        torque_assist::{TorqueAssist, TorqueAssistConfig},
+        traction_control::{TractionControl, TractionControlGains, TractionControlMode},
+        walk_assist::{WalkAssist, WalkAssistConfig},
     },
     subsystems::shared::Subsystem,
-    utils::{percentage::Percentage, speed::WheelSpeed, time::Timestamp},
+    utils::{
+        percentage::Percentage,
+        speed::{GroundSpeed, WheelSpeed},
+        time::Timestamp,
+        torque::Torque,
+    },
 };
 
 #[derive(Debug, Clone, Copy)]
@@ -12,55 +29,250 @@ pub struct EngineRequest {
     pub rear_ws: Option<WheelSpeed>,
     pub front_ws: Option<WheelSpeed>,
     pub throttle_req: Percentage,
+    pub pas_req: Percentage,
+    pub rider_torque: Option<Torque>,
+    pub brake_req: Percentage,
+    // from the IMU when one is fitted
+    pub longitudinal_accel: Option<f32>,
+    pub pitch_deg: Option<f32>,
+    // button event from the FCU, if one arrived since the last run
+    pub cruise_cmd: Option<CruiseCommand>,
+    pub walk_assist: bool,
+    // false when the MCU failsafe or operating state forbids driving the motor
+    pub motor_allowed: bool,
+    // ceiling from the MCU operating state, full when the motor isn't restricted
+    pub throttle_limit: Percentage,
+    pub motor_temp_c: Option<f32>,
+    pub controller_temp_c: Option<f32>,
+    // from the BMS, current positive while discharging
+    pub pack_voltage_v: Option<f32>,
+    pub pack_current_a: Option<f32>,
     pub timestamp: Timestamp,
 }
 
 #[derive(Debug, Clone, Copy)]
 pub struct EngineResponse {
     pub throttle_req: Percentage,
+    pub slip: SlipEstimate,
+    pub cruise_target: Option<GroundSpeed>,
+    pub derate: Percentage,
+    pub limit_reason: PowerLimitReason,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AssistMode {
+    Throttle(), // twist throttle only
+    Pas(),      // pedal assist only
+    Combined(), // whichever of the two asks for more
+    Torque(),   // proportional to rider torque
+}
+
+impl AssistMode {
+    // pedal_req is the cadence or torque based request, depending on the mode
+    pub fn combine(&self, throttle_req: Percentage, pedal_req: Percentage) -> Percentage {
+        match self {
+            AssistMode::Throttle() => throttle_req,
+            AssistMode::Pas() | AssistMode::Torque() => pedal_req,
+            AssistMode::Combined() => {
+                if pedal_req > throttle_req {
+                    pedal_req
+                } else {
+                    throttle_req
+                }
+            }
+        }
+    }
+
+    // whether the rider is asking for any motor output in this mode
+    pub fn has_demand(
+        &self,
+        throttle_req: Percentage,
+        pas_req: Percentage,
+        rider_torque: Option<Torque>,
+    ) -> bool {
+        let throttle = throttle_req > Percentage::zero();
+        let pas = pas_req > Percentage::zero();
+        match self {
+            AssistMode::Throttle() => throttle,
+            AssistMode::Pas() => pas,
+            AssistMode::Combined() => throttle || pas,
+            AssistMode::Torque() => rider_torque.is_some_and(|torque| torque > Torque::zero()),
+        }
+    }
+
+    pub fn to_small_str(&self) -> &str {
+        match self {
+            AssistMode::Throttle() => "THR",
+            AssistMode::Pas() => "PAS",
+            AssistMode::Combined() => "CMB",
+            AssistMode::Torque() => "TRQ",
+        }
+    }
+}
+
+impl From<AssistMode> for u8 {
+    fn from(mode: AssistMode) -> u8 {
+        match mode {
+            AssistMode::Throttle() => 0,
+            AssistMode::Pas() => 1,
+            AssistMode::Combined() => 2,
+            AssistMode::Torque() => 3,
+        }
+    }
+}
+
+impl TryFrom<u8> for AssistMode {
+    type Error = DecodeError;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(AssistMode::Throttle()),
+            1 => Ok(AssistMode::Pas()),
+            2 => Ok(AssistMode::Combined()),
+            3 => Ok(AssistMode::Torque()),
+            _ => Err(DecodeError::InvalidValue {
+                field: "AssistMode",
+                value,
+            }),
+        }
+    }
 }
 
 #[derive(Debug, Clone, Copy)]
 pub struct EngineConfig {
+    pub assist_mode: AssistMode,
     pub throttle_map_mode: ThottleMapMode,
+    pub throttle_table: ThrottleTable,
     pub traction_control_mode: TractionControlMode,
+    pub traction_control_gains: TractionControlGains,
     pub desired_slip: Percentage,
+    pub brake_cutoff_threshold: Percentage,
+    pub brake_cutoff_hysteresis: Percentage,
+    pub torque_assist: TorqueAssistConfig,
+    pub speed_limit_class: SpeedLimitClass,
+    pub wheel_diameter_inch: f32,
+    pub launch_control_mode: LaunchControlMode,
+    pub slew_limiter: SlewLimiterConfig,
+    pub slip_estimator: SlipEstimatorConfig,
+    pub anti_wheelie_mode: AntiWheelieMode,
+    pub cruise_control: CruiseControlConfig,
+    pub walk_assist: WalkAssistConfig,
+    // ceiling on the motor command, full is no limit
+    pub power_limit: Percentage,
+    pub thermal_derate: ThermalDerateConfig,
+    pub power_limiter: PowerLimiterConfig,
 }
 
 impl Default for EngineConfig {
     fn default() -> Self {
         EngineConfig {
+            assist_mode: AssistMode::Throttle(),
             throttle_map_mode: ThottleMapMode::Level2(),
+            throttle_table: ThrottleTable::default(),
             traction_control_mode: TractionControlMode::Level1(),
+            traction_control_gains: TractionControlGains::default(),
             desired_slip: Percentage::from_fractional(0.1),
+            brake_cutoff_threshold: Percentage::from_fractional(0.05),
+            brake_cutoff_hysteresis: Percentage::from_fractional(0.02),
+            torque_assist: TorqueAssistConfig::default(),
+            speed_limit_class: SpeedLimitClass::Eu(),
+            wheel_diameter_inch: 27.5,
+            launch_control_mode: LaunchControlMode::Off(),
+            slew_limiter: SlewLimiterConfig::default(),
+            slip_estimator: SlipEstimatorConfig::default(),
+            anti_wheelie_mode: AntiWheelieMode::Level0(),
+            cruise_control: CruiseControlConfig::default(),
+            walk_assist: WalkAssistConfig::default(),
+            power_limit: Percentage::full(),
+            thermal_derate: ThermalDerateConfig::default(),
+            power_limiter: PowerLimiterConfig::default(),
         }
     }
 }
 
 pub struct EngineSubsystem {
+    pub assist_mode: AssistMode,
     pub throttle_map: ThottleMap,
     pub traction_control: TractionControl,
+    pub brake_cutoff: BrakeCutoff,
+    pub torque_assist: TorqueAssist,
+    pub speed_limiter: SpeedLimiter,
+    pub launch_control: LaunchControl,
+    pub slew_limiter: SlewLimiter,
+    pub slip_estimator: SlipEstimator,
+    pub anti_wheelie: AntiWheelie,
+    pub cruise_control: CruiseControl,
+    pub walk_assist: WalkAssist,
+    pub thermal_derate: ThermalDerate,
+    pub power_limiter: PowerLimiter,
+    wheel_diameter_inch: f32,
+    desired_slip: Percentage,
+    power_limit: Percentage,
 }
 
 impl Subsystem<EngineConfig, EngineRequest, EngineResponse> for EngineSubsystem {
     fn new(config: EngineConfig) -> Self {
         EngineSubsystem {
-            throttle_map: ThottleMap::new(config.throttle_map_mode),
+            assist_mode: config.assist_mode,
+            throttle_map: ThottleMap::new(config.throttle_map_mode, config.throttle_table),
             traction_control: TractionControl::new(
                 config.traction_control_mode,
+                config.traction_control_gains,
                 config.desired_slip,
             ),
+            brake_cutoff: BrakeCutoff::new(
+                config.brake_cutoff_threshold,
+                config.brake_cutoff_hysteresis,
+            ),
+            torque_assist: TorqueAssist::new(config.torque_assist),
+            speed_limiter: SpeedLimiter::new(config.speed_limit_class, config.wheel_diameter_inch),
+            launch_control: LaunchControl::new(config.launch_control_mode),
+            slew_limiter: SlewLimiter::new(config.slew_limiter),
+            slip_estimator: SlipEstimator::new(config.slip_estimator, config.wheel_diameter_inch),
+            anti_wheelie: AntiWheelie::new(config.anti_wheelie_mode),
+            cruise_control: CruiseControl::new(config.cruise_control),
+            walk_assist: WalkAssist::new(config.walk_assist),
+            thermal_derate: ThermalDerate::new(config.thermal_derate),
+            power_limiter: PowerLimiter::new(config.power_limiter),
+            wheel_diameter_inch: config.wheel_diameter_inch,
+            desired_slip: config.desired_slip,
+            power_limit: config.power_limit,
         }
     }
 
     fn update(&mut self, config: EngineConfig) {
         // println!("Engine Subsystem Config: {:?}", config);
 
+        self.assist_mode = config.assist_mode;
         self.throttle_map.update_mode(config.throttle_map_mode);
+        self.throttle_map.update_table(config.throttle_table);
+        self.traction_control
+            .update_gains(config.traction_control_gains);
         self.traction_control
             .update_mode(config.traction_control_mode);
         self.traction_control
             .update_desired_slip(config.desired_slip);
+        self.brake_cutoff.update_threshold(
+            config.brake_cutoff_threshold,
+            config.brake_cutoff_hysteresis,
+        );
+        self.torque_assist.update_config(config.torque_assist);
+        self.speed_limiter
+            .update_config(config.speed_limit_class, config.wheel_diameter_inch);
+        self.launch_control.update_mode(config.launch_control_mode);
+        self.slew_limiter.update_config(config.slew_limiter);
+        self.slip_estimator
+            .update_config(config.slip_estimator, config.wheel_diameter_inch);
+        self.anti_wheelie.update_mode(config.anti_wheelie_mode);
+        self.cruise_control.update_config(config.cruise_control);
+        self.cruise_control.disengage();
+        self.walk_assist.update_config(config.walk_assist);
+        self.walk_assist.reset();
+        self.thermal_derate.update_config(config.thermal_derate);
+        self.power_limiter.update_config(config.power_limiter);
+        self.wheel_diameter_inch = config.wheel_diameter_inch;
+        self.desired_slip = config.desired_slip;
+        self.power_limit = config.power_limit;
         self.reset();
     }
 
@@ -71,32 +283,181 @@ impl Subsystem<EngineConfig, EngineRequest, EngineResponse> for EngineSubsystem
 
     fn run(&mut self, req: EngineRequest) -> EngineResponse {
         // println!("Engine Subsystem Response: {:?}", req);
-        // reset subsystem back to default if no throttle request (it means we've finished this acceleration cycle)
-        if req.throttle_req == Percentage::zero() {
+        // braking always wins over the throttle, treat it the same as a released throttle
+        let brake_cut = self.brake_cutoff.run_algo(req.brake_req);
+        // the failsafe drops the motor the same way a brake cut does
+        let motor_cut = brake_cut || !req.motor_allowed;
+        let pedal_req = if self.assist_mode == AssistMode::Torque() {
+            self.torque_assist.run_algo(req.timestamp, req.rider_torque)
+        } else {
+            req.pas_req
+        };
+        let rider_req = self.assist_mode.combine(req.throttle_req, pedal_req);
+
+        // the reference speed is tracked every cycle, not only while driving
+        let slip = self.slip_estimator.run_algo(
+            req.timestamp,
+            req.front_ws,
+            req.rear_ws,
+            req.longitudinal_accel,
+            // last cycle's verdict, the wheelie check itself needs this reference
+            self.anti_wheelie.is_lifted(),
+        );
+        self.anti_wheelie.update(
+            req.timestamp,
+            req.front_ws,
+            req.rear_ws,
+            if slip.valid {
+                Some(slip.reference)
+            } else {
+                None
+            },
+            req.pitch_deg,
+        );
+
+        // temperatures are tracked every cycle so the limit is ready before the next pull
+        self.thermal_derate
+            .update(req.motor_temp_c, req.controller_temp_c);
+        let derate = self.thermal_derate.derate();
+        self.power_limiter
+            .update(req.timestamp, req.pack_voltage_v, req.pack_current_a);
+        let limit_reason = self.power_limiter.reason();
+
+        // speed off the front wheel since the rear may be spinning
+        let speed = req
+            .front_ws
+            .map(|ws| GroundSpeed::from_wheel_speed(ws, self.wheel_diameter_inch));
+
+        // walk assist bypasses the riding maps, it only ever asks for a small fixed command
+        if self.walk_assist.update(req.walk_assist, rider_req) && !motor_cut {
+            self.cruise_control.disengage();
             self.reset();
             return EngineResponse {
-                throttle_req: Percentage::zero(),
+                throttle_req: self.slew_limiter.run_algo(
+                    req.timestamp,
+                    self.power_limiter.run_algo(
+                        self.thermal_derate
+                            .run_algo(self.walk_assist.run_algo(speed)),
+                    ),
+                ),
+                slip,
+                cruise_target: None,
+                derate,
+                limit_reason,
             };
         }
 
-        // calculate throttle position from map
-        let mut desired_throttle = self.throttle_map.run_algo(req.throttle_req);
-
-        // if we have ws info and detected slip run TC
-        if let Some(rear_ws) = req.rear_ws {
-            if let Some(front_ws) = req.front_ws {
-                if rear_ws > front_ws {
-                    let slip = Percentage::from_fractional(
-                        ((Into::<f32>::into(rear_ws) - Into::<f32>::into(front_ws))
-                            / Into::<f32>::into(rear_ws)),
-                    );
-                    desired_throttle =
-                        self.traction_control
-                            .run_algo(req.timestamp, slip, desired_throttle);
+        match req.cruise_cmd {
+            Some(CruiseCommand::Set()) => {
+                self.cruise_control.engage(speed, rider_req);
+            }
+            Some(CruiseCommand::Cancel()) => self.cruise_control.disengage(),
+            None => {}
+        }
+        if motor_cut || speed.is_none() {
+            self.cruise_control.disengage();
+        }
+        self.cruise_control.update_rider(rider_req);
+        // the rider can still ride over the cruise speed
+        let rider_req = match speed {
+            Some(speed) if self.cruise_control.is_engaged() => {
+                let cruise_req = self.cruise_control.run_algo(req.timestamp, speed);
+                if cruise_req > rider_req {
+                    cruise_req
+                } else {
+                    rider_req
                 }
             }
+            _ => rider_req,
+        };
+        let cruise_target = self.cruise_control.target();
+
+        // launch control tracks standstill even while nothing is requested
+        let was_launching = self.launch_control.is_active();
+        self.launch_control.update(
+            req.timestamp,
+            req.front_ws,
+            req.rear_ws,
+            if motor_cut {
+                Percentage::zero()
+            } else {
+                rider_req
+            },
+        );
+        let launching = self.launch_control.is_active();
+        if launching != was_launching {
+            // launch targets a little more slip than normal riding
+            let desired_slip = if launching {
+                self.desired_slip + self.launch_control.mode.slip_offset()
+            } else {
+                self.desired_slip
+            };
+            self.traction_control.update_desired_slip(desired_slip);
         }
 
+        // reset subsystem back to default if no throttle request (it means we've finished this acceleration cycle)
+        if motor_cut || rider_req == Percentage::zero() {
+            self.reset();
+            // a cut drops the motor at once, a roll off winds down at the fall rate
+            let throttle_req = if motor_cut {
+                self.slew_limiter.reset();
+                Percentage::zero()
+            } else {
+                self.slew_limiter
+                    .run_algo(req.timestamp, Percentage::zero())
+            };
+            // still goes through the power limiter so it knows what the motor is getting
+            // when the next pull starts
+            let throttle_req = self.power_limiter.run_algo(throttle_req);
+            return EngineResponse {
+                throttle_req,
+                slip,
+                cruise_target,
+                derate,
+                limit_reason,
+            };
+        }
+
+        // calculate throttle position from map
+        let mut desired_throttle = self.throttle_map.run_algo(rider_req);
+
+        // ramp the throttle in on a standing start
+        desired_throttle = self
+            .launch_control
+            .run_algo(req.timestamp, desired_throttle);
+
+        // if we have a slip estimate run TC, no slip still runs so the integrator can unwind
+        if slip.valid {
+            desired_throttle =
+                self.traction_control
+                    .run_algo(req.timestamp, slip.drive_slip, desired_throttle);
+        }
+
+        // bring the front wheel back down
+        desired_throttle = self.anti_wheelie.run_algo(desired_throttle);
+
+        // fade assist out approaching the legal speed limit
+        desired_throttle = self.speed_limiter.run_algo(req.front_ws, desired_throttle);
+
+        // the ride profile caps how hard the motor is driven
+        if desired_throttle > self.power_limit {
+            desired_throttle = self.power_limit;
+        }
+
+        // limp mode caps it further, ahead of the limiters so they track what is sent
+        if desired_throttle > req.throttle_limit {
+            desired_throttle = req.throttle_limit;
+        }
+
+        // back off as the motor or controller heats up
+        desired_throttle = self.thermal_derate.run_algo(desired_throttle);
+
+        // keep the pack inside its current and voltage limits
+        desired_throttle = self.power_limiter.run_algo(desired_throttle);
+
+        // limit how fast the motor command may change
+        desired_throttle = self.slew_limiter.run_algo(req.timestamp, desired_throttle);
+
         // println!(
         //     "Engine Subsystem Response: Desired Throttle: {:?}",
         //     desired_throttle
@@ -104,6 +465,287 @@ impl Subsystem<EngineConfig, EngineRequest, EngineResponse> for EngineSubsystem
 
         EngineResponse {
             throttle_req: desired_throttle,
+            slip,
+            cruise_target,
+            derate,
+            limit_reason,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // engine loop period on the MCU
+    const STEP_MS: u64 = 20;
+
+    fn request(throttle: f32, brake: f32, now_ms: u64) -> EngineRequest {
+        EngineRequest {
+            rear_ws: Some(WheelSpeed::zero()),
+            front_ws: Some(WheelSpeed::zero()),
+            throttle_req: Percentage::from_fractional(throttle),
+            pas_req: Percentage::zero(),
+            rider_torque: None,
+            brake_req: Percentage::from_fractional(brake),
+            longitudinal_accel: None,
+            pitch_deg: None,
+            cruise_cmd: None,
+            walk_assist: false,
+            motor_allowed: true,
+            throttle_limit: Percentage::full(),
+            motor_temp_c: None,
+            controller_temp_c: None,
+            pack_voltage_v: None,
+            pack_current_a: None,
+            timestamp: Timestamp::from_micros(now_ms * 1000),
+        }
+    }
+
+    // runs the engine on its own clock with the shipped slew limiter
+    struct Bench {
+        engine: EngineSubsystem,
+        now_ms: u64,
+    }
+
+    impl Bench {
+        fn new(config: EngineConfig) -> Self {
+            Bench {
+                engine: EngineSubsystem::new(config),
+                now_ms: 0,
+            }
+        }
+
+        fn step_with(&mut self, req: EngineRequest) -> Percentage {
+            self.now_ms += STEP_MS;
+            self.engine
+                .run(EngineRequest {
+                    timestamp: Timestamp::from_micros(self.now_ms * 1000),
+                    ..req
+                })
+                .throttle_req
+        }
+
+        fn step(&mut self, throttle: f32, brake: f32) -> Percentage {
+            self.step_with(request(throttle, brake, 0))
+        }
+
+        // long enough for the rise rate and smoothing to finish at any throttle
+        fn settle(&mut self, throttle: f32, brake: f32) -> Percentage {
+            let mut out = Percentage::zero();
+            for _ in 0..50 {
+                out = self.step(throttle, brake);
+            }
+            out
+        }
+    }
+
+    fn engine() -> Bench {
+        Bench::new(EngineConfig {
+            throttle_map_mode: ThottleMapMode::Level1(),
+            brake_cutoff_threshold: Percentage::from_fractional(0.1),
+            brake_cutoff_hysteresis: Percentage::from_fractional(0.05),
+            ..EngineConfig::default()
+        })
+    }
+
+    fn assert_near(actual: Percentage, expected: f32) {
+        assert!(
+            (actual.to_fractional() - expected).abs() < 1e-3,
+            "expected {} got {}",
+            expected,
+            actual.to_fractional()
+        );
+    }
+
+    #[test]
+    fn assist_mode_picks_the_request() {
+        let throttle = Percentage::from_fractional(0.3);
+        let pedal = Percentage::from_fractional(0.6);
+        assert_eq!(AssistMode::Throttle().combine(throttle, pedal), throttle);
+        assert_eq!(AssistMode::Pas().combine(throttle, pedal), pedal);
+        assert_eq!(AssistMode::Torque().combine(throttle, pedal), pedal);
+        // combined takes whichever asks for more
+        assert_eq!(AssistMode::Combined().combine(throttle, pedal), pedal);
+        assert_eq!(AssistMode::Combined().combine(pedal, throttle), pedal);
+    }
+
+    #[test]
+    fn assist_mode_demand() {
+        let some = Percentage::from_fractional(0.2);
+        let none = Percentage::zero();
+        assert!(AssistMode::Throttle().has_demand(some, none, None));
+        assert!(!AssistMode::Throttle().has_demand(none, some, None));
+        assert!(AssistMode::Pas().has_demand(none, some, None));
+        assert!(!AssistMode::Pas().has_demand(some, none, None));
+        assert!(AssistMode::Combined().has_demand(some, none, None));
+        assert!(AssistMode::Combined().has_demand(none, some, None));
+        assert!(!AssistMode::Combined().has_demand(none, none, None));
+    }
+
+    #[test]
+    fn pas_mode_ignores_throttle() {
+        let mut bench = Bench::new(EngineConfig {
+            assist_mode: AssistMode::Pas(),
+            throttle_map_mode: ThottleMapMode::Level1(),
+            ..EngineConfig::default()
+        });
+        assert_near(bench.settle(1.0, 0.0), 0.0);
+
+        let mut out = Percentage::zero();
+        for _ in 0..50 {
+            out = bench.step_with(EngineRequest {
+                pas_req: Percentage::from_fractional(0.5),
+                ..request(0.0, 0.0, 0)
+            });
+        }
+        assert_near(out, 0.5);
+    }
+
+    #[test]
+    fn combined_mode_follows_the_larger_request() {
+        let mut bench = Bench::new(EngineConfig {
+            assist_mode: AssistMode::Combined(),
+            throttle_map_mode: ThottleMapMode::Level1(),
+            ..EngineConfig::default()
+        });
+        let mut out = Percentage::zero();
+        for _ in 0..50 {
+            out = bench.step_with(EngineRequest {
+                pas_req: Percentage::from_fractional(0.5),
+                ..request(0.25, 0.0, 0)
+            });
+        }
+        assert_near(out, 0.5);
+        for _ in 0..50 {
+            out = bench.step_with(EngineRequest {
+                pas_req: Percentage::from_fractional(0.5),
+                ..request(0.75, 0.0, 0)
+            });
+        }
+        assert_near(out, 0.75);
+    }
+
+    #[test]
+    fn throttle_passes_without_brake() {
+        let mut bench = engine();
+        assert_near(bench.settle(0.5, 0.0), 0.5);
+    }
+
+    #[test]
+    fn throttle_ramps_in_at_rise_rate() {
+        let mut bench = engine();
+        let rise_per_s = SlewLimiterConfig::default().rise_per_s;
+        let first = bench.step(1.0, 0.0);
+        assert!(first.to_fractional() <= rise_per_s * STEP_MS as f32 / 1000.0 + 1e-6);
+        assert_near(bench.settle(1.0, 0.0), 1.0);
+    }
+
+    #[test]
+    fn roll_off_winds_down() {
+        let mut bench = engine();
+        bench.settle(1.0, 0.0);
+        let fall_per_s = SlewLimiterConfig::default().fall_per_s;
+        let out = bench.step(0.0, 0.0).to_fractional();
+        assert!(out > 0.0);
+        assert!(out >= 1.0 - fall_per_s * STEP_MS as f32 / 1000.0 - 1e-6);
+        assert_near(bench.settle(0.0, 0.0), 0.0);
+    }
+
+    #[test]
+    fn brake_below_threshold_keeps_throttle() {
+        let mut bench = engine();
+        assert_near(bench.settle(0.5, 0.08), 0.5);
+    }
+
+    #[test]
+    fn brake_overlapping_throttle_cuts_motor() {
+        let mut bench = engine();
+        bench.settle(1.0, 0.0);
+        assert_eq!(bench.step(1.0, 0.2), Percentage::zero());
+        assert!(bench.engine.brake_cutoff.is_engaged());
+    }
+
+    #[test]
+    fn failsafe_at_full_throttle_cuts_on_same_cycle() {
+        let mut bench = engine();
+        bench.settle(1.0, 0.0);
+        let out = bench.step_with(EngineRequest {
+            motor_allowed: false,
+            ..request(1.0, 0.0, 0)
+        });
+        assert_eq!(out, Percentage::zero());
+        // and it ramps back in from zero once allowed again
+        let out = bench.step(1.0, 0.0).to_fractional();
+        assert!(out < 0.1);
+    }
+
+    #[test]
+    fn brake_cutoff_releases_after_hysteresis() {
+        let mut bench = engine();
+        bench.step(0.5, 0.2);
+
+        // back under the threshold but still inside the hysteresis band
+        assert_eq!(bench.settle(0.5, 0.08), Percentage::zero());
+
+        // released below threshold - hysteresis
+        assert_near(bench.settle(0.5, 0.04), 0.5);
+        assert!(!bench.engine.brake_cutoff.is_engaged());
+    }
+
+    #[test]
+    fn brake_cutoff_releases_when_hysteresis_exceeds_threshold() {
+        let mut bench = Bench::new(EngineConfig {
+            throttle_map_mode: ThottleMapMode::Level1(),
+            brake_cutoff_threshold: Percentage::from_fractional(0.05),
+            brake_cutoff_hysteresis: Percentage::from_fractional(0.1),
+            ..EngineConfig::default()
+        });
+        bench.step(0.5, 0.2);
+        assert_near(bench.settle(0.5, 0.0), 0.5);
+    }
+
+    #[test]
+    fn brake_disengages_cruise() {
+        let mut bench = engine();
+        // riding along well above the minimum cruise speed
+        let rolling = EngineRequest {
+            front_ws: Some(WheelSpeed::from(120u16)),
+            rear_ws: Some(WheelSpeed::from(120u16)),
+            ..request(0.5, 0.0, 0)
+        };
+        bench.step_with(rolling);
+        bench.step_with(EngineRequest {
+            cruise_cmd: Some(CruiseCommand::Set()),
+            ..rolling
+        });
+        assert!(bench.engine.cruise_control.is_engaged());
+
+        bench.step_with(EngineRequest {
+            brake_req: Percentage::from_fractional(0.2),
+            ..rolling
+        });
+        assert!(!bench.engine.cruise_control.is_engaged());
+    }
+
+    #[test]
+    fn power_limit_starts_from_cut_motor() {
+        let mut bench = engine();
+        bench.settle(1.0, 0.0);
+        assert_near(bench.step(1.0, 0.5), 0.0);
+
+        // back on the throttle straight into an overcurrent, the limit starts from the
+        // zero the motor was last given rather than the full throttle before the cut
+        for _ in 0..5 {
+            let out = bench.step_with(EngineRequest {
+                pack_current_a: Some(60.0),
+                ..request(1.0, 0.0, 0)
+            });
+            assert_near(out, 0.0);
         }
+        assert_eq!(
+            bench.engine.power_limiter.reason(),
+            PowerLimitReason::Current()
+        );
     }
 }
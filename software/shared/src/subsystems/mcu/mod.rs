@@ -1,2 +1,11 @@
 #[path = "./engine.rs"]
 pub mod engine;
+
+#[path = "./regen.rs"]
+pub mod regen;
+
+#[path = "./pas.rs"]
+pub mod pas;
+
+#[path = "./abs.rs"]
+pub mod abs;
@@ -0,0 +1,141 @@
+use crate::{
+    subsystems::shared::Subsystem,
+    utils::{percentage::Percentage, speed::Cadence},
+};
+
+pub const PAS_TIER_COUNT: usize = 4;
+
+#[derive(Debug, Clone, Copy)]
+pub struct PasTier {
+    // minimum crank cadence for this tier
+    pub cadence: Cadence,
+    pub assist: Percentage,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct PasRequest {
+    pub cadence: Option<Cadence>,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct PasResponse {
+    pub assist_req: Percentage,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct PasConfig {
+    // in any order, the highest tier the cadence reaches applies
+    pub tiers: [PasTier; PAS_TIER_COUNT],
+}
+
+impl Default for PasConfig {
+    fn default() -> Self {
+        PasConfig {
+            tiers: [
+                PasTier {
+                    cadence: Cadence::from_rpm(30),
+                    assist: Percentage::from_fractional(0.25),
+                },
+                PasTier {
+                    cadence: Cadence::from_rpm(50),
+                    assist: Percentage::from_fractional(0.5),
+                },
+                PasTier {
+                    cadence: Cadence::from_rpm(70),
+                    assist: Percentage::from_fractional(0.75),
+                },
+                PasTier {
+                    cadence: Cadence::from_rpm(90),
+                    assist: Percentage::full(),
+                },
+            ],
+        }
+    }
+}
+
+pub struct PasSubsystem {
+    config: PasConfig,
+}
+
+impl Subsystem<PasConfig, PasRequest, PasResponse> for PasSubsystem {
+    fn new(config: PasConfig) -> Self {
+        PasSubsystem { config }
+    }
+
+    fn update(&mut self, config: PasConfig) {
+        self.config = config;
+    }
+
+    fn run(&mut self, req: PasRequest) -> PasResponse {
+        // no assist until the rider reaches the lowest tier
+        let mut reached: Option<&PasTier> = None;
+        if let Some(cadence) = req.cadence {
+            for tier in self.config.tiers.iter() {
+                if cadence >= tier.cadence
+                    && reached.is_none_or(|best| tier.cadence >= best.cadence)
+                {
+                    reached = Some(tier);
+                }
+            }
+        }
+        PasResponse {
+            assist_req: reached.map_or(Percentage::zero(), |tier| tier.assist),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assist(pas: &mut PasSubsystem, rpm: Option<u16>) -> Percentage {
+        pas.run(PasRequest {
+            cadence: rpm.map(Cadence::from_rpm),
+        })
+        .assist_req
+    }
+
+    #[test]
+    fn no_assist_below_first_tier() {
+        let mut pas = PasSubsystem::new(PasConfig::default());
+        assert_eq!(assist(&mut pas, Some(0)), Percentage::zero());
+        assert_eq!(assist(&mut pas, Some(29)), Percentage::zero());
+    }
+
+    #[test]
+    fn tier_applies_from_its_cadence() {
+        let config = PasConfig::default();
+        let mut pas = PasSubsystem::new(config);
+        for tier in config.tiers {
+            let rpm = tier.cadence.as_rpm();
+            assert_eq!(assist(&mut pas, Some(rpm)), tier.assist);
+            // and holds until the next tier up
+            assert_eq!(assist(&mut pas, Some(rpm + 1)), tier.assist);
+        }
+        assert_eq!(assist(&mut pas, Some(49)), config.tiers[0].assist);
+        assert_eq!(assist(&mut pas, Some(250)), Percentage::full());
+    }
+
+    #[test]
+    fn no_assist_without_cadence() {
+        let mut pas = PasSubsystem::new(PasConfig::default());
+        assert_eq!(assist(&mut pas, Some(60)), Percentage::from_fractional(0.5));
+        assert_eq!(assist(&mut pas, None), Percentage::zero());
+    }
+
+    #[test]
+    fn tier_order_does_not_matter() {
+        let mut config = PasConfig::default();
+        config.tiers.reverse();
+        let mut shuffled = PasSubsystem::new(config);
+        let mut sorted = PasSubsystem::new(PasConfig::default());
+        for rpm in [0, 29, 30, 45, 50, 69, 70, 89, 90, 120] {
+            assert_eq!(
+                assist(&mut shuffled, Some(rpm)),
+                assist(&mut sorted, Some(rpm)),
+                "at {} rpm",
+                rpm
+            );
+        }
+    }
+}
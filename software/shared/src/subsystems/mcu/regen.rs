@@ -0,0 +1,187 @@
+use crate::{
+    operations::slip_estimator::SlipEstimate, subsystems::shared::Subsystem,
+    utils::percentage::Percentage,
+};
+
+#[derive(Debug, Clone, Copy)]
+pub struct RegenRequest {
+    // the engine's estimate, so regen and traction control agree on the bike's speed
+    pub slip: SlipEstimate,
+    pub brake_req: Percentage,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct RegenResponse {
+    // magnitude of the negative motor torque command
+    pub regen: Percentage,
+    pub slip_limited: bool,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct RegenConfig {
+    // brake request below this is left to the friction brakes
+    pub brake_start: Percentage,
+    // regen command at full brake request
+    pub max_regen: Percentage,
+    // rear brake slip at which regen starts being backed off
+    pub max_brake_slip: Percentage,
+}
+
+impl Default for RegenConfig {
+    fn default() -> Self {
+        RegenConfig {
+            brake_start: Percentage::from_fractional(0.05),
+            max_regen: Percentage::from_fractional(0.5),
+            max_brake_slip: Percentage::from_fractional(0.1),
+        }
+    }
+}
+
+pub struct RegenSubsystem {
+    config: RegenConfig,
+}
+
+impl RegenSubsystem {
+    // scale regen down linearly once slip passes the limit, reaching zero at twice the limit
+    fn slip_scale(&self, slip: Percentage) -> f32 {
+        let max_slip = self.config.max_brake_slip.to_fractional();
+        let slip = slip.to_fractional();
+        if slip <= max_slip {
+            1.0
+        } else if max_slip <= 0.0 {
+            0.0
+        } else {
+            (1.0 - (slip - max_slip) / max_slip).max(0.0)
+        }
+    }
+}
+
+impl Subsystem<RegenConfig, RegenRequest, RegenResponse> for RegenSubsystem {
+    fn new(config: RegenConfig) -> Self {
+        RegenSubsystem { config }
+    }
+
+    fn update(&mut self, config: RegenConfig) {
+        self.config = config;
+    }
+
+    fn run(&mut self, req: RegenRequest) -> RegenResponse {
+        let brake = req.brake_req.to_fractional();
+        let start = self.config.brake_start.to_fractional();
+        if brake <= start || start >= 1.0 {
+            return RegenResponse {
+                regen: Percentage::zero(),
+                slip_limited: false,
+            };
+        }
+
+        // map the brake request above the start point onto the regen range
+        let requested = (brake - start) / (1.0 - start) * self.config.max_regen.to_fractional();
+
+        // without a slip estimate a locking rear can't be seen, so leave it to the friction
+        // brakes. That covers a missing wheel speed as well as crawling below the sensors' range
+        let scale = if req.slip.valid {
+            self.slip_scale(req.slip.brake_slip)
+        } else {
+            0.0
+        };
+
+        RegenResponse {
+            regen: Percentage::from_fractional(requested * scale),
+            slip_limited: scale < 1.0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::{
+        operations::slip_estimator::{SlipEstimator, SlipEstimatorConfig},
+        utils::{speed::WheelSpeed, time::Timestamp},
+    };
+
+    fn slip(brake_slip: f32) -> SlipEstimate {
+        SlipEstimate {
+            reference: WheelSpeed::from(200u16),
+            drive_slip: Percentage::zero(),
+            brake_slip: Percentage::from_fractional(brake_slip),
+            valid: true,
+        }
+    }
+
+    fn run(brake: f32, slip: SlipEstimate) -> RegenResponse {
+        RegenSubsystem::new(RegenConfig::default()).run(RegenRequest {
+            slip,
+            brake_req: Percentage::from_fractional(brake),
+        })
+    }
+
+    fn assert_near(actual: Percentage, expected: f32) {
+        let actual = actual.to_fractional();
+        assert!(
+            (actual - expected).abs() < 1e-3,
+            "{} != {}",
+            actual,
+            expected
+        );
+    }
+
+    #[test]
+    fn full_regen_without_slip() {
+        let resp = run(1.0, slip(0.0));
+        assert_near(resp.regen, 0.5);
+        assert!(!resp.slip_limited);
+
+        // below the start point the friction brakes do it all
+        let resp = run(0.05, slip(0.0));
+        assert_near(resp.regen, 0.0);
+    }
+
+    #[test]
+    fn slip_backs_regen_off() {
+        let subsystem = RegenSubsystem::new(RegenConfig::default());
+        assert_eq!(subsystem.slip_scale(Percentage::from_fractional(0.1)), 1.0);
+        assert!((subsystem.slip_scale(Percentage::from_fractional(0.15)) - 0.5).abs() < 1e-3);
+        assert_eq!(subsystem.slip_scale(Percentage::from_fractional(0.2)), 0.0);
+        assert_eq!(subsystem.slip_scale(Percentage::from_fractional(0.5)), 0.0);
+
+        // half way from the slip limit to cut off
+        let resp = run(1.0, slip(0.15));
+        assert_near(resp.regen, 0.25);
+        assert!(resp.slip_limited);
+
+        let resp = run(1.0, slip(0.5));
+        assert_near(resp.regen, 0.0);
+    }
+
+    #[test]
+    fn missing_speed_drops_regen() {
+        let resp = run(1.0, SlipEstimate::none());
+        assert_near(resp.regen, 0.0);
+        assert!(resp.slip_limited);
+    }
+
+    #[test]
+    fn estimator_feeds_regen() {
+        let mut estimator = SlipEstimator::new(SlipEstimatorConfig::default(), 26.0);
+        let mut estimate = |rear_ws: Option<u16>, front_ws: Option<u16>| {
+            estimator.run_algo(
+                Timestamp::from_micros(0),
+                front_ws.map(WheelSpeed::from),
+                rear_ws.map(WheelSpeed::from),
+                None,
+                false,
+            )
+        };
+
+        // rear at 85% of the front is 15% slip
+        let resp = run(1.0, estimate(Some(170), Some(200)));
+        assert_near(resp.regen, 0.25);
+        let resp = run(1.0, estimate(None, Some(200)));
+        assert_near(resp.regen, 0.0);
+        let resp = run(1.0, estimate(Some(200), None));
+        assert_near(resp.regen, 0.0);
+    }
+}
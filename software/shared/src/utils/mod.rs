@@ -9,3 +9,6 @@ pub mod parts;
 
 #[path = "./time.rs"]
 pub mod time;
+
+#[path = "./torque.rs"]
+pub mod torque;
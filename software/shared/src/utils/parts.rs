@@ -1,3 +1,5 @@
+use crate::messages::error::DecodeError;
+
 #[derive(Debug, Clone, Copy)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub enum Wheel {
@@ -13,12 +15,17 @@ impl Into<u8> for Wheel {
         }
     }
 }
-impl From<u8> for Wheel {
-    fn from(value: u8) -> Self {
-        if value == 1 {
-            Wheel::Front
-        } else {
-            Wheel::Rear
+impl TryFrom<u8> for Wheel {
+    type Error = DecodeError;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(Wheel::Rear),
+            1 => Ok(Wheel::Front),
+            _ => Err(DecodeError::InvalidValue {
+                field: "Wheel",
+                value,
+            }),
         }
     }
 }
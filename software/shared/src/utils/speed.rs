@@ -1,5 +1,7 @@
 use core::ops::{Add, Div, Mul, Sub};
 
+use crate::utils::time::Duration;
+
 #[derive(Debug, Clone, Copy, PartialEq, PartialOrd, Eq)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct WheelSpeed {
@@ -53,6 +55,48 @@ impl WheelSpeed {
     }
 }
 
+// Crank cadence in revolutions per minute
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd, Eq, Ord)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Cadence {
+    rpm: u16,
+}
+
+impl Cadence {
+    pub fn zero() -> Self {
+        Self { rpm: 0 }
+    }
+
+    pub fn from_rpm(rpm: u16) -> Self {
+        Self { rpm }
+    }
+
+    pub fn as_rpm(&self) -> u16 {
+        self.rpm
+    }
+
+    // Cadence from the number of crank sensor pulses counted over a window
+    pub fn from_pulses(pulses: u32, window: Duration, pulses_per_rev: u16) -> Self {
+        if window.as_millis() == 0 || pulses_per_rev == 0 {
+            return Self::zero();
+        }
+        let rpm = (pulses as u64 * 60_000) / (window.as_millis() * pulses_per_rev as u64);
+        Self {
+            rpm: rpm.min(u16::MAX as u64) as u16,
+        }
+    }
+
+    pub fn to_packets(&self) -> [u8; 2] {
+        [(self.rpm & 0xFF) as u8, (self.rpm >> 8) as u8]
+    }
+
+    pub fn from_packets(data: &[u8; 2]) -> Self {
+        Self {
+            rpm: (data[0] as u16) | ((data[1] as u16) << 8),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Copy)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct GroundSpeed {
@@ -71,4 +115,14 @@ impl GroundSpeed {
         let mph = rph * circumference_miles;
         Self { mph }
     }
+
+    pub fn from_kph(kph: f32) -> Self {
+        Self {
+            mph: kph / 1.609344,
+        }
+    }
+
+    pub fn kph(&self) -> f32 {
+        self.mph * 1.609344
+    }
 }
@@ -0,0 +1,32 @@
+// Pedal torque, sent over the wire in 0.1 Nm steps
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Torque {
+    nm: f32,
+}
+
+impl Torque {
+    pub fn zero() -> Self {
+        Self { nm: 0.0 }
+    }
+
+    pub fn from_nm(nm: f32) -> Self {
+        Self { nm }
+    }
+
+    pub fn as_nm(&self) -> f32 {
+        self.nm
+    }
+
+    pub fn to_packets(&self) -> [u8; 2] {
+        let raw = (self.nm * 10.0).clamp(0.0, u16::MAX as f32) as u16;
+        [(raw & 0xFF) as u8, (raw >> 8) as u8]
+    }
+
+    pub fn from_packets(data: &[u8; 2]) -> Self {
+        let raw = (data[0] as u16) | ((data[1] as u16) << 8);
+        Self {
+            nm: raw as f32 / 10.0,
+        }
+    }
+}